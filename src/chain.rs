@@ -0,0 +1,268 @@
+use crate::matrix::Matrix;
+use crate::operation::Operator;
+use std::fmt::{Display, Formatter, Result};
+
+/// The ways building an OperationChain can fail
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ChainError {
+    /// An operator was added where a matrix was expected
+    ExpectedMatrix,
+    /// A matrix was added where an operator was expected
+    ExpectedOperator,
+    /// The chain was built empty or ending in an operator
+    Incomplete,
+    /// A matrix's shape does not work with the operator before it
+    Incompatible {
+        operator: Operator,
+        left: (usize, usize),
+        right: (usize, usize),
+    },
+}
+
+impl Display for ChainError {
+    /// Format ChainError for display
+    ///
+    /// # Arguments
+    /// * self - reference to this ChainError
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            ChainError::ExpectedMatrix => f.write_str("expected a matrix, found an operator"),
+            ChainError::ExpectedOperator => f.write_str("expected an operator, found a matrix"),
+            ChainError::Incomplete => f.write_str("chain must start and end with a matrix"),
+            ChainError::Incompatible {
+                operator,
+                left,
+                right,
+            } => write!(
+                f,
+                "cannot {:?} a {}x{} matrix and a {}x{} matrix",
+                operator, left.0, left.1, right.0, right.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+/// A sequence of matrices joined by operators, evaluated left to right
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationChain {
+    first: Matrix,
+    steps: Vec<(Operator, Matrix)>,
+}
+
+impl OperationChain {
+    /// Evaluate the chain from left to right; there is no operator precedence,
+    /// so `A + B * C` is `(A + B) * C`
+    ///
+    /// # Arguments
+    /// * self - reference to this OperationChain
+    ///
+    /// # Returns
+    /// The Matrix resulting from every step
+    pub fn evaluate(&self) -> Matrix {
+        self.steps
+            .iter()
+            .fold(self.first.clone(), |acc, (operator, matr)| {
+                operator.apply(&acc, matr)
+            })
+    }
+
+    /// Evaluate the chain from left to right, keeping the result of each step
+    ///
+    /// # Arguments
+    /// * self - reference to this OperationChain
+    ///
+    /// # Returns
+    /// The Matrix after each operator is applied, in order; the last one is
+    /// the result of `evaluate`
+    pub fn evaluate_all(&self) -> Vec<Matrix> {
+        let mut intermediates: Vec<Matrix> = Vec::with_capacity(self.steps.len());
+        for (operator, matr) in &self.steps {
+            let acc = intermediates.last().unwrap_or(&self.first);
+            intermediates.push(operator.apply(acc, matr));
+        }
+        intermediates
+    }
+}
+
+/// Builds an OperationChain one step at a time, checking each step as it is
+/// added so a built chain can always be evaluated
+#[derive(Debug, Default)]
+pub struct OperationChainBuilder {
+    first: Option<Matrix>,
+    steps: Vec<(Operator, Matrix)>,
+    pending: Option<Operator>,
+    shape: (usize, usize),
+}
+
+impl OperationChainBuilder {
+    /// Start an empty chain
+    ///
+    /// # Returns
+    /// A builder expecting a matrix first
+    pub fn new() -> OperationChainBuilder {
+        OperationChainBuilder::default()
+    }
+
+    /// Add a matrix, which must follow an operator unless it is the first
+    ///
+    /// # Arguments
+    /// * self - this builder
+    /// * matr - the matrix to add
+    ///
+    /// # Returns
+    /// The builder, or an error if a matrix doesn't belong here or its shape
+    /// doesn't work with the pending operator
+    pub fn matrix(mut self, matr: Matrix) -> std::result::Result<Self, ChainError> {
+        let shape = (matr.rows(), matr.cols());
+        if self.first.is_none() {
+            self.first = Some(matr);
+            self.shape = shape;
+            return Ok(self);
+        }
+
+        let operator = self.pending.take().ok_or(ChainError::ExpectedOperator)?;
+        self.shape = operator
+            .result_shape(self.shape, shape)
+            .ok_or(ChainError::Incompatible {
+                operator,
+                left: self.shape,
+                right: shape,
+            })?;
+        self.steps.push((operator, matr));
+        Ok(self)
+    }
+
+    /// Add an operator, which must follow a matrix
+    ///
+    /// # Arguments
+    /// * self - this builder
+    /// * operator - the operator to apply to the chain so far and the next matrix
+    ///
+    /// # Returns
+    /// The builder, or an error if an operator doesn't belong here
+    pub fn op(mut self, operator: Operator) -> std::result::Result<Self, ChainError> {
+        if self.first.is_none() || self.pending.is_some() {
+            return Err(ChainError::ExpectedMatrix);
+        }
+        self.pending = Some(operator);
+        Ok(self)
+    }
+
+    /// Finish the chain
+    ///
+    /// # Arguments
+    /// * self - this builder
+    ///
+    /// # Returns
+    /// The chain, or an error if it is empty or ends in an operator
+    pub fn build(self) -> std::result::Result<OperationChain, ChainError> {
+        match (self.first, self.pending) {
+            (Some(first), None) => Ok(OperationChain {
+                first,
+                steps: self.steps,
+            }),
+            _ => Err(ChainError::Incomplete),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test building and evaluating a three matrix chain
+    #[test]
+    fn test_build_and_evaluate() {
+        let a = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+        let b = Matrix::new(2, 2, vec![1, 1, 1, 1]);
+        let c = Matrix::new(1, 2, vec![1, 2]);
+
+        let chain = OperationChainBuilder::new()
+            .matrix(a.clone())
+            .and_then(|builder| builder.op(Operator::Add))
+            .and_then(|builder| builder.matrix(b.clone()))
+            .and_then(|builder| builder.op(Operator::Multiply))
+            .and_then(|builder| builder.matrix(c.clone()))
+            .and_then(OperationChainBuilder::build)
+            .unwrap();
+
+        assert_eq!(chain.evaluate(), a.add_mat(&b).mul_mat(&c));
+        assert_eq!(chain.evaluate(), Matrix::new(1, 2, vec![8, 14]));
+    }
+
+    /// Test a matrix with incompatible dimensions is rejected when added
+    #[test]
+    fn test_incompatible_dimensions() {
+        let builder = OperationChainBuilder::new()
+            .matrix(Matrix::new(2, 2, vec![1, 2, 3, 4]))
+            .and_then(|b| b.op(Operator::Add))
+            .unwrap();
+
+        assert_eq!(
+            builder.matrix(Matrix::new(1, 2, vec![1, 2])).unwrap_err(),
+            ChainError::Incompatible {
+                operator: Operator::Add,
+                left: (2, 2),
+                right: (2, 1),
+            }
+        );
+    }
+
+    /// Test matrices and operators must alternate
+    #[test]
+    fn test_alternation() {
+        let matr = Matrix::new(1, 1, vec![1]);
+
+        assert_eq!(
+            OperationChainBuilder::new().op(Operator::Add).unwrap_err(),
+            ChainError::ExpectedMatrix
+        );
+        assert_eq!(
+            OperationChainBuilder::new()
+                .matrix(matr.clone())
+                .and_then(|b| b.matrix(matr.clone()))
+                .unwrap_err(),
+            ChainError::ExpectedOperator
+        );
+        assert_eq!(
+            OperationChainBuilder::new()
+                .matrix(matr)
+                .and_then(|b| b.op(Operator::Add))
+                .and_then(OperationChainBuilder::build)
+                .unwrap_err(),
+            ChainError::Incomplete
+        );
+    }
+
+    /// Test every intermediate of a three operation chain is kept in order
+    #[test]
+    fn test_evaluate_all() {
+        let a = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+        let b = Matrix::new(2, 2, vec![1, 1, 1, 1]);
+        let c = Matrix::new(2, 2, vec![2, 0, 0, 2]);
+        let d = Matrix::new(1, 2, vec![1, -1]);
+
+        let chain = OperationChainBuilder::new()
+            .matrix(a.clone())
+            .and_then(|builder| builder.op(Operator::Subtract))
+            .and_then(|builder| builder.matrix(b.clone()))
+            .and_then(|builder| builder.op(Operator::Multiply))
+            .and_then(|builder| builder.matrix(c.clone()))
+            .and_then(|builder| builder.op(Operator::Multiply))
+            .and_then(|builder| builder.matrix(d.clone()))
+            .and_then(OperationChainBuilder::build)
+            .unwrap();
+
+        let first = a.sub_mat(&b);
+        let second = first.mul_mat(&c);
+        let third = second.mul_mat(&d);
+        assert_eq!(chain.evaluate_all(), vec![first, second, third.clone()]);
+        assert_eq!(chain.evaluate(), third);
+    }
+}
@@ -1,12 +1,79 @@
+use sample_code::matrix::Matrix;
+use sample_code::operation::Operator;
 use std::path::PathBuf;
 
+/// Description: Where the operator of an Operation assembled from separate files comes from
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperatorSource {
+    /// an operator given directly on the command line
+    Inline(Operator),
+    /// a file holding the operator, as json or as its symbol or name
+    File(PathBuf),
+}
+
+/// Description: Where one matrix of an Operation assembled from parts comes from
+#[derive(PartialEq, Eq)]
+pub enum MatrixSource {
+    /// a matrix given directly on the command line
+    Inline(Matrix),
+    /// a json file holding the matrix
+    File(PathBuf),
+}
+
+impl std::fmt::Debug for MatrixSource {
+    /// Description: Formats the source as its file, or the size of the inline matrix
+    /// Parameter self: reference to this MatrixSource
+    /// Parameter f: reference to a formatter stream
+    /// Return: The result of the write to the formatter stream
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MatrixSource::Inline(matr) => {
+                write!(f, "inline {}x{} matrix", matr.rows(), matr.cols())
+            }
+            MatrixSource::File(path) => write!(f, "{:?}", path),
+        }
+    }
+}
+
+/// Description: Which operand of an Operation to pick out
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OperandSide {
+    Left,
+    Right,
+}
+
+/// Description: Where the program gets its input from
+#[derive(Debug, PartialEq, Eq)]
+pub enum Input {
+    /// a json file holding an Operation
+    File(PathBuf),
+    /// a matrix given directly on the command line
+    Inline(Matrix),
+    /// a directory of json Operation files to validate without running them
+    ValidateDir(PathBuf),
+    /// a json Operation file to print one operand of, without evaluating it
+    Extract { file: PathBuf, side: OperandSide },
+    /// an Operation assembled from a matrix on each side and an operator
+    Parts {
+        left: MatrixSource,
+        operator: OperatorSource,
+        right: MatrixSource,
+    },
+}
+
 /// Author: Tyler Holinka
 /// Description: The "internal" representation of the command line arguments
 #[derive(PartialEq, Eq)]
 pub struct Arguments {
     pub debug: bool,
-    pub input: PathBuf,
+    pub input: Input,
     pub out: Option<PathBuf>,
+    pub parallel: bool,
+    pub threads: Option<usize>,
+    pub max_elements: Option<usize>,
+    pub precision: Option<usize>,
+    pub report: bool,
+    pub also_write: Vec<PathBuf>,
 }
 
 impl std::fmt::Debug for Arguments {
@@ -17,13 +84,49 @@ impl std::fmt::Debug for Arguments {
     /// Return: The result of the write to the formatter stream
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         writeln!(f, "Debug: {}", self.debug)?;
-        writeln!(f, "Input: {:?}", self.input)?;
+        match &self.input {
+            Input::File(path) => writeln!(f, "Input: {:?}", path)?,
+            Input::Inline(matr) => {
+                writeln!(f, "Input: inline {}x{} matrix", matr.rows(), matr.cols())?
+            }
+            Input::ValidateDir(dir) => writeln!(f, "Input: validate {:?}", dir)?,
+            Input::Extract { file, side } => {
+                writeln!(f, "Input: {:?} operand of {:?}", side, file)?
+            }
+            Input::Parts {
+                left,
+                operator,
+                right,
+            } => {
+                write!(f, "Input: {:?} ", left)?;
+                match operator {
+                    OperatorSource::Inline(op) => write!(f, "{:?}", op)?,
+                    OperatorSource::File(path) => write!(f, "operator from {:?}", path)?,
+                }
+                writeln!(f, " {:?}", right)?
+            }
+        }
+        writeln!(f, "Parallel: {}", self.parallel)?;
+        match self.threads {
+            Some(threads) => writeln!(f, "Threads: {}", threads)?,
+            None => writeln!(f, "Threads: default")?,
+        }
+        match self.max_elements {
+            Some(max_elements) => writeln!(f, "Max elements: {}", max_elements)?,
+            None => writeln!(f, "Max elements: none")?,
+        }
+        match self.precision {
+            Some(precision) => writeln!(f, "Precision: {}", precision)?,
+            None => writeln!(f, "Precision: default")?,
+        }
+        writeln!(f, "Report: {}", self.report)?;
+        writeln!(f, "Also write: {:?}", self.also_write)?;
 
-        if let Some(out) = self.out_as_ref() {
+        if let Some(out) = self.out.as_ref() {
             // don't output "Some(PathBuf)", instead output "PathBuf"
             writeln!(f, "Out: {:?}", out)
         } else {
-            f.write_str("Out: None")
+            writeln!(f, "Out: None")
         }
     }
 }
@@ -40,24 +143,39 @@ mod tests {
         // only input
         let args = Arguments {
             debug: false,
-            input: PathBuf::from_str("test-file.json").unwrap(),
+            input: Input::File(PathBuf::from_str("test-file.json").unwrap()),
             out: None,
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
         };
 
         let fmt = format!("{:?}", args);
-        assert_eq!(fmt, "Debug: false\nInput: \"test-file.json\"\nOut: None\n");
+        assert_eq!(
+            fmt,
+            "Debug: false\nInput: \"test-file.json\"\nParallel: true\nThreads: default\nMax elements: none\nPrecision: default\nReport: false\nAlso write: []\nOut: None\n"
+        );
 
         // debug + out as well
         let args = Arguments {
             debug: true,
-            input: PathBuf::from_str("test-file.json").unwrap(),
+            input: Input::File(PathBuf::from_str("test-file.json").unwrap()),
             out: Some(PathBuf::from_str("test-out.json").unwrap()),
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
         };
 
         let fmt = format!("{:?}", args);
         assert_eq!(
             fmt,
-            "Debug: true\nInput: \"test-file.json\"\nOut: \"test-out.json\"\n"
+            "Debug: true\nInput: \"test-file.json\"\nParallel: true\nThreads: default\nMax elements: none\nPrecision: default\nReport: false\nAlso write: []\nOut: \"test-out.json\"\n"
         );
     }
 
@@ -67,8 +185,14 @@ mod tests {
     fn test_cmd_partialeq() {
         let args = Arguments {
             debug: true,
-            input: PathBuf::from_str("test-file.json").unwrap(),
+            input: Input::File(PathBuf::from_str("test-file.json").unwrap()),
             out: Some(PathBuf::from_str("test-out.json").unwrap()),
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
         };
 
         // equal
@@ -76,8 +200,14 @@ mod tests {
             args,
             Arguments {
                 debug: true,
-                input: PathBuf::from_str("test-file.json").unwrap(),
+                input: Input::File(PathBuf::from_str("test-file.json").unwrap()),
                 out: Some(PathBuf::from_str("test-out.json").unwrap()),
+                parallel: true,
+                threads: None,
+                max_elements: None,
+                precision: None,
+                report: false,
+                also_write: Vec::new(),
             }
         );
 
@@ -86,8 +216,14 @@ mod tests {
             args,
             Arguments {
                 debug: false,
-                input: PathBuf::from_str("test-file.json").unwrap(),
+                input: Input::File(PathBuf::from_str("test-file.json").unwrap()),
                 out: Some(PathBuf::from_str("test-out.json").unwrap()),
+                parallel: true,
+                threads: None,
+                max_elements: None,
+                precision: None,
+                report: false,
+                also_write: Vec::new(),
             }
         );
 
@@ -96,8 +232,14 @@ mod tests {
             args,
             Arguments {
                 debug: true,
-                input: PathBuf::from_str("different-test-file.json").unwrap(),
+                input: Input::File(PathBuf::from_str("different-test-file.json").unwrap()),
                 out: Some(PathBuf::from_str("test-out.json").unwrap()),
+                parallel: true,
+                threads: None,
+                max_elements: None,
+                precision: None,
+                report: false,
+                also_write: Vec::new(),
             }
         );
 
@@ -106,9 +248,101 @@ mod tests {
             args,
             Arguments {
                 debug: true,
-                input: PathBuf::from_str("test-file.json").unwrap(),
+                input: Input::File(PathBuf::from_str("test-file.json").unwrap()),
                 out: Some(PathBuf::from_str("different-test-out.json").unwrap()),
+                parallel: true,
+                threads: None,
+                max_elements: None,
+                precision: None,
+                report: false,
+                also_write: Vec::new(),
             }
         )
     }
+
+    /// Description: Tests the fmt::Debug output for an inline matrix
+    #[test]
+    fn test_fmt_debug_inline() {
+        let args = Arguments {
+            debug: false,
+            input: Input::Inline(Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6])),
+            out: None,
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
+        };
+
+        let fmt = format!("{:?}", args);
+        assert_eq!(
+            fmt,
+            "Debug: false\nInput: inline 2x3 matrix\nParallel: true\nThreads: default\nMax elements: none\nPrecision: default\nReport: false\nAlso write: []\nOut: None\n"
+        );
+    }
+
+    /// Description: Tests the fmt::Debug output for an Operation assembled from parts
+    #[test]
+    fn test_fmt_debug_parts() {
+        let mut args = Arguments {
+            debug: false,
+            input: Input::Parts {
+                left: MatrixSource::File(PathBuf::from("a.json")),
+                operator: OperatorSource::Inline(Operator::Add),
+                right: MatrixSource::File(PathBuf::from("b.json")),
+            },
+            out: None,
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
+        };
+
+        let fmt = format!("{:?}", args);
+        assert_eq!(
+            fmt,
+            "Debug: false\nInput: \"a.json\" Add \"b.json\"\nParallel: true\nThreads: default\nMax elements: none\nPrecision: default\nReport: false\nAlso write: []\nOut: None\n"
+        );
+
+        args.input = Input::Parts {
+            left: MatrixSource::File(PathBuf::from("a.json")),
+            operator: OperatorSource::File(PathBuf::from("op.json")),
+            right: MatrixSource::File(PathBuf::from("b.json")),
+        };
+        let fmt = format!("{:?}", args);
+        assert!(fmt.contains("Input: \"a.json\" operator from \"op.json\" \"b.json\"\n"));
+
+        args.input = Input::Parts {
+            left: MatrixSource::Inline(Matrix::new(2, 1, vec![1, 2])),
+            operator: OperatorSource::Inline(Operator::Add),
+            right: MatrixSource::File(PathBuf::from("b.json")),
+        };
+        let fmt = format!("{:?}", args);
+        assert!(fmt.contains("Input: inline 1x2 matrix Add \"b.json\"\n"));
+    }
+
+    /// Description: Tests the fmt::Debug output when extracting an operand
+    #[test]
+    fn test_fmt_debug_extract() {
+        let args = Arguments {
+            debug: false,
+            input: Input::Extract {
+                file: PathBuf::from("in.json"),
+                side: OperandSide::Right,
+            },
+            out: None,
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
+        };
+
+        let fmt = format!("{:?}", args);
+        assert!(fmt.contains("Input: Right operand of \"in.json\"\n"));
+    }
 }
@@ -1,6 +1,11 @@
 use arguments::Arguments;
+pub use arguments::{Input, MatrixSource, OperandSide, OperatorSource};
+use sample_code::matrix::Matrix;
+use sample_code::operation::Operator;
+use sample_code::runner::parse_threads;
 use std::path::PathBuf;
 use std::str::FromStr;
+use structopt::clap::ArgGroup;
 use structopt::StructOpt;
 
 mod arguments;
@@ -11,7 +16,9 @@ mod arguments;
 #[structopt(
     name = "Linear Algebra",
     about = "Sample Linear Algebra Operations.",
-    no_version
+    no_version,
+    group = ArgGroup::with_name("input").required(true),
+    group = ArgGroup::with_name("operator")
 )]
 pub struct Opt {
     // note: triple slash is used by StructOpt as the "description" by default
@@ -20,12 +27,102 @@ pub struct Opt {
     debug: bool,
 
     /// input json file
-    #[structopt(parse(from_str), short, long, required = true)]
-    in_file: PathBuf,
+    #[structopt(parse(from_str), short, long, group = "input")]
+    in_file: Option<PathBuf>,
+
+    /// a matrix to print instead of an input file, as RxC:elements, e.g. 2x2:1,2,3,4;
+    /// --left and --right take a matrix in the same form to use it as an operand
+    #[structopt(long, parse(try_from_str = Matrix::from_compact), group = "input")]
+    inline_matrix: Option<Matrix>,
+
+    /// the left matrix instead of an input file, as a json file or as RxC:elements, e.g. 2x2:1,2,3,4;
+    /// needs --right and --op or --op-file
+    #[structopt(long, parse(try_from_str = parse_matrix_source), group = "input", requires_all = &["right", "operator"])]
+    left: Option<MatrixSource>,
+
+    /// the operator to apply to --left and --right, as a symbol or name, e.g. + or multiply
+    #[structopt(long, parse(try_from_str = Operator::from_str), group = "operator", requires = "left")]
+    op: Option<Operator>,
+
+    /// file holding the operator to apply to --left and --right, as json or as a symbol or name
+    #[structopt(parse(from_str), long, group = "operator", requires = "left")]
+    op_file: Option<PathBuf>,
+
+    /// the right matrix, as a json file or as RxC:elements
+    #[structopt(long, parse(try_from_str = parse_matrix_source), requires = "left")]
+    right: Option<MatrixSource>,
+
+    /// print the left or right operand of the input file instead of evaluating it
+    #[structopt(long, parse(try_from_str = parse_side), requires = "in-file",
+                conflicts_with_all = &["out-file", "report", "also-write"])]
+    extract: Option<OperandSide>,
+
+    /// check every .json Operation file in a directory can be evaluated, without running them
+    #[structopt(parse(from_str), long, group = "input")]
+    validate_dir: Option<PathBuf>,
+
+    /// multiply on a single thread
+    #[structopt(long)]
+    no_parallel: bool,
+
+    /// the number of threads to multiply on, instead of the MATRIX_THREADS environment
+    /// variable or one per core
+    #[structopt(long, parse(try_from_str = parse_threads), conflicts_with = "no-parallel")]
+    threads: Option<usize>,
+
+    /// reject any matrix read from a file with more than this many elements
+    #[structopt(long)]
+    max_elements: Option<usize>,
+
+    /// the number of digits to print after the decimal point of floating point results
+    #[structopt(long)]
+    precision: Option<usize>,
+
+    /// write timing and shape metadata of an Operation to a .report.json file beside the output,
+    /// or the input if there is no output file
+    #[structopt(long, conflicts_with_all = &["inline-matrix", "validate-dir"])]
+    report: bool,
 
     /// output json file
-    #[structopt(parse(try_from_str), short, long, required = false, default_value = "")]
-    out_file: String,
+    // a default value would count as given, and clash with the inputs that
+    // can't be written out
+    #[structopt(parse(from_str), short, long, conflicts_with_all = &["inline-matrix", "validate-dir"])]
+    out_file: Option<PathBuf>,
+
+    /// another file to write the evaluated operation to, in the format its extension picks;
+    /// can be given more than once
+    #[structopt(parse(from_str), long, number_of_values = 1, conflicts_with_all = &["inline-matrix", "validate-dir"])]
+    also_write: Vec<PathBuf>,
+}
+
+/// Description: Parses a matrix given to --left or --right: inline if it looks like RxC:elements,
+///              otherwise the json file holding it
+/// Argument s: the matrix or file, as given by the user
+/// Return: where the matrix comes from, or why an inline matrix isn't valid
+fn parse_matrix_source(s: &str) -> Result<MatrixSource, String> {
+    let is_dim = |dim: &str| !dim.is_empty() && dim.chars().all(|c| c.is_ascii_digit());
+    let inline = s
+        .split_once(':')
+        .and_then(|(shape, _)| shape.split_once('x'))
+        .is_some_and(|(rows, cols)| is_dim(rows) && is_dim(cols));
+    if inline {
+        Matrix::from_compact(s)
+            .map(MatrixSource::Inline)
+            .map_err(|e| e.to_string())
+    } else {
+        Ok(MatrixSource::File(PathBuf::from(s)))
+    }
+}
+
+/// Description: Parses which operand to extract
+/// Argument s: "left" or "right", in any case
+/// Return: the side, or why it isn't valid
+pub fn parse_side(s: &str) -> Result<OperandSide, String> {
+    match s.trim().to_lowercase().as_str() {
+        "left" => Ok(OperandSide::Left),
+        "right" => Ok(OperandSide::Right),
+        _ => Err(format!("expected left or right, found {:?}", s)),
+    }
 }
 
 /// Author: Tyler Holinka
@@ -33,16 +130,43 @@ pub struct Opt {
 /// Argument opt: The Opt provided by StructOpt
 /// Return: Our internal representation of arguments
 fn process(opt: Opt) -> Arguments {
-    // convert the outfile to an Option
-    let out: Option<PathBuf> = match opt.out_file.as_ref() {
-        "" => None,
-        _ => PathBuf::from_str(&opt.out_file).ok(), 
+    // StructOpt makes sure exactly one of these is given, along with whatever
+    // else it needs
+    let input = match (opt.inline_matrix, opt.in_file, opt.validate_dir, opt.left) {
+        (Some(matr), _, _, _) => Input::Inline(matr),
+        (None, Some(file), _, _) => match opt.extract {
+            Some(side) => Input::Extract { file, side },
+            None => Input::File(file),
+        },
+        (None, None, Some(dir), _) => Input::ValidateDir(dir),
+        (None, None, None, Some(left)) => {
+            let operator = match (opt.op, opt.op_file) {
+                (Some(op), _) => OperatorSource::Inline(op),
+                (None, Some(file)) => OperatorSource::File(file),
+                (None, None) => unreachable!("--op or --op-file is required with --left"),
+            };
+            let right = opt.right.expect("--right is required with --left");
+            Input::Parts {
+                left,
+                operator,
+                right,
+            }
+        }
+        (None, None, None, None) => {
+            unreachable!("an input file, inline matrix, directory or --left is required")
+        }
     };
 
     Arguments {
         debug: opt.debug,
-        input: opt.in_file,
-        out,
+        input,
+        out: opt.out_file,
+        parallel: !opt.no_parallel,
+        threads: opt.threads,
+        max_elements: opt.max_elements,
+        precision: opt.precision,
+        report: opt.report,
+        also_write: opt.also_write,
     }
 }
 
@@ -62,11 +186,20 @@ mod tests {
     /// Description: test only having no input file on the command line
     #[test]
     fn opt_test_no_input() {
+        let usage = Opt::clap()
+            .get_matches_from_safe(["test"])
+            .unwrap_err()
+            .message;
+        assert!(
+            usage.contains("<--in-file <in-file>|--inline-matrix <inline-matrix>|--left <left>"),
+            "{}",
+            usage
+        );
+
         let opt = Opt::from_iter_safe(&["test"]);
 
-        match opt {
-            Ok(_) => panic!("no input file should panic, not succeed"),
-            Err(_) => (),
+        if opt.is_ok() {
+            panic!("no input file should panic, not succeed")
         }
     }
 
@@ -77,8 +210,14 @@ mod tests {
         let file = "test-input.json";
         let expected = Arguments {
             debug: false,
-            input: PathBuf::from_str(file).unwrap(),
+            input: Input::File(PathBuf::from_str(file).unwrap()),
             out: None,
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
         };
 
         let opt = Opt::from_iter(&["test", "-i", file]);
@@ -96,8 +235,14 @@ mod tests {
         let out = "test-out.json";
         let expected = Arguments {
             debug: false,
-            input: PathBuf::from_str(input).unwrap(),
+            input: Input::File(PathBuf::from_str(input).unwrap()),
             out: PathBuf::from_str(out).ok(),
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
         };
 
         let opt = Opt::from_iter(&["test", "-i", input, "-o", out]);
@@ -115,8 +260,14 @@ mod tests {
 
         let expected = Arguments {
             debug: true,
-            input: PathBuf::from_str(input).unwrap(),
+            input: Input::File(PathBuf::from_str(input).unwrap()),
             out: None,
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
         };
 
         let opt = Opt::from_iter(&["test", "-i", input, "-d"]);
@@ -125,4 +276,292 @@ mod tests {
 
         assert_eq!(args, expected)
     }
+
+    /// Description: test turning off parallel multiplication
+    #[test]
+    fn process_test_no_parallel() {
+        let input = "test-input.json";
+
+        let expected = Arguments {
+            debug: false,
+            input: Input::File(PathBuf::from_str(input).unwrap()),
+            out: None,
+            parallel: false,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
+        };
+
+        let opt = Opt::from_iter(&["test", "-i", input, "--no-parallel"]);
+
+        let args = process(opt);
+
+        assert_eq!(args, expected)
+    }
+
+    /// Description: test giving a matrix inline instead of an input file
+    #[test]
+    fn process_test_inline_matrix() {
+        let expected = Arguments {
+            debug: false,
+            input: Input::Inline(Matrix::new(2, 2, vec![1, 2, 3, 4])),
+            out: None,
+            parallel: true,
+            threads: None,
+            max_elements: None,
+            precision: None,
+            report: false,
+            also_write: Vec::new(),
+        };
+
+        let opt = Opt::from_iter(&["test", "--inline-matrix", "2x2:1,2,3,4"]);
+
+        let args = process(opt);
+
+        assert_eq!(args, expected)
+    }
+
+    /// Description: test an inline matrix with the wrong number of elements, an
+    ///              empty element, or alongside an input file, is rejected
+    #[test]
+    fn opt_test_inline_matrix_invalid() {
+        let opt = Opt::from_iter_safe(&["test", "--inline-matrix", "2x2:1,2,3"]);
+        let err = opt.unwrap_err();
+        assert!(err.message.contains("expected 4 elements, found 3"));
+
+        let opt = Opt::from_iter_safe(&["test", "--inline-matrix", "2x2:1,2,,4"]);
+        let err = opt.unwrap_err();
+        assert!(err
+            .message
+            .contains("\"\": cannot parse integer from empty string"));
+
+        let opt =
+            Opt::from_iter_safe(&["test", "--inline-matrix", "1x1:1", "-i", "test-input.json"]);
+        assert!(opt.is_err());
+
+        let opt = Opt::from_iter_safe(&["test", "--inline-matrix", "1x1:1", "-o", "test-out.json"]);
+        assert!(opt.is_err());
+    }
+
+    /// Description: test asking for a report
+    #[test]
+    fn process_test_report() {
+        let input = "test-input.json";
+
+        let opt = Opt::from_iter(&["test", "-i", input, "--report"]);
+
+        let args = process(opt);
+
+        assert!(args.report);
+        assert!(Opt::from_iter_safe(&["test", "--inline-matrix", "1x1:1", "--report"]).is_err());
+    }
+
+    /// Description: test assembling an Operation from separate files
+    #[test]
+    fn process_test_parts() {
+        let opt = Opt::from_iter(&["test", "--left", "a.json", "--op", "*", "--right", "b.json"]);
+
+        let args = process(opt);
+
+        assert_eq!(
+            args.input,
+            Input::Parts {
+                left: MatrixSource::File(PathBuf::from("a.json")),
+                operator: OperatorSource::Inline(Operator::Multiply),
+                right: MatrixSource::File(PathBuf::from("b.json")),
+            }
+        );
+
+        let opt = Opt::from_iter(&[
+            "test",
+            "--left",
+            "a.json",
+            "--op-file",
+            "op.json",
+            "--right",
+            "b.json",
+            "-o",
+            "out.json",
+        ]);
+
+        let args = process(opt);
+
+        assert_eq!(
+            args.input,
+            Input::Parts {
+                left: MatrixSource::File(PathBuf::from("a.json")),
+                operator: OperatorSource::File(PathBuf::from("op.json")),
+                right: MatrixSource::File(PathBuf::from("b.json")),
+            }
+        );
+        assert_eq!(args.out, Some(PathBuf::from("out.json")));
+
+        let opt = Opt::from_iter(&[
+            "test", "--left", "1x2:1,2", "--op", "+", "--right", "b.json",
+        ]);
+        assert_eq!(
+            process(opt).input,
+            Input::Parts {
+                left: MatrixSource::Inline(Matrix::new(2, 1, vec![1, 2])),
+                operator: OperatorSource::Inline(Operator::Add),
+                right: MatrixSource::File(PathBuf::from("b.json")),
+            }
+        );
+        // a malformed inline matrix isn't taken for a file name
+        assert!(Opt::from_iter_safe(&[
+            "test", "--left", "1x2:1", "--op", "+", "--right", "1x2:1,2"
+        ])
+        .is_err());
+    }
+
+    /// Description: test an incomplete or conflicting set of parts is rejected
+    #[test]
+    fn opt_test_parts_invalid() {
+        let rejected = |args: &[&str]| Opt::from_iter_safe(args).is_err();
+
+        assert!(rejected(&["test", "--left", "a.json", "--op", "+"]));
+        assert!(rejected(&["test", "--left", "a.json", "--right", "b.json"]));
+        assert!(rejected(&["test", "--op", "+", "--right", "b.json"]));
+        assert!(rejected(&[
+            "test", "--left", "a.json", "--op", "%", "--right", "b.json"
+        ]));
+        assert!(rejected(&[
+            "test",
+            "--left",
+            "a.json",
+            "--op",
+            "+",
+            "--op-file",
+            "op.json",
+            "--right",
+            "b.json"
+        ]));
+        assert!(rejected(&[
+            "test", "-i", "in.json", "--left", "a.json", "--op", "+", "--right", "b.json"
+        ]));
+    }
+
+    /// Description: test validating a directory, which can't be combined with other inputs
+    #[test]
+    fn process_test_validate_dir() {
+        let opt = Opt::from_iter(&["test", "--validate-dir", "data"]);
+
+        let args = process(opt);
+
+        assert_eq!(args.input, Input::ValidateDir(PathBuf::from("data")));
+        assert!(Opt::from_iter_safe(&["test", "--validate-dir", "data", "-i", "in.json"]).is_err());
+        assert!(Opt::from_iter_safe(&["test", "--validate-dir", "data", "--report"]).is_err());
+    }
+
+    /// Description: test setting the number of threads, which can't be zero or
+    ///              combined with --no-parallel
+    #[test]
+    fn process_test_threads() {
+        let opt = Opt::from_iter(&["test", "-i", "test-input.json", "--threads", "3"]);
+
+        let args = process(opt);
+
+        assert_eq!(args.threads, Some(3));
+        assert!(args.parallel);
+
+        let rejected = |args: &[&str]| Opt::from_iter_safe(args).is_err();
+        assert!(rejected(&["test", "-i", "in.json", "--threads", "0"]));
+        assert!(rejected(&["test", "-i", "in.json", "--threads", "many"]));
+        assert!(rejected(&[
+            "test",
+            "-i",
+            "in.json",
+            "--threads",
+            "2",
+            "--no-parallel"
+        ]));
+    }
+
+    /// Description: test limiting the size of the matrices read
+    #[test]
+    fn process_test_max_elements() {
+        let opt = Opt::from_iter(&["test", "-i", "test-input.json", "--max-elements", "100"]);
+
+        let args = process(opt);
+
+        assert_eq!(args.max_elements, Some(100));
+        assert!(Opt::from_iter_safe(&["test", "-i", "in.json", "--max-elements", "-1"]).is_err());
+        assert!(Opt::from_iter_safe(&["test", "-i", "in.json", "--max-elements", "x"]).is_err());
+    }
+
+    /// Description: test giving several extra outputs
+    #[test]
+    fn process_test_also_write() {
+        let opt = Opt::from_iter(&[
+            "test",
+            "-i",
+            "test-input.json",
+            "--also-write",
+            "out.csv",
+            "--also-write",
+            "out.txt",
+        ]);
+
+        let args = process(opt);
+
+        assert_eq!(
+            args.also_write,
+            vec![PathBuf::from("out.csv"), PathBuf::from("out.txt")]
+        );
+        assert!(Opt::from_iter_safe(&[
+            "test",
+            "--inline-matrix",
+            "1x1:1",
+            "--also-write",
+            "a.csv"
+        ])
+        .is_err());
+    }
+
+    /// Description: test extracting an operand, which needs an input file and nothing to write
+    #[test]
+    fn process_test_extract() {
+        let opt = Opt::from_iter(&["test", "-i", "in.json", "--extract", "Left"]);
+
+        let args = process(opt);
+
+        assert_eq!(
+            args.input,
+            Input::Extract {
+                file: PathBuf::from("in.json"),
+                side: OperandSide::Left,
+            }
+        );
+        let rejected = |args: &[&str]| Opt::from_iter_safe(args).is_err();
+        assert!(rejected(&["test", "-i", "in.json", "--extract", "middle"]));
+        assert!(rejected(&[
+            "test",
+            "-i",
+            "in.json",
+            "--extract",
+            "left",
+            "-o",
+            "out.json"
+        ]));
+        assert!(rejected(&[
+            "test",
+            "--inline-matrix",
+            "1x1:1",
+            "--extract",
+            "left"
+        ]));
+    }
+
+    /// Description: test setting how many decimal places floating point results are printed with
+    #[test]
+    fn process_test_precision() {
+        let opt = Opt::from_iter(&["test", "-i", "test-input.json", "--precision", "2"]);
+
+        let args = process(opt);
+
+        assert_eq!(args.precision, Some(2));
+        assert!(Opt::from_iter_safe(&["test", "-i", "in.json", "--precision", "-1"]).is_err());
+    }
 }
@@ -0,0 +1,235 @@
+use crate::error::{ComputationError, MatrixError};
+use crate::operation::{FloatOperation, Normalization, Operation, UnaryOperation, WideOperation};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter, Result};
+
+/// Anything an input file can hold, told apart by its keys and data, so it
+/// can be read and evaluated without knowing ahead of time which it is
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Computation {
+    Operation(Operation),
+    Normalization(Normalization),
+    Unary(UnaryOperation),
+    Float(FloatOperation),
+    Wide(WideOperation),
+}
+
+impl Computation {
+    /// Read a Computation from JSON: a Normalization if it has a `normalize`
+    /// key, a UnaryOperation if it has an `operand` key, and otherwise an
+    /// Operation, done in floating point if any of its numbers has a fraction,
+    /// or on i64s if any whole number doesn't fit in an i32
+    ///
+    /// # Arguments
+    /// * s - the JSON text
+    ///
+    /// # Returns
+    /// The Computation, or the reason it couldn't be read
+    pub fn from_json_str(s: &str) -> std::result::Result<Computation, MatrixError> {
+        // only the keys are looked at, so nothing is built twice
+        #[derive(Deserialize)]
+        struct Peek {
+            normalize: Option<IgnoredAny>,
+            operand: Option<IgnoredAny>,
+        }
+        let peek: Peek = serde_json::from_str(s).map_err(|e| MatrixError::Format(e.to_string()))?;
+        let parsed = if peek.normalize.is_some() {
+            serde_json::from_str(s).map(Computation::Normalization)
+        } else if peek.operand.is_some() {
+            serde_json::from_str(s).map(Computation::Unary)
+        } else if serde_json::from_str(s).is_ok_and(|value| has_fraction(&value)) {
+            serde_json::from_str(s).map(Computation::Float)
+        } else if serde_json::from_str(s).is_ok_and(|value| has_wide_integer(&value)) {
+            serde_json::from_str(s).map(Computation::Wide)
+        } else {
+            serde_json::from_str(s).map(Computation::Operation)
+        };
+        parsed.map_err(|e| MatrixError::Format(e.to_string()))
+    }
+
+    /// Evaluate this Computation and store the result, so it is displayed and
+    /// serialized with it
+    ///
+    /// # Arguments
+    /// * self - mutable reference to this Computation
+    /// * threads - how many threads multiplication may use; 1 keeps the work
+    ///   on the current thread
+    ///
+    /// # Returns
+    /// Nothing, or the error of the step that failed
+    pub fn run_with_threads(
+        &mut self,
+        threads: usize,
+    ) -> std::result::Result<(), ComputationError> {
+        match self {
+            Computation::Operation(op) => op.try_do_operation_and_store_with_threads(threads)?,
+            Computation::Normalization(norm) => {
+                norm.try_normalize_and_store_with_threads(threads)?
+            }
+            Computation::Unary(op) => op.try_do_operation_and_store_with_threads(threads)?,
+            Computation::Float(op) => op.try_do_operation_and_store()?,
+            Computation::Wide(op) => op.try_do_operation_and_store_with_threads(threads)?,
+        }
+        Ok(())
+    }
+}
+
+impl Display for Computation {
+    /// Format whichever Computation this is for display, passing on the
+    /// formatter's precision
+    ///
+    /// # Arguments
+    /// * self - reference to this Computation
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            Computation::Operation(op) => op.fmt(f),
+            Computation::Normalization(norm) => norm.fmt(f),
+            Computation::Unary(op) => op.fmt(f),
+            Computation::Float(op) => op.fmt(f),
+            Computation::Wide(op) => op.fmt(f),
+        }
+    }
+}
+
+/// Read a Computation from JSON and evaluate it, the way the binary does for
+/// an input file
+///
+/// # Arguments
+/// * s - the JSON text
+/// * threads - how many threads multiplication may use; 1 keeps the work on
+///   the current thread
+///
+/// # Returns
+/// The Computation with its result stored, or the reason it couldn't be read
+/// or evaluated
+///
+/// ```
+/// let json = r#"{
+///     "left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+///     "operator": "Add",
+///     "right_operand": {"rows": 1, "cols": 2, "data": [3, 4]}
+/// }"#;
+/// let computation = sample_code::run_json(json, 1).unwrap();
+/// assert!(computation.to_string().ends_with("Equals\n\n     4      6 \n"));
+/// ```
+pub fn run_json(s: &str, threads: usize) -> std::result::Result<Computation, ComputationError> {
+    let mut computation = Computation::from_json_str(s)?;
+    computation.run_with_threads(threads)?;
+    Ok(computation)
+}
+
+/// Checks whether any number in some JSON has a fraction or exponent
+///
+/// # Arguments
+/// * value - the JSON to check
+///
+/// # Returns
+/// true if a number can only be read as floating point
+fn has_fraction(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Number(num) => num.is_f64(),
+        serde_json::Value::Array(items) => items.iter().any(has_fraction),
+        serde_json::Value::Object(fields) => fields.values().any(has_fraction),
+        _ => false,
+    }
+}
+
+/// Checks whether any whole number in some JSON doesn't fit in an i32
+///
+/// # Arguments
+/// * value - the JSON to check
+///
+/// # Returns
+/// true if a number can only be read as an i64 or wider
+fn has_wide_integer(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Number(num) => {
+            !num.is_f64() && num.as_i64().is_none_or(|num| i32::try_from(num).is_err())
+        }
+        serde_json::Value::Array(items) => items.iter().any(has_wide_integer),
+        serde_json::Value::Object(fields) => fields.values().any(has_wide_integer),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix;
+
+    /// Test each kind of input is told apart by its keys and data
+    #[test]
+    fn test_from_json_str() {
+        let operation = r#"{
+            "left_operand": {"rows": 1, "cols": 1, "data": [2]},
+            "operator": "Multiply",
+            "right_operand": {"rows": 1, "cols": 1, "data": [3]}
+        }"#;
+        assert!(matches!(
+            Computation::from_json_str(operation),
+            Ok(Computation::Operation(_))
+        ));
+        assert!(matches!(
+            Computation::from_json_str(&operation.replace("[3]", "[3.5]")),
+            Ok(Computation::Float(_))
+        ));
+        assert!(matches!(
+            Computation::from_json_str(
+                r#"{"operand": {"rows": 1, "cols": 1, "data": [2]}, "operator": "Transpose"}"#
+            ),
+            Ok(Computation::Unary(_))
+        ));
+        assert!(matches!(
+            Computation::from_json_str(
+                r#"{"normalize": {"rows": 1, "cols": 1, "data": [2]}, "by": "One"}"#
+            ),
+            Ok(Computation::Normalization(_))
+        ));
+        assert!(matches!(
+            Computation::from_json_str(&operation.replace("[3]", "[3000000000]")),
+            Ok(Computation::Wide(_))
+        ));
+        assert!(matches!(
+            Computation::from_json_str("[1, 2]"),
+            Err(MatrixError::Format(_))
+        ));
+    }
+
+    /// Test running JSON stores the result, or gives the step that failed
+    #[test]
+    fn test_run_json() {
+        let computation = run_json(
+            r#"{
+                "left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+                "operator": "Subtract",
+                "right_operand": {"rows": 1, "cols": 2, "data": [0.5, 0.5]}
+            }"#,
+            1,
+        )
+        .unwrap();
+        match computation {
+            Computation::Float(op) => {
+                assert_eq!(op.result(), Some(&Matrix::new(2, 1, vec![0.5, 1.5])))
+            }
+            other => panic!("expected a FloatOperation, found {:?}", other),
+        }
+
+        let err = run_json(
+            r#"{"operand": {"rows": 1, "cols": 2, "data": [1, 2]}, "operator": "Inverse"}"#,
+            1,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ComputationError::Unary(_)));
+        assert!(matches!(
+            run_json("{", 1),
+            Err(ComputationError::Input(MatrixError::Format(_)))
+        ));
+    }
+}
@@ -0,0 +1,375 @@
+use crate::operation::{Operator, UnaryOperator};
+use std::fmt::{Display, Formatter, Result};
+
+/// The ways a Matrix operation can fail instead of panicking
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum MatrixError {
+    /// A matrix did not have the (rows, cols) shape the operation needed
+    DimensionMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// A line of text input had a different number of elements than the first
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// Some input could not be parsed as a matrix element
+    Parse { line: usize, message: String },
+    /// The input could not be read
+    Io(String),
+    /// A divisor element was zero
+    DivisionByZero { row: usize, col: usize },
+    /// A matrix could not be converted to or from a serialized format
+    Format(String),
+    /// The number of elements in a (rows, cols) shape doesn't fit in a usize
+    DimensionOverflow { rows: usize, cols: usize },
+    /// A matrix was given a different number of elements than its shape holds
+    DataLength { expected: usize, found: usize },
+    /// An element was infinite, NaN or too large to convert to an i32
+    OutOfRange { row: usize, col: usize },
+    /// A string did not name any Operator
+    UnknownOperator(String),
+    /// The first column and first row given for a matrix disagree on the
+    /// element they share
+    CornerMismatch { first_col: i32, first_row: i32 },
+    /// An element of a result was too large to store in an i32
+    Overflow { row: usize, col: usize },
+    /// A matrix read in had more elements than the limit it was read under
+    TooManyElements {
+        rows: usize,
+        cols: usize,
+        limit: usize,
+    },
+    /// A square matrix had a determinant of zero, so it has no inverse
+    Singular,
+    /// The inverse of an integer matrix had elements that aren't whole numbers
+    FractionalInverse,
+    /// A (row, col) index was outside a matrix with the given shape
+    IndexOutOfBounds {
+        row: usize,
+        col: usize,
+        rows: usize,
+        cols: usize,
+    },
+}
+
+impl Display for MatrixError {
+    /// Format MatrixError for display
+    ///
+    /// # Arguments
+    /// * self - reference to this MatrixError
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            MatrixError::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected a {}x{} matrix, found {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            MatrixError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {} has {} elements, expected {}",
+                line, found, expected
+            ),
+            MatrixError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            MatrixError::Io(message) => write!(f, "unable to read input: {}", message),
+            MatrixError::DivisionByZero { row, col } => {
+                write!(f, "division by zero at row {}, column {}", row, col)
+            }
+            MatrixError::Format(message) => write!(f, "invalid format: {}", message),
+            MatrixError::DimensionOverflow { rows, cols } => {
+                write!(
+                    f,
+                    "a {}x{} matrix has too many elements to store",
+                    rows, cols
+                )
+            }
+            MatrixError::DataLength { expected, found } => {
+                write!(f, "expected {} elements, found {}", expected, found)
+            }
+            MatrixError::OutOfRange { row, col } => write!(
+                f,
+                "element at row {}, column {} does not fit in an i32",
+                row, col
+            ),
+            MatrixError::UnknownOperator(name) => write!(f, "unknown operator {:?}", name),
+            MatrixError::CornerMismatch {
+                first_col,
+                first_row,
+            } => write!(
+                f,
+                "the first column starts with {} but the first row starts with {}",
+                first_col, first_row
+            ),
+            MatrixError::TooManyElements { rows, cols, limit } => write!(
+                f,
+                "a {}x{} matrix has more than the limit of {} elements",
+                rows, cols, limit
+            ),
+            MatrixError::Overflow { row, col } => {
+                write!(f, "result at row {}, column {} overflows an i32", row, col)
+            }
+            MatrixError::Singular => write!(f, "the matrix is singular, so it has no inverse"),
+            MatrixError::FractionalInverse => {
+                write!(f, "the inverse has elements that aren't whole numbers")
+            }
+            MatrixError::IndexOutOfBounds {
+                row,
+                col,
+                rows,
+                cols,
+            } => write!(
+                f,
+                "row {}, column {} is outside a {}x{} matrix",
+                row, col, rows, cols
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+impl From<std::io::Error> for MatrixError {
+    /// Wraps an io::Error, keeping only its message so MatrixError stays comparable
+    fn from(err: std::io::Error) -> MatrixError {
+        MatrixError::Io(err.to_string())
+    }
+}
+
+/// An Operation that couldn't be evaluated, with the operator and operand
+/// shapes of the step that failed
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct OperationError {
+    pub operator: Operator,
+    pub left_shape: (usize, usize),
+    pub right_shape: (usize, usize),
+    pub cause: MatrixError,
+}
+
+impl Display for OperationError {
+    /// Format OperationError for display
+    ///
+    /// # Arguments
+    /// * self - reference to this OperationError
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "unable to apply {:?} to a {}x{} and a {}x{} matrix: {}",
+            self.operator,
+            self.left_shape.0,
+            self.left_shape.1,
+            self.right_shape.0,
+            self.right_shape.1,
+            self.cause
+        )
+    }
+}
+
+impl std::error::Error for OperationError {
+    /// The reason the step failed
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+/// A UnaryOperation that couldn't be evaluated: either its operand is an
+/// Operation that couldn't be, or the operator can't be applied to its shape
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum UnaryOperationError {
+    /// A step of the operand failed
+    Operand(OperationError),
+    /// The operator couldn't be applied to the evaluated operand
+    Operator {
+        operator: UnaryOperator,
+        shape: (usize, usize),
+        cause: MatrixError,
+    },
+}
+
+impl Display for UnaryOperationError {
+    /// Format UnaryOperationError for display
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperationError
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            UnaryOperationError::Operand(err) => err.fmt(f),
+            UnaryOperationError::Operator {
+                operator,
+                shape,
+                cause,
+            } => write!(
+                f,
+                "unable to apply {:?} to a {}x{} matrix: {}",
+                operator, shape.0, shape.1, cause
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UnaryOperationError {
+    /// The reason the step failed
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UnaryOperationError::Operand(err) => Some(err),
+            UnaryOperationError::Operator { cause, .. } => Some(cause),
+        }
+    }
+}
+
+impl From<OperationError> for UnaryOperationError {
+    /// Wraps the error of an Operation used as the operand
+    fn from(err: OperationError) -> UnaryOperationError {
+        UnaryOperationError::Operand(err)
+    }
+}
+
+/// A Computation that couldn't be read or evaluated
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ComputationError {
+    /// The input couldn't be read as any kind of Computation
+    Input(MatrixError),
+    /// A step of an Operation, FloatOperation or Normalization failed
+    Operation(OperationError),
+    /// A step of a UnaryOperation failed
+    Unary(UnaryOperationError),
+}
+
+impl Display for ComputationError {
+    /// Format ComputationError for display
+    ///
+    /// # Arguments
+    /// * self - reference to this ComputationError
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            ComputationError::Input(err) => write!(f, "invalid input: {}", err),
+            ComputationError::Operation(err) => err.fmt(f),
+            ComputationError::Unary(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ComputationError {
+    /// The reason the input couldn't be read or the step failed
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ComputationError::Input(err) => Some(err),
+            ComputationError::Operation(err) => Some(err),
+            ComputationError::Unary(err) => Some(err),
+        }
+    }
+}
+
+impl From<MatrixError> for ComputationError {
+    /// Wraps the reason an input couldn't be read
+    fn from(err: MatrixError) -> ComputationError {
+        ComputationError::Input(err)
+    }
+}
+
+impl From<OperationError> for ComputationError {
+    /// Wraps the error of a failed step
+    fn from(err: OperationError) -> ComputationError {
+        ComputationError::Operation(err)
+    }
+}
+
+impl From<UnaryOperationError> for ComputationError {
+    /// Wraps the error of a failed UnaryOperation
+    fn from(err: UnaryOperationError) -> ComputationError {
+        ComputationError::Unary(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test the display of a dimension mismatch
+    #[test]
+    fn test_display_dimension_mismatch() {
+        let err = MatrixError::DimensionMismatch {
+            expected: (2, 2),
+            found: (3, 1),
+        };
+        assert_eq!(err.to_string(), "expected a 2x2 matrix, found 3x1");
+    }
+
+    /// Test the display of an operation error gives the operator and shapes
+    #[test]
+    fn test_display_operation_error() {
+        let err = OperationError {
+            operator: Operator::Multiply,
+            left_shape: (2, 3),
+            right_shape: (2, 3),
+            cause: MatrixError::DimensionMismatch {
+                expected: (3, 3),
+                found: (2, 3),
+            },
+        };
+        assert_eq!(
+            err.to_string(),
+            "unable to apply Multiply to a 2x3 and a 2x3 matrix: expected a 3x3 matrix, found 2x3"
+        );
+    }
+
+    /// Test the display of a unary operator applied to the wrong shape
+    #[test]
+    fn test_display_unary_operation_error() {
+        let err = UnaryOperationError::Operator {
+            operator: UnaryOperator::Determinant,
+            shape: (2, 3),
+            cause: MatrixError::DimensionMismatch {
+                expected: (2, 2),
+                found: (2, 3),
+            },
+        };
+        assert_eq!(
+            err.to_string(),
+            "unable to apply Determinant to a 2x3 matrix: expected a 2x2 matrix, found 2x3"
+        );
+    }
+
+    /// Test the display of an index outside a matrix
+    #[test]
+    fn test_display_index_out_of_bounds() {
+        let err = MatrixError::IndexOutOfBounds {
+            row: 4,
+            col: 0,
+            rows: 2,
+            cols: 3,
+        };
+        assert_eq!(err.to_string(), "row 4, column 0 is outside a 2x3 matrix");
+    }
+
+    /// Test the display of an input that couldn't be read
+    #[test]
+    fn test_display_computation_error() {
+        let err = ComputationError::from(MatrixError::Format("expected value".to_string()));
+        assert_eq!(
+            err.to_string(),
+            "invalid input: invalid format: expected value"
+        );
+    }
+}
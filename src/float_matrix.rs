@@ -0,0 +1,788 @@
+use crate::error::MatrixError;
+use crate::matrix::Matrix;
+
+/// Pivots smaller than this are treated as zero when eliminating
+const PIVOT_EPSILON: f64 = 1e-12;
+
+/// A matrix of floating point numbers, for algorithms whose results aren't
+/// whole numbers; the same type a FloatOperation is done on
+pub type FloatMatrix = Matrix<f64>;
+
+impl Matrix<f64> {
+    /// Returns the `n`x`n` Hilbert matrix, where `H[i, j] = 1 / (i + j + 1)`;
+    /// it is famously ill-conditioned, which makes it useful for testing
+    /// numerical methods
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of rows and columns of the matrix
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::hilbert(2);
+    /// assert_eq!(matrix, FloatMatrix::new(2, 2, vec![1.0, 0.5, 0.5, 1.0 / 3.0]));
+    /// ```
+    pub fn hilbert(n: usize) -> FloatMatrix {
+        let data = (0..n)
+            .flat_map(|i| (0..n).map(move |j| 1.0 / (i + j + 1) as f64))
+            .collect();
+        FloatMatrix::new(n, n, data)
+    }
+
+    /// Returns the inverse of a square matrix by Gauss-Jordan elimination with
+    /// partial pivoting
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to invert
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 2, vec![2.0, 0.0, 0.0, 4.0]);
+    /// let inverse = matrix.inverse().unwrap();
+    /// assert_eq!(inverse, FloatMatrix::new(2, 2, vec![0.5, 0.0, 0.0, 0.25]));
+    /// ```
+    pub fn inverse(&self) -> Option<FloatMatrix> {
+        assert_eq!(self.rows(), self.cols(), "Inverse requires a square matrix");
+        self.gauss_jordan(FloatMatrix::identity(self.rows()))
+    }
+
+    /// Solves `self * x = b` directly, by Gauss-Jordan elimination with partial
+    /// pivoting
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The square coefficient matrix
+    /// * `b` - The right hand side, with as many rows as `self`
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 2, vec![2.0, 0.0, 0.0, 4.0]);
+    /// let b = FloatMatrix::new(1, 2, vec![1.0, 1.0]);
+    /// assert_eq!(matrix.solve(&b), Some(FloatMatrix::new(1, 2, vec![0.5, 0.25])));
+    /// ```
+    pub fn solve(&self, b: &FloatMatrix) -> Option<FloatMatrix> {
+        assert_eq!(self.rows(), self.cols(), "Solve requires a square matrix");
+        assert_eq!(self.rows(), b.rows());
+        self.gauss_jordan(b.clone())
+    }
+
+    /// Reduces `[self | rhs]` until `self` is the identity, applying the same
+    /// row operations to `rhs`
+    ///
+    /// # Arguments
+    /// * self - the square matrix to reduce
+    /// * rhs - the matrix to carry along, with as many rows as `self`
+    ///
+    /// # Returns
+    /// `self⁻¹ * rhs`, or None if `self` is singular
+    fn gauss_jordan(&self, mut rhs: FloatMatrix) -> Option<FloatMatrix> {
+        let n = self.rows();
+        let mut work = self.clone();
+
+        for k in 0..n {
+            let pivot_row = (k..n)
+                .max_by(|&a, &b| work[[a, k]].abs().total_cmp(&work[[b, k]].abs()))
+                .unwrap();
+            if work[[pivot_row, k]].abs() < PIVOT_EPSILON {
+                return None;
+            }
+            work.swap_rows(k, pivot_row);
+            rhs.swap_rows(k, pivot_row);
+
+            let pivot = work[[k, k]];
+            for j in 0..n {
+                work[[k, j]] /= pivot;
+            }
+            for j in 0..rhs.cols() {
+                rhs[[k, j]] /= pivot;
+            }
+            for i in (0..n).filter(|&i| i != k) {
+                let factor = work[[i, k]];
+                for j in 0..n {
+                    work[[i, j]] -= factor * work[[k, j]];
+                }
+                for j in 0..rhs.cols() {
+                    rhs[[i, j]] -= factor * rhs[[k, j]];
+                }
+            }
+        }
+        Some(rhs)
+    }
+
+    /// Checks whether each row's diagonal element is at least as large, in
+    /// absolute value, as the sum of the row's other elements; iterative
+    /// solvers need this to converge. Non-square matrices never are
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatMatrix
+    ///
+    /// # Returns
+    /// true if the matrix is diagonally dominant
+    pub fn is_diagonally_dominant(&self) -> bool {
+        self.rows() == self.cols()
+            && (0..self.rows()).all(|i| {
+                let off_diagonal: f64 = (0..self.cols())
+                    .filter(|&j| j != i)
+                    .map(|j| self[[i, j]].abs())
+                    .sum();
+                self[[i, i]].abs() >= off_diagonal
+            })
+    }
+
+    /// Solves `self * x = b` by Jacobi iteration, starting from zero and
+    /// updating every element of `x` from the previous estimate each step
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The square, diagonally dominant coefficient matrix
+    /// * `b` - The right hand side column vector
+    /// * `iterations` - The most steps to take before giving up
+    /// * `epsilon` - Converged once no element of `x` changes by more than this
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 2, vec![4.0, 1.0, 1.0, 3.0]);
+    /// let b = FloatMatrix::new(1, 2, vec![1.0, 2.0]);
+    /// let x = matrix.solve_jacobi(&b, 100, 1e-10).unwrap();
+    /// ```
+    pub fn solve_jacobi(
+        &self,
+        b: &FloatMatrix,
+        iterations: usize,
+        epsilon: f64,
+    ) -> Option<FloatMatrix> {
+        self.iterate_solver(b, iterations, epsilon, |previous, _, i| {
+            let sum: f64 = (0..self.cols())
+                .filter(|&j| j != i)
+                .map(|j| self[[i, j]] * previous[j])
+                .sum();
+            (b[[i, 0]] - sum) / self[[i, i]]
+        })
+    }
+
+    /// Solves `self * x = b` by Gauss-Seidel iteration, starting from zero.
+    /// Unlike Jacobi, each element of `x` is worked out from the elements
+    /// already updated this step, which usually converges in fewer steps
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The square, diagonally dominant coefficient matrix
+    /// * `b` - The right hand side column vector
+    /// * `iterations` - The most steps to take before giving up
+    /// * `epsilon` - Converged once no element of `x` changes by more than this
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 2, vec![4.0, 1.0, 1.0, 3.0]);
+    /// let b = FloatMatrix::new(1, 2, vec![1.0, 2.0]);
+    /// let x = matrix.solve_gauss_seidel(&b, 100, 1e-10).unwrap();
+    /// ```
+    pub fn solve_gauss_seidel(
+        &self,
+        b: &FloatMatrix,
+        iterations: usize,
+        epsilon: f64,
+    ) -> Option<FloatMatrix> {
+        self.iterate_solver(b, iterations, epsilon, |previous, current, i| {
+            let updated: f64 = (0..i).map(|j| self[[i, j]] * current[j]).sum();
+            let remaining: f64 = (i + 1..self.cols())
+                .map(|j| self[[i, j]] * previous[j])
+                .sum();
+            (b[[i, 0]] - updated - remaining) / self[[i, i]]
+        })
+    }
+
+    /// Runs an iterative solver until it converges, giving up if it doesn't
+    ///
+    /// # Arguments
+    /// * self - the square coefficient matrix
+    /// * b - the right hand side column vector
+    /// * iterations - the most steps to take
+    /// * epsilon - converged once no element of `x` changes by more than this
+    /// * update - works out the next value of `x[i]` from the previous estimate
+    ///   and the elements before `i` of the new one
+    ///
+    /// # Returns
+    /// `x` as a column vector, or None if the matrix isn't diagonally dominant
+    /// or the solver didn't converge
+    fn iterate_solver<F: Fn(&[f64], &[f64], usize) -> f64>(
+        &self,
+        b: &FloatMatrix,
+        iterations: usize,
+        epsilon: f64,
+        update: F,
+    ) -> Option<FloatMatrix> {
+        assert_eq!(self.rows(), b.rows());
+        assert_eq!(b.cols(), 1, "b must be a column vector");
+        if !self.is_diagonally_dominant() {
+            return None;
+        }
+
+        let mut x = vec![0.0; self.rows()];
+        for _ in 0..iterations {
+            let mut next = x.clone();
+            for i in 0..self.rows() {
+                next[i] = update(&x, &next, i);
+            }
+            let change = next
+                .iter()
+                .zip(x.iter())
+                .map(|(new, old)| (new - old).abs())
+                .fold(0.0, f64::max);
+            x = next;
+            if change <= epsilon {
+                return Some(FloatMatrix::new(1, self.rows(), x));
+            }
+        }
+        None
+    }
+
+    /// Returns the product of two compatible matrices
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 1, vec![0.5, 2.0]);
+    /// let product = matrix.mul_mat(&FloatMatrix::new(1, 2, vec![2.0, 1.0]));
+    /// assert_eq!(product, FloatMatrix::new(1, 1, vec![3.0]));
+    /// ```
+    pub fn mul_mat(&self, rhs: &FloatMatrix) -> FloatMatrix {
+        assert_eq!(self.cols(), rhs.rows());
+        let data = (0..self.rows())
+            .flat_map(|i| {
+                (0..rhs.cols())
+                    .map(move |j| (0..self.cols()).map(|k| self[[i, k]] * rhs[[k, j]]).sum())
+            })
+            .collect();
+        FloatMatrix::new(rhs.cols(), self.rows(), data)
+    }
+
+    /// Gets the sum of the diagonal of a square FloatMatrix
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatMatrix
+    ///
+    /// # Returns
+    /// the trace
+    pub fn trace(&self) -> f64 {
+        assert_eq!(self.rows(), self.cols(), "Trace requires a square matrix");
+        (0..self.rows()).map(|i| self[[i, i]]).sum()
+    }
+
+    /// Gets the determinant of a square FloatMatrix by Gaussian elimination
+    /// with partial pivoting
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatMatrix
+    ///
+    /// # Returns
+    /// the determinant
+    pub fn determinant(&self) -> f64 {
+        assert_eq!(
+            self.rows(),
+            self.cols(),
+            "Determinant requires a square matrix"
+        );
+        let n = self.rows();
+        let mut work = self.clone();
+        let mut det = 1.0;
+        for k in 0..n {
+            let pivot_row = (k..n)
+                .max_by(|&a, &b| work[[a, k]].abs().total_cmp(&work[[b, k]].abs()))
+                .unwrap();
+            if work[[pivot_row, k]] == 0.0 {
+                return 0.0;
+            }
+            if pivot_row != k {
+                work.swap_rows(k, pivot_row);
+                det = -det;
+            }
+            det *= work[[k, k]];
+            for i in k + 1..n {
+                let factor = work[[i, k]] / work[[k, k]];
+                for j in k..n {
+                    work[[i, j]] -= factor * work[[k, j]];
+                }
+            }
+        }
+        det
+    }
+
+    /// Gets the 1-norm of the matrix, the largest absolute column sum
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatMatrix
+    ///
+    /// # Returns
+    /// the 1-norm
+    pub fn norm_1(&self) -> f64 {
+        (0..self.cols())
+            .map(|j| (0..self.rows()).map(|i| self[[i, j]].abs()).sum::<f64>())
+            .fold(0.0, f64::max)
+    }
+
+    /// Gets the Frobenius norm of the matrix, the square root of the sum of
+    /// the squares of its elements
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatMatrix
+    ///
+    /// # Returns
+    /// the Frobenius norm
+    pub fn frobenius_norm(&self) -> f64 {
+        (0..self.rows())
+            .flat_map(|i| self.row_iter(i))
+            .map(|num| num * num)
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// Returns this matrix with every element divided by `divisor`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to divide
+    /// * `divisor` - The number to divide each element by
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 1, vec![3.0, 4.0]);
+    /// assert_eq!(matrix.div_scalar(2.0), FloatMatrix::new(2, 1, vec![1.5, 2.0]));
+    /// ```
+    pub fn div_scalar(&self, divisor: f64) -> FloatMatrix {
+        self.map(|num| num / divisor)
+    }
+
+    /// Estimates the condition number of a square matrix in the 1-norm,
+    /// `||A|| * ||A⁻¹||`; large values mean solving with this matrix amplifies
+    /// rounding error
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatMatrix
+    ///
+    /// # Returns
+    /// the condition number, or infinity if the matrix is singular
+    pub fn condition_number_estimate(&self) -> f64 {
+        match self.inverse() {
+            Some(inverse) => self.norm_1() * inverse.norm_1(),
+            None => f64::INFINITY,
+        }
+    }
+
+    /// Returns the row echelon form of this matrix, by forward Gaussian
+    /// elimination with partial pivoting and no back substitution. Each row's
+    /// leading element is to the right of the one above, with zeros below it,
+    /// and any zero rows are at the bottom
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to reduce
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 2, vec![1.0, 2.0, 2.0, 2.0]);
+    /// let echelon = matrix.row_echelon_form();
+    /// assert_eq!(echelon, FloatMatrix::new(2, 2, vec![2.0, 2.0, 0.0, 1.0]));
+    /// ```
+    pub fn row_echelon_form(&self) -> FloatMatrix {
+        let mut work = self.clone();
+        let mut pivot_row = 0;
+        for col in 0..self.cols() {
+            if pivot_row == self.rows() {
+                break;
+            }
+            let best = (pivot_row..self.rows())
+                .max_by(|&a, &b| work[[a, col]].abs().total_cmp(&work[[b, col]].abs()))
+                .unwrap();
+            if work[[best, col]].abs() < PIVOT_EPSILON {
+                // what's left of this column is rounding error
+                for i in pivot_row..self.rows() {
+                    work[[i, col]] = 0.0;
+                }
+                continue;
+            }
+            work.swap_rows(pivot_row, best);
+
+            for i in pivot_row + 1..self.rows() {
+                let factor = work[[i, col]] / work[[pivot_row, col]];
+                for j in col + 1..self.cols() {
+                    work[[i, j]] -= factor * work[[pivot_row, j]];
+                }
+                work[[i, col]] = 0.0;
+            }
+            pivot_row += 1;
+        }
+        work
+    }
+
+    /// Returns the reduced row echelon form of this matrix: the row echelon
+    /// form, back substituted so each pivot is 1 and is the only non-zero
+    /// element in its column
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to reduce
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 2, vec![1.0, 2.0, 2.0, 2.0]);
+    /// assert_eq!(matrix.reduced_row_echelon_form(), FloatMatrix::identity(2));
+    /// ```
+    pub fn reduced_row_echelon_form(&self) -> FloatMatrix {
+        let mut work = self.row_echelon_form();
+        for i in (0..self.rows()).rev() {
+            let lead = match work.leading_column(i) {
+                Some(lead) => lead,
+                None => continue,
+            };
+            let pivot = work[[i, lead]];
+            for j in lead..self.cols() {
+                work[[i, j]] /= pivot;
+            }
+            for above in 0..i {
+                let factor = work[[above, lead]];
+                for j in lead + 1..self.cols() {
+                    work[[above, j]] -= factor * work[[i, j]];
+                }
+                work[[above, lead]] = 0.0;
+            }
+        }
+        work
+    }
+
+    /// Returns a basis of the nullspace of this matrix: column vectors `v` with
+    /// `self * v = 0`, one for each column without a pivot in the reduced row
+    /// echelon form
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix whose kernel to find
+    ///
+    /// # Returns
+    /// the basis vectors, which is empty when the columns are independent
+    ///
+    /// ```
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 1, vec![1.0, 1.0]);
+    /// assert_eq!(matrix.nullspace(), vec![FloatMatrix::new(1, 2, vec![-1.0, 1.0])]);
+    /// ```
+    pub fn nullspace(&self) -> Vec<FloatMatrix> {
+        let rref = self.reduced_row_echelon_form();
+        let pivots: Vec<(usize, usize)> = (0..self.rows())
+            .filter_map(|i| rref.leading_column(i).map(|lead| (i, lead)))
+            .collect();
+
+        (0..self.cols())
+            .filter(|col| !pivots.iter().any(|(_, lead)| lead == col))
+            .map(|free| {
+                let mut basis = vec![0.0; self.cols()];
+                basis[free] = 1.0;
+                for &(i, lead) in &pivots {
+                    basis[lead] = -rref[[i, free]];
+                }
+                FloatMatrix::new(1, self.cols(), basis)
+            })
+            .collect()
+    }
+
+    /// Gets the column of the first element of a row that isn't rounding error
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatMatrix
+    /// * row - the row to search
+    ///
+    /// # Returns
+    /// the column, or None if the whole row is zero
+    fn leading_column(&self, row: usize) -> Option<usize> {
+        (0..self.cols()).find(|&j| self[[row, j]].abs() >= PIVOT_EPSILON)
+    }
+
+    /// Rounds each element to the nearest integer, giving an integer Matrix
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to convert
+    ///
+    /// # Returns
+    /// the rounded Matrix, or OutOfRange for the first element that is
+    /// infinite, NaN or outside the range of an i32 once rounded
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = FloatMatrix::new(2, 1, vec![1.4, -2.6]);
+    /// assert_eq!(matrix.to_i32_checked(), Ok(Matrix::new(2, 1, vec![1, -3])));
+    /// ```
+    pub fn to_i32_checked(&self) -> Result<Matrix, MatrixError> {
+        let data = (0..self.rows())
+            .flat_map(|row| (0..self.cols()).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                let rounded = self[[row, col]].round();
+                if rounded.is_finite()
+                    && rounded >= f64::from(i32::MIN)
+                    && rounded <= f64::from(i32::MAX)
+                {
+                    Ok(rounded as i32)
+                } else {
+                    Err(MatrixError::OutOfRange { row, col })
+                }
+            })
+            .collect::<Result<Vec<i32>, MatrixError>>()?;
+        Ok(Matrix::new(self.cols(), self.rows(), data))
+    }
+
+    /// Swaps two rows in place
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatMatrix
+    /// * a - one of the rows
+    /// * b - the other row
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        if a != b {
+            for j in 0..self.cols() {
+                let num = self[[a, j]];
+                self[[a, j]] = self[[b, j]];
+                self[[b, j]] = num;
+            }
+        }
+    }
+}
+
+impl From<&Matrix> for FloatMatrix {
+    /// Converts each element of an integer Matrix to floating point
+    fn from(matr: &Matrix) -> FloatMatrix {
+        matr.map(f64::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Layout;
+
+    /// Test a few Hilbert matrix entries and its conditioning
+    #[test]
+    fn test_hilbert() {
+        let hilbert = FloatMatrix::hilbert(5);
+        assert_eq!(hilbert.rows(), 5);
+        assert_eq!(hilbert[[0, 0]], 1.0);
+        assert_eq!(hilbert[[1, 2]], 0.25);
+        assert_eq!(hilbert[[2, 1]], 0.25);
+        assert_eq!(hilbert[[4, 4]], 1.0 / 9.0);
+
+        // the exact 1-norm condition number of the 5x5 Hilbert matrix is about 9.4e5
+        let condition = hilbert.condition_number_estimate();
+        assert!(condition > 9e5 && condition < 1e6, "{}", condition);
+        assert!((FloatMatrix::identity(5).condition_number_estimate() - 1.0).abs() < 1e-12);
+    }
+
+    /// Test inverting a matrix and detecting a singular one
+    #[test]
+    fn test_inverse() {
+        let our_mat = FloatMatrix::from(&Matrix::new(2, 2, vec![4, 7, 2, 6]));
+        let inverse = our_mat.inverse().unwrap();
+        let expected = [0.6, -0.7, -0.2, 0.4];
+        for (found, expected) in inverse.to_rows().concat().iter().zip(expected.iter()) {
+            assert!((found - expected).abs() < 1e-12);
+        }
+
+        let singular = FloatMatrix::from(&Matrix::new(2, 2, vec![1, 2, 2, 4]));
+        assert_eq!(singular.inverse(), None);
+
+        // the elimination works on elements, not on how they are stored
+        let col_major = our_mat.to_layout(Layout::ColMajor);
+        assert_eq!(col_major.inverse(), Some(inverse));
+        assert_eq!(col_major.determinant(), our_mat.determinant());
+    }
+
+    /// Test reading a FloatMatrix checks its data fills its shape
+    #[test]
+    fn test_deserialize() {
+        let our_mat: FloatMatrix =
+            serde_json::from_str(r#"{"rows": 1, "cols": 2, "data": [0.5, -2]}"#).unwrap();
+        assert_eq!(our_mat, FloatMatrix::new(2, 1, vec![0.5, -2.0]));
+
+        let err = serde_json::from_str::<FloatMatrix>(r#"{"rows": 2, "cols": 2, "data": [0.5]}"#)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("expected 4 elements, found 1"),
+            "{}",
+            err
+        );
+    }
+
+    /// Test a FloatMatrix is displayed like any other Matrix
+    #[test]
+    fn test_display() {
+        let our_mat = FloatMatrix::new(2, 1, vec![0.5, -2.0]);
+        assert_eq!(our_mat.to_string(), "   0.5     -2 \n");
+        assert_eq!(format!("{:.1}", our_mat), "   0.5   -2.0 \n");
+    }
+
+    /// Test Jacobi iteration agrees with the direct solve
+    #[test]
+    fn test_solve_jacobi() {
+        let our_mat = FloatMatrix::from(&Matrix::new(3, 3, vec![10, -1, 2, -1, 11, -1, 2, -1, 10]));
+        let b = FloatMatrix::new(1, 3, vec![6.0, 25.0, -11.0]);
+
+        let direct = our_mat.solve(&b).unwrap();
+        let jacobi = our_mat.solve_jacobi(&b, 100, 1e-12).unwrap();
+        for i in 0..3 {
+            assert!((direct[[i, 0]] - jacobi[[i, 0]]).abs() < 1e-9);
+        }
+
+        // not enough iterations to converge
+        assert_eq!(our_mat.solve_jacobi(&b, 2, 1e-12), None);
+
+        // not diagonally dominant
+        let our_mat = FloatMatrix::from(&Matrix::new(2, 2, vec![1, 2, 3, 4]));
+        let b = FloatMatrix::new(1, 2, vec![1.0, 1.0]);
+        assert_eq!(our_mat.solve_jacobi(&b, 100, 1e-12), None);
+    }
+
+    /// Gets the column of the first non-zero element of each row
+    fn leading_columns(matr: &FloatMatrix) -> Vec<Option<usize>> {
+        (0..matr.rows())
+            .map(|i| (0..matr.cols()).find(|&j| matr[[i, j]] != 0.0))
+            .collect()
+    }
+
+    /// Test forward elimination leaves a staircase of pivots with zeros below
+    #[test]
+    fn test_row_echelon_form() {
+        // the second row is twice the first, so the rank is 2
+        let our_mat =
+            FloatMatrix::from(&Matrix::new(4, 3, vec![1, 2, 3, 4, 2, 4, 6, 8, 1, 3, 5, 6]));
+        let echelon = our_mat.row_echelon_form();
+        assert_eq!(leading_columns(&echelon), vec![Some(0), Some(1), None]);
+        for i in 0..3 {
+            for j in 0..i.min(4) {
+                assert_eq!(echelon[[i, j]], 0.0);
+            }
+        }
+
+        let our_mat = FloatMatrix::from(&Matrix::new(3, 3, vec![0, 0, 1, 0, 2, 1, 0, 4, 3]));
+        let echelon = our_mat.row_echelon_form();
+        assert_eq!(leading_columns(&echelon), vec![Some(1), Some(2), None]);
+        assert_eq!(
+            echelon,
+            FloatMatrix::new(3, 3, vec![0.0, 4.0, 3.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0])
+        );
+    }
+
+    /// Test the reduced form of a rank deficient matrix
+    #[test]
+    fn test_reduced_row_echelon_form() {
+        let our_mat = FloatMatrix::from(&Matrix::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]));
+        let rref = our_mat.reduced_row_echelon_form();
+        let expected = [1.0, 0.0, -1.0, 0.0, 1.0, 2.0, 0.0, 0.0, 0.0];
+        for (i, value) in expected.iter().enumerate() {
+            assert!((rref[[i / 3, i % 3]] - value).abs() < 1e-9);
+        }
+    }
+
+    /// Test each nullspace basis vector is sent to zero
+    #[test]
+    fn test_nullspace() {
+        let our_mat =
+            FloatMatrix::from(&Matrix::new(4, 3, vec![1, 2, 3, 4, 2, 4, 6, 8, 1, 3, 5, 6]));
+        let basis = our_mat.nullspace();
+        assert_eq!(basis.len(), 2);
+        for v in &basis {
+            assert_eq!((v.rows(), v.cols()), (4, 1));
+            for i in 0..our_mat.rows() {
+                let product: f64 = (0..our_mat.cols())
+                    .map(|j| our_mat[[i, j]] * v[[j, 0]])
+                    .sum();
+                assert!(product.abs() < 1e-9);
+            }
+        }
+
+        // full column rank has a trivial nullspace
+        let our_mat = FloatMatrix::from(&Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 7]));
+        assert!(our_mat.nullspace().is_empty());
+    }
+
+    /// Gets the fewest iterations a solver needs to converge
+    fn iterations_to_converge<F: Fn(usize) -> Option<FloatMatrix>>(solver: F) -> usize {
+        (1..1000)
+            .find(|&iterations| solver(iterations).is_some())
+            .unwrap()
+    }
+
+    /// Test Gauss-Seidel agrees with the direct solve, in fewer steps than Jacobi
+    #[test]
+    fn test_solve_gauss_seidel() {
+        let our_mat = FloatMatrix::from(&Matrix::new(3, 3, vec![10, -1, 2, -1, 11, -1, 2, -1, 10]));
+        let b = FloatMatrix::new(1, 3, vec![6.0, 25.0, -11.0]);
+
+        let direct = our_mat.solve(&b).unwrap();
+        let gauss_seidel = our_mat.solve_gauss_seidel(&b, 100, 1e-12).unwrap();
+        for i in 0..3 {
+            assert!((direct[[i, 0]] - gauss_seidel[[i, 0]]).abs() < 1e-9);
+        }
+
+        let jacobi_steps = iterations_to_converge(|n| our_mat.solve_jacobi(&b, n, 1e-12));
+        let gauss_seidel_steps =
+            iterations_to_converge(|n| our_mat.solve_gauss_seidel(&b, n, 1e-12));
+        assert!(gauss_seidel_steps < jacobi_steps);
+
+        // not diagonally dominant
+        let our_mat = FloatMatrix::from(&Matrix::new(2, 2, vec![1, 2, 3, 4]));
+        let b = FloatMatrix::new(1, 2, vec![1.0, 1.0]);
+        assert_eq!(our_mat.solve_gauss_seidel(&b, 100, 1e-12), None);
+    }
+
+    /// Test the float trace and determinant
+    #[test]
+    fn test_trace_determinant() {
+        let our_mat = FloatMatrix::from(&Matrix::new(3, 3, vec![2, -1, 0, 1, 3, 4, 0, 5, -2]));
+        assert_eq!(our_mat.trace(), 3.0);
+        assert!((our_mat.determinant() - -54.0).abs() < 1e-9);
+        assert_eq!(
+            FloatMatrix::hilbert(3).mul_mat(&FloatMatrix::identity(3)),
+            FloatMatrix::hilbert(3)
+        );
+    }
+
+    /// Test rounding to an integer Matrix, and rejecting elements that don't fit
+    #[test]
+    fn test_to_i32_checked() {
+        let our_mat = FloatMatrix::new(3, 1, vec![0.4, -1.5, 2147483647.2]);
+        assert_eq!(
+            our_mat.to_i32_checked(),
+            Ok(Matrix::new(3, 1, vec![0, -2, i32::MAX]))
+        );
+
+        let our_mat = FloatMatrix::new(2, 2, vec![1.0, 2.0, 2147483648.0, 4.0]);
+        assert_eq!(
+            our_mat.to_i32_checked(),
+            Err(MatrixError::OutOfRange { row: 1, col: 0 })
+        );
+        for &bad in [f64::NAN, f64::INFINITY, -2147483649.0].iter() {
+            let our_mat = FloatMatrix::new(2, 1, vec![1.0, bad]);
+            assert_eq!(
+                our_mat.to_i32_checked(),
+                Err(MatrixError::OutOfRange { row: 0, col: 1 })
+            );
+        }
+    }
+
+    /// Test the Frobenius norm and dividing by it
+    #[test]
+    fn test_frobenius_norm() {
+        let our_mat = FloatMatrix::new(2, 2, vec![3.0, 0.0, -4.0, 0.0]);
+        assert_eq!(our_mat.frobenius_norm(), 5.0);
+        assert_eq!(our_mat.norm_1(), 7.0);
+        assert_eq!(
+            our_mat.div_scalar(our_mat.frobenius_norm()),
+            FloatMatrix::new(2, 2, vec![0.6, 0.0, -0.8, 0.0])
+        );
+        assert_eq!(FloatMatrix::new(0, 0, vec![]).frobenius_norm(), 0.0);
+    }
+}
@@ -0,0 +1,17 @@
+//! Name: Sample Code
+//! Author: Tyler Holinka, Matthew Krohn, Kendric Thompson, Jennifer Kulich
+//! Class: CSC 461 - Programming Languages
+//! Description: Matrices and the linear algebra operations on them, read from and written to json.
+//! The sample_code binary is a command line interface over this library.
+pub mod chain;
+pub mod computation;
+pub mod error;
+pub mod float_matrix;
+pub mod matrix;
+pub mod operation;
+pub mod runner;
+
+pub use computation::{run_json, Computation};
+pub use error::{ComputationError, MatrixError};
+pub use matrix::Matrix;
+pub use operation::{Operand, Operation, Operator};
@@ -2,30 +2,87 @@
 /// Author: Tyler Holinka, Matthew Krohn, Kendric Thompson, Jennifer Kulich
 /// Class: CSC 461 - Programming Languages
 /// Description: A basic project that gets vectors from a json file, and does linear algebra operations on them.
-use operation::Operation;
-use std::fs::File;
+use cli::{Input, MatrixSource, OperandSide, OperatorSource};
+use sample_code::matrix::{with_element_limit, AnyMatrix};
+use sample_code::operation::{Operand, Operation, Operator, WideOperation};
+use sample_code::runner::{
+    report_path, run_computation, thread_count, validate_dir, write_output, THREADS_VAR,
+};
+use sample_code::Computation;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 mod cli;
-mod matrix;
-mod operation;
 
 /// Author: Tyler Holinka
 /// Description: Function to get an Operation from a json file
-/// Parameter input: the PathBuf representing the input file
+/// Parameter input: the contents of the input file
 /// Return: the Operation to run
-fn get_opt(input: std::path::PathBuf) -> Operation {
-    // make sure we have a valid in file, and open it
-    let input = match File::open(input) {
+fn get_opt(input: &str) -> Operation {
+    match serde_json::from_str(input) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("invalid json. {}", e);
+            std::process::exit(1)
+        }
+    }
+}
+
+/// Description: Reads a file, exiting if it can't be read
+/// Parameter path: the file to read
+/// Return: the contents of the file
+fn read_input(path: &Path) -> String {
+    match std::fs::read_to_string(path) {
         Ok(v) => v,
         Err(e) => {
             eprintln!("need a valid file. {}", e);
             std::process::exit(1);
         }
+    }
+}
+
+/// Description: Prints something to the given precision, or as it normally is if there is none
+/// Parameter value: what to print
+/// Parameter precision: the digits to print after the decimal point
+fn print_with_precision<D: std::fmt::Display>(value: &D, precision: Option<usize>) {
+    match precision {
+        Some(precision) => println!("{:.*}", precision, value),
+        None => println!("{}", value),
+    }
+}
+
+/// Description: Function to get a Matrix given on the command line or in a json file, with
+///              the element type its data needs
+/// Parameter source: the matrix, or the path of the file holding it
+/// Return: the Matrix
+fn get_matrix(source: &MatrixSource) -> AnyMatrix {
+    let path = match source {
+        MatrixSource::Inline(matr) => return AnyMatrix::I32(matr.clone()),
+        MatrixSource::File(path) => path,
     };
-    match serde_json::from_reader(input) {
+    match AnyMatrix::from_json_str(&read_input(path)) {
         Ok(v) => v,
         Err(e) => {
-            eprintln!("invalid json. {}", e);
+            eprintln!("invalid matrix in {}. {}", path.display(), e);
+            std::process::exit(1)
+        }
+    }
+}
+
+/// Description: Function to get the Operator to apply to matrices given in separate files
+/// Parameter source: the Operator, or the file holding it as json or as its symbol or name
+/// Return: the Operator
+fn get_operator(source: OperatorSource) -> Operator {
+    let path = match source {
+        OperatorSource::Inline(op) => return op,
+        OperatorSource::File(path) => path,
+    };
+    let text = read_input(&path);
+    let op = serde_json::from_str(&text).or_else(|_| Operator::from_str(&text));
+    match op {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("invalid operator in {}. {}", path.display(), e);
             std::process::exit(1)
         }
     }
@@ -36,15 +93,119 @@ fn get_opt(input: std::path::PathBuf) -> Operation {
 fn main() {
     let args = cli::process_args();
 
-    let op = get_opt(args.input);
+    let limit = args.max_elements;
+    let env = std::env::var(THREADS_VAR).ok();
+    let threads = match thread_count(args.parallel, args.threads, env.as_deref()) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let (mut computation, input) = match args.input {
+        Input::File(path) => {
+            let text = read_input(&path);
+            match with_element_limit(limit, || Computation::from_json_str(&text)) {
+                Ok(v) => (v, Some(path)),
+                Err(e) => {
+                    eprintln!("invalid json. {}", e);
+                    std::process::exit(1)
+                }
+            }
+        }
+        Input::Extract { file, side } => {
+            let op = with_element_limit(limit, || get_opt(&read_input(&file)));
+            let operand = match side {
+                OperandSide::Left => op.left_operand(),
+                OperandSide::Right => op.right_operand(),
+            };
+            // a nested Operation is shown as its operands and operator without its own result;
+            // an operand that can't be worked out is shown as the reason why
+            match operand {
+                Operand::Matrix(matr) if args.debug => print!("{}", matr.display_with_summary()),
+                Operand::Matrix(matr) => print!("{}", matr.display_with_header()),
+                Operand::Operation(op) if args.debug => println!("{}", op.display_debug()),
+                Operand::Operation(op) => println!("{}", op),
+            }
+            return;
+        }
+        Input::ValidateDir(dir) => {
+            let (passed, failed) = match with_element_limit(limit, || validate_dir(&dir)) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("unable to read directory. {}", e);
+                    std::process::exit(1);
+                }
+            };
+            for (path, e) in &failed {
+                println!("{}: {}", path.display(), e);
+            }
+            println!("{} passed, {} failed", passed, failed.len());
+            if !failed.is_empty() {
+                std::process::exit(1);
+            }
+            return;
+        }
+        Input::Parts {
+            left,
+            operator,
+            right,
+        } => {
+            let operator = get_operator(operator);
+            let lhs = with_element_limit(limit, || get_matrix(&left));
+            let rhs = with_element_limit(limit, || get_matrix(&right));
+            // only i32 matrices make an Operation; anything wider is a
+            // WideOperation, done in the wider element type
+            let computation = match (lhs, rhs) {
+                (AnyMatrix::I32(lhs), AnyMatrix::I32(rhs)) => Computation::Operation(
+                    Operation::new(Operand::Matrix(lhs), operator, Operand::Matrix(rhs)),
+                ),
+                (lhs, rhs) => Computation::Wide(WideOperation::new(lhs, operator, rhs)),
+            };
+            // the report goes beside the first matrix read from a file
+            let file = match (left, right) {
+                (MatrixSource::File(path), _) | (_, MatrixSource::File(path)) => Some(path),
+                _ => None,
+            };
+            (computation, file)
+        }
+        Input::Inline(matr) => {
+            // there's no operation to run on a single matrix, so just show it
+            if args.debug {
+                print!("{}", matr.display_with_summary());
+            } else {
+                print!("{}", matr.display_with_header());
+            }
+            return;
+        }
+    };
 
-    op.do_operation_and_store();
+    let out = args.out.as_deref();
+    let report = match (args.report, out.or(input.as_deref())) {
+        (false, _) => None,
+        (true, Some(path)) => Some(report_path(path)),
+        (true, None) => {
+            eprintln!("--report needs an output file when no matrix is read from a file");
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = run_computation(&mut computation, threads, report.as_deref()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 
-    match args.out {
-        None => println!("{}", op),
-        Some(file) => {
-            let out = File::create(file).unwrap();
-            serde_json::to_writer_pretty(out, &op).expect("Unable to write to file");
+    match (out, &computation) {
+        (None, Computation::Operation(op)) if args.debug => println!("{}", op.display_debug()),
+        (None, computation) => print_with_precision(computation, args.precision),
+        (Some(_), _) => {}
+    }
+    for file in out
+        .into_iter()
+        .chain(args.also_write.iter().map(PathBuf::as_path))
+    {
+        if let Err(e) = write_output(&computation, file) {
+            eprintln!("unable to write to {}. {}", file.display(), e);
+            std::process::exit(1);
         }
     }
 }
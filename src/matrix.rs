@@ -1,17 +1,364 @@
 extern crate crossbeam;
 
+use crate::error::MatrixError;
+use crate::float_matrix::FloatMatrix;
+use crate::operation::{Operand, Operation, Operator};
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter, Result};
-use std::ops::{Index, IndexMut, Mul};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::{Add, Index, IndexMut, Mul, Sub};
 
-#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
-pub struct Matrix {
+/// The version of the format a Matrix or Operation is serialized in; files
+/// written before it was recorded are read as version 0
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Square matrices at least this size have their LU elimination steps spread
+/// across threads
+const PARALLEL_LU_SIZE: usize = 128;
+
+/// The side of the square tiles `transpose_parallel` copies at a time, small
+/// enough that a tile's rows and columns stay in cache
+const TRANSPOSE_TILE: usize = 32;
+
+/// Matrices with fewer elements than this are zipped on the current thread by
+/// `par_zip_map`, since spawning threads would cost more than it saves
+const PARALLEL_ZIP_SIZE: usize = 1 << 16;
+
+/// Products needing fewer multiply-adds than this are done on the current
+/// thread by `mul_mat`
+const PARALLEL_MUL_WORK: usize = 1 << 15;
+
+/// The side of the square blocks `MulStrategy::Blocked` multiplies at a time
+const MUL_BLOCK: usize = 64;
+
+/// `MulStrategy::Strassen` multiplies square matrices no larger than this
+/// directly instead of splitting them further
+const STRASSEN_CUTOFF: usize = 64;
+
+/// The order the elements of a Matrix are stored in
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Layout {
+    /// Each row is stored contiguously, one row after another
+    RowMajor,
+    /// Each column is stored contiguously, one column after another
+    ColMajor,
+}
+
+impl Layout {
+    /// Checks whether this is the default, row-major layout
+    ///
+    /// # Arguments
+    /// * self - reference to this Layout
+    ///
+    /// # Returns
+    /// true if this is Layout::RowMajor
+    fn is_row_major(&self) -> bool {
+        *self == Layout::RowMajor
+    }
+}
+
+impl Default for Layout {
+    /// Matrices are row-major unless asked otherwise
+    fn default() -> Layout {
+        Layout::RowMajor
+    }
+}
+
+/// The text format `Matrix::from_reader_auto` reads a matrix in
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum FormatHint {
+    /// Work the format out from the text: JSON if it starts with `{` or `[`,
+    /// CSV if its first line has a comma, whitespace-delimited otherwise
+    Auto,
+    /// A JSON object with `rows`, `cols` and `data` keys, or an array of rows
+    Json,
+    /// Comma-separated elements, one row per line
+    Csv,
+    /// Whitespace-delimited elements, one row per line
+    Text,
+}
+
+/// The algorithms `Matrix::mul_mat_with` can multiply by; all of them give the
+/// same product
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MulStrategy {
+    /// Each element on the current thread, as a row of `self` dotted with a
+    /// column of `rhs`
+    Sequential,
+    /// Bands of rows split across one thread per core
+    Parallel,
+    /// On the current thread, a block of each matrix at a time so the
+    /// elements being worked on stay in cache
+    Blocked,
+    /// Strassen's algorithm, which replaces one of the eight products of
+    /// quarter-size blocks with additions; the matrices are zero-padded to a
+    /// square that can be halved down to `STRASSEN_CUTOFF`
+    Strassen,
+}
+
+impl MulStrategy {
+    /// Picks the strategy `mul_mat` uses: on the current thread when the
+    /// product is too small for threads to pay off, otherwise in parallel
+    ///
+    /// # Arguments
+    /// * lhs - the "left" matrix in the multiplication
+    /// * rhs - the "right" matrix in the multiplication
+    ///
+    /// # Returns
+    /// Sequential or Parallel
+    pub fn choose(lhs: &Matrix, rhs: &Matrix) -> MulStrategy {
+        let work = lhs.rows.saturating_mul(lhs.cols).saturating_mul(rhs.cols);
+        if work < PARALLEL_MUL_WORK {
+            MulStrategy::Sequential
+        } else {
+            MulStrategy::Parallel
+        }
+    }
+}
+
+/// Several aggregates of a Matrix's elements, gathered in one pass by
+/// `Matrix::stats`
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct MatrixStats {
+    /// The smallest element
+    pub min: i32,
+    /// The largest element
+    pub max: i32,
+    /// The sum of the elements, which can't overflow
+    pub sum: i64,
+    /// The sum divided by the count
+    pub mean: f64,
+    /// The number of elements
+    pub count: usize,
+}
+
+/// What a Matrix can hold. Most of what Matrix provides, and all of
+/// Operation, is only for the default of i32; construction, indexing,
+/// iteration, transposing and serialization work for any Element, and
+/// addition, subtraction and multiplication for any Number
+pub trait Element: Copy + PartialEq + fmt::Debug + Display + Send + Sync {
+    /// The additive identity
+    const ZERO: Self;
+    /// The multiplicative identity
+    const ONE: Self;
+}
+
+/// The Elements with arithmetic, which a Matrix can add, subtract and
+/// multiply
+pub trait Number:
+    Element + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Sum
+{
+}
+
+impl<T: Element + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Sum> Number for T {}
+
+impl Element for i32 {
+    const ZERO: i32 = 0;
+    const ONE: i32 = 1;
+}
+
+impl Element for i64 {
+    const ZERO: i64 = 0;
+    const ONE: i64 = 1;
+}
+
+impl Element for f64 {
+    const ZERO: f64 = 0.0;
+    const ONE: f64 = 1.0;
+}
+
+/// Booleans, for adjacency and reachability, where OR takes the place of
+/// addition and AND of multiplication
+impl Element for bool {
+    const ZERO: bool = false;
+    const ONE: bool = true;
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(
+    try_from = "MatrixRepr<T>",
+    bound(deserialize = "T: Element + Deserialize<'de>")
+)]
+pub struct Matrix<T = i32> {
     rows: usize,
     cols: usize,
-    data: Vec<i32>,
+    data: Vec<T>,
+    layout: Layout,
 }
 
-impl Matrix {
+/// A Matrix as it is read from a serialized format, before its version is
+/// checked
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+pub(crate) struct MatrixRepr<T = i32> {
+    #[serde(default)]
+    pub(crate) version: u32,
+    pub(crate) rows: usize,
+    pub(crate) cols: usize,
+    // some tools call the flat array `values`; read either, write `data`
+    #[serde(alias = "values", deserialize_with = "deserialize_data")]
+    pub(crate) data: Vec<T>,
+    #[serde(default)]
+    pub(crate) layout: Layout,
+}
+
+impl MatrixRepr<serde_json::Number> {
+    /// Converts each element to the given type, failing on the first that
+    /// doesn't fit, and builds the Matrix
+    ///
+    /// # Arguments
+    /// * self - the matrix as it was read
+    /// * convert - gets an element as the new type, or None if it doesn't fit
+    ///
+    /// # Returns
+    /// the Matrix, or why it can't be built
+    fn convert<T: Element>(
+        self,
+        convert: impl Fn(&serde_json::Number) -> Option<T>,
+    ) -> Result<Matrix<T>, MatrixError> {
+        let data = self
+            .data
+            .iter()
+            .map(|num| {
+                convert(num)
+                    .ok_or_else(|| MatrixError::Format(format!("{} is too large for an i64", num)))
+            })
+            .collect::<Result<_, _>>()?;
+        Matrix::try_from(MatrixRepr {
+            version: self.version,
+            rows: self.rows,
+            cols: self.cols,
+            data,
+            layout: self.layout,
+        })
+        .map_err(MatrixError::Format)
+    }
+}
+
+/// Checks a serialized Matrix or Operation is in a format version that can be
+/// read
+///
+/// # Arguments
+/// * version - the version the file gives, or 0 if it has none
+///
+/// # Returns
+/// the reason the version can't be read, if it can't
+pub(crate) fn check_format_version(version: u32) -> Result<(), String> {
+    match version {
+        // version 1 only added the version field itself
+        0 | 1 => Ok(()),
+        _ => Err(format!(
+            "unsupported format version {}, expected at most {}",
+            version, FORMAT_VERSION
+        )),
+    }
+}
+
+thread_local! {
+    /// The most elements a Matrix deserialized on this thread may have, as set
+    /// by `with_element_limit`
+    static ELEMENT_LIMIT: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Runs a function with every Matrix deserialized on this thread while it runs
+/// limited to a number of elements; a matrix whose shape is larger fails to
+/// deserialize with TooManyElements, and data longer than the limit stops
+/// being read as soon as it passes it
+///
+/// # Arguments
+/// * limit - the most elements a Matrix may have, or None for no limit
+/// * f - the function to run, usually one reading an Operation or Matrix
+///
+/// # Returns
+/// what the function returns
+pub fn with_element_limit<T, F: FnOnce() -> T>(limit: Option<usize>, f: F) -> T {
+    /// Puts the previous limit back when dropped, even if `f` panics
+    struct Restore(Option<usize>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            ELEMENT_LIMIT.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let _restore = Restore(ELEMENT_LIMIT.with(|cell| cell.replace(limit)));
+    f()
+}
+
+/// Checks a matrix of the given size is within the limit set by
+/// `with_element_limit`, if there is one
+///
+/// # Arguments
+/// * rows - the number of rows
+/// * cols - the number of columns
+///
+/// # Returns
+/// TooManyElements if the matrix is over the limit
+fn check_element_limit(rows: usize, cols: usize) -> Result<(), MatrixError> {
+    match ELEMENT_LIMIT.with(Cell::get) {
+        Some(limit) if rows.checked_mul(cols).is_none_or(|len| len > limit) => {
+            Err(MatrixError::TooManyElements { rows, cols, limit })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reads the data of a serialized Matrix, failing as soon as it has more
+/// elements than the limit set by `with_element_limit`, so an oversized array
+/// is never held in memory
+///
+/// # Arguments
+/// * deserializer - where to read the array of elements from
+///
+/// # Returns
+/// the elements, or why they couldn't be read
+pub(crate) fn deserialize_data<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    struct DataVisitor<T> {
+        limit: Option<usize>,
+        element: PhantomData<T>,
+    }
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for DataVisitor<T> {
+        type Value = Vec<T>;
+
+        fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+            f.write_str("an array of elements")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+            let mut data = Vec::new();
+            while let Some(num) = seq.next_element()? {
+                if let Some(limit) = self.limit.filter(|&limit| data.len() == limit) {
+                    return Err(de::Error::custom(format!(
+                        "data has more than the limit of {} elements",
+                        limit
+                    )));
+                }
+                data.push(num);
+            }
+            Ok(data)
+        }
+    }
+
+    deserializer.deserialize_seq(DataVisitor {
+        limit: ELEMENT_LIMIT.with(Cell::get),
+        element: PhantomData,
+    })
+}
+
+impl<T: Element> Matrix<T> {
     /// Returns a matrix with the given size and elements
     ///
     /// # Arguments
@@ -20,14 +367,88 @@ impl Matrix {
     /// * `rows` - The number of rows of the matrix
     ///
     /// ```
-    /// use matrix::Matrix;
-    /// let matrix = Matrix::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
     /// ```
     ///
     /// Author: Matthew Krohn
-    pub fn new(cols: usize, rows: usize, data: Vec<i32>) -> Matrix {
-        assert_eq!(cols * rows, data.len());
-        Matrix { cols, rows, data }
+    pub fn new(cols: usize, rows: usize, data: Vec<T>) -> Matrix<T> {
+        match Matrix::try_new(cols, rows, data) {
+            Ok(matr) => matr,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Returns a matrix with the given size and elements, or an error instead
+    /// of panicking if the elements don't fit the size
+    ///
+    /// # Arguments
+    ///
+    /// * `cols` - The number of columns of the matrix
+    /// * `rows` - The number of rows of the matrix
+    /// * `data` - The elements, row by row
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// # use sample_code::error::MatrixError;
+    /// let err = Matrix::<i32>::try_new(usize::MAX, 2, vec![]).unwrap_err();
+    /// assert_eq!(err, MatrixError::DimensionOverflow { rows: 2, cols: usize::MAX });
+    /// ```
+    pub fn try_new(cols: usize, rows: usize, data: Vec<T>) -> Result<Matrix<T>, MatrixError> {
+        Self::check_data_len(cols, rows, data.len())?;
+        Ok(Matrix {
+            cols,
+            rows,
+            data,
+            layout: Layout::RowMajor,
+        })
+    }
+
+    /// Checks a matrix of the given size holds exactly `len` elements
+    ///
+    /// # Arguments
+    /// * cols - the number of columns
+    /// * rows - the number of rows
+    /// * len - the number of elements given
+    ///
+    /// # Returns
+    /// DimensionOverflow if the size can't be stored, or DataLength if `len`
+    /// is wrong
+    fn check_data_len(cols: usize, rows: usize, len: usize) -> Result<(), MatrixError> {
+        let expected = cols
+            .checked_mul(rows)
+            .ok_or(MatrixError::DimensionOverflow { rows, cols })?;
+        if expected != len {
+            return Err(MatrixError::DataLength {
+                expected,
+                found: len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the `n`x`n` identity matrix
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of rows and columns of the matrix
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::identity(2);
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![1, 0, 0, 1]));
+    /// ```
+    pub fn identity(n: usize) -> Matrix<T> {
+        let mut matr = Matrix {
+            cols: n,
+            rows: n,
+            data: vec![T::ZERO; n * n],
+            layout: Layout::RowMajor,
+        };
+        for i in 0..n {
+            matr[[i, i]] = T::ONE;
+        }
+        matr
     }
 
     /// Gets the numbr of columns in this Matrix
@@ -56,6 +477,91 @@ impl Matrix {
         self.rows
     }
 
+    /// Gets the order the elements of this Matrix are stored in
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// the Layout of this Matrix
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Returns a copy of this matrix with its elements stored in the given
+    /// layout; the logical elements are unchanged
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - The layout to store the copy in
+    ///
+    /// ```
+    /// # use sample_code::matrix::{Layout, Matrix};
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// let col_major = matrix.to_layout(Layout::ColMajor);
+    /// assert_eq!(col_major[[0, 1]], 2);
+    /// ```
+    pub fn to_layout(&self, layout: Layout) -> Matrix<T> {
+        if layout == self.layout {
+            return self.clone();
+        }
+        let data = match layout {
+            Layout::RowMajor => (0..self.rows)
+                .flat_map(|i| self.row_iter(i))
+                .copied()
+                .collect(),
+            Layout::ColMajor => (0..self.cols)
+                .flat_map(|j| self.col_iter(j))
+                .copied()
+                .collect(),
+        };
+
+        Matrix {
+            cols: self.cols,
+            rows: self.rows,
+            data,
+            layout,
+        }
+    }
+
+    /// Takes the matrix in row-major order, only copying its data when it is
+    /// stored some other way. Results are kept this way so their JSON reads
+    /// right even to a consumer that ignores `layout`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to take
+    ///
+    /// ```
+    /// # use sample_code::matrix::{Layout, Matrix};
+    /// let transposed = Matrix::new(2, 1, vec![1, 2]).transpose();
+    /// assert_eq!(transposed.layout(), Layout::ColMajor);
+    /// assert_eq!(transposed.into_row_major().layout(), Layout::RowMajor);
+    /// ```
+    pub fn into_row_major(self) -> Matrix<T> {
+        if self.layout.is_row_major() {
+            self
+        } else {
+            self.to_layout(Layout::RowMajor)
+        }
+    }
+
+    /// Gets the position in `data` of the element at the given row and column
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    /// * `row_num` - the row of the element
+    /// * `col_num` - the column of the element
+    ///
+    /// # Returns
+    /// the index into `data`
+    fn offset(&self, row_num: usize, col_num: usize) -> usize {
+        match self.layout {
+            Layout::RowMajor => row_num * self.cols + col_num,
+            Layout::ColMajor => col_num * self.rows + row_num,
+        }
+    }
+
     /// Returns an iterator of references to the items of the given row
     ///
     /// # Arguments
@@ -63,16 +569,23 @@ impl Matrix {
     /// * `row_num` - The row number to get an iterator for; is 0-indexed
     ///
     /// ```
-    /// use matrix::Matrix;
-    /// let matrix = Matrix::new(1, 4, vec![1, 2, 3, 4];
-    /// assert_eq!(matrix.row_iter(0).collect(), vec![&1, &2, &3, &4]);
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(4, 1, vec![1, 2, 3, 4]);
+    /// assert_eq!(matrix.row_iter(0).collect::<Vec<_>>(), vec![&1, &2, &3, &4]);
     /// ```
     ///
     /// Author: Matthew Krohn
-    pub fn row_iter(&self, row_num: usize) -> impl Iterator<Item = &i32> + '_ {
+    pub fn row_iter(&self, row_num: usize) -> impl Iterator<Item = &T> + '_ {
         assert!(row_num < self.rows, "Row index out of bounds");
-        let offset = self.cols * row_num;
-        self.data[offset..offset + self.cols].iter()
+        let step = match self.layout {
+            Layout::RowMajor => 1,
+            Layout::ColMajor => self.rows,
+        };
+        self.data
+            .iter()
+            .skip(self.offset(row_num, 0))
+            .step_by(step)
+            .take(self.cols)
     }
 
     /// Returns an iterator of references to the items of the given column
@@ -82,19 +595,29 @@ impl Matrix {
     /// * `col_num` - The column number to get an iterator for; is 0-indexed
     ///
     /// ```
-    /// use matrix::Matrix;
-    /// let matrix = Matrix::new(4, 1, vec![1, 2, 3, 4];
-    /// assert_eq!(matrix.col_iter(0).collect(), vec![&1, &2, &3, &4]);
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(1, 4, vec![1, 2, 3, 4]);
+    /// assert_eq!(matrix.col_iter(0).collect::<Vec<_>>(), vec![&1, &2, &3, &4]);
     /// ```
     ///
     /// Author: Matthew Krohn
-    pub fn col_iter(&self, col_num: usize) -> impl Iterator<Item = &i32> + '_ {
+    pub fn col_iter(&self, col_num: usize) -> impl Iterator<Item = &T> + '_ {
         assert!(col_num < self.cols, "Column index out of bounds");
-        self.data.iter().skip(col_num).step_by(self.cols)
+        let step = match self.layout {
+            Layout::RowMajor => self.cols,
+            Layout::ColMajor => 1,
+        };
+        self.data
+            .iter()
+            .skip(self.offset(0, col_num))
+            .step_by(step)
+            .take(self.rows)
     }
+}
 
-    /// Returns a new matrix that is the result of two compatible matrices being
-    /// multiplied
+impl<T: Number> Matrix<T> {
+    /// Multiplies two compatible matrices on the current thread only; gives
+    /// the same result as `mul_mat`
     ///
     /// # Arguments
     ///
@@ -102,40 +625,38 @@ impl Matrix {
     /// * `rhs` - The "right" matrix in the multiplication
     ///
     /// ```
+    /// # use sample_code::matrix::Matrix;
     /// let our_mat1 = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
     /// let our_mat2 = Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]);
-    /// let result_mat = Matrix::new(2, 2, vec![22, 28, 49, 64]);
-    ///
-    /// let new_mat = our_mat1.mul_mat(&our_mat2);
-    /// assert_eq!(new_mat, result_mat);
+    /// assert_eq!(our_mat1.mul_mat_sequential(&our_mat2), our_mat1.mul_mat(&our_mat2));
     /// ```
-    /// Author: Matthew Krohn
-    pub fn mul_mat(&self, rhs: &Matrix) -> Matrix {
+    pub fn mul_mat_sequential(&self, rhs: &Matrix<T>) -> Matrix<T> {
         assert_eq!(self.cols, rhs.rows);
-        let mut matr_data = vec![0; self.rows * rhs.cols];
+        let mut matr_data = vec![T::ZERO; self.rows * rhs.cols];
 
-        let parts = matr_data.chunks_exact_mut(rhs.cols).enumerate();
+        for (row_num, part) in matr_data.chunks_exact_mut(rhs.cols.max(1)).enumerate() {
+            self.mul_row(rhs, row_num, part);
+        }
 
-        // Concurrent matrix multiply
-        crossbeam::scope(|spawner| {
-            for (row_num, part) in parts {
-                spawner.spawn(move |_| {
-                    for (col_num, cell) in part.iter_mut().enumerate() {
-                        *cell = self
-                            .row_iter(row_num)
-                            .zip(rhs.col_iter(col_num))
-                            .map(|(lhs, rhs)| lhs + rhs)
-                            .sum();
-                    }
-                });
-            }
-        })
-        .unwrap();
+        Matrix::new(rhs.cols, self.rows, matr_data)
+    }
 
-        Matrix {
-            cols: rhs.cols,
-            rows: self.rows,
-            data: matr_data,
+    /// Fills one row of a product with the dot products of a row of `self` and
+    /// each column of `rhs`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    /// * `row_num` - The row of the product being filled
+    /// * `part` - The product's row to write into
+    fn mul_row(&self, rhs: &Matrix<T>, row_num: usize, part: &mut [T]) {
+        for (col_num, cell) in part.iter_mut().enumerate() {
+            *cell = self
+                .row_iter(row_num)
+                .zip(rhs.col_iter(col_num))
+                .map(|(&lhs, &rhs)| lhs * rhs)
+                .sum();
         }
     }
 
@@ -147,6 +668,7 @@ impl Matrix {
     /// * `rhs` - The "right" matrix in the addition
     ///
     /// ```
+    /// # use sample_code::matrix::Matrix;
     /// let our_mat1 = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
     /// let our_mat2 = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
     /// let result_mat = Matrix::new(3, 2, vec![2, 4, 6, 8, 10, 12]);
@@ -156,24 +678,32 @@ impl Matrix {
     /// ```
     ///
     /// Author: Kendric Thompson
-    pub fn add_mat(&self, rhs: &Matrix) -> Matrix {
-        assert_eq!(self.cols, rhs.cols);
-        assert_eq!(self.rows, rhs.rows);
-
-        let matr_data = self
-            .data
-            .iter()
-            .zip(rhs.data.iter())
-            .map(|(num1, num2)| num1 + num2)
-            .collect();
-
-        Matrix {
-            cols: self.cols,
-            rows: self.rows,
-            data: matr_data,
+    pub fn add_mat(&self, rhs: &Matrix<T>) -> Matrix<T> {
+        match self.try_add(rhs) {
+            Ok(matr) => matr,
+            Err(e) => panic!("{}", e),
         }
     }
 
+    /// Adds two matrices like `add_mat`, returning an error instead of
+    /// panicking if their shapes differ. Overflow isn't checked
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the addition
+    /// * `rhs` - The "right" matrix in the addition
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// # use sample_code::error::MatrixError;
+    /// let err = Matrix::new(2, 1, vec![1, 2]).try_add(&Matrix::new(1, 2, vec![1, 2]));
+    /// assert_eq!(err, Err(MatrixError::DimensionMismatch { expected: (1, 2), found: (2, 1) }));
+    /// ```
+    pub fn try_add(&self, rhs: &Matrix<T>) -> Result<Matrix<T>, MatrixError> {
+        Operator::Add.check_shapes((self.rows, self.cols), (rhs.rows, rhs.cols))?;
+        Ok(self.zip_map(rhs, |num1, num2| num1 + num2))
+    }
+
     /// Subtracts two matrices with the same dimensions
     ///
     /// # Arguments
@@ -182,291 +712,6100 @@ impl Matrix {
     /// * `rhs` - The "right" matrix in the subtraction
     ///
     /// ```
+    /// # use sample_code::matrix::Matrix;
     /// let our_mat1 = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
     /// let our_mat2 = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
     /// let result_mat = Matrix::new(3, 2, vec![0, 0, 0, 0, 0, 0]);
     ///
-    /// let new_mat = our_mat1.add_mat(&our_mat2);
+    /// let new_mat = our_mat1.sub_mat(&our_mat2);
     /// assert_eq!(new_mat, result_mat);
     /// ```
     ///
     /// Author: Kendric Thompson
-    pub fn sub_mat(&self, rhs: &Matrix) -> Matrix {
-        assert_eq!(self.cols, rhs.cols);
-        assert_eq!(self.rows, rhs.rows);
+    pub fn sub_mat(&self, rhs: &Matrix<T>) -> Matrix<T> {
+        match self.try_sub(rhs) {
+            Ok(matr) => matr,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Subtracts two matrices like `sub_mat`, returning an error instead of
+    /// panicking if their shapes differ. Overflow isn't checked
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the subtraction
+    /// * `rhs` - The "right" matrix in the subtraction
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let diff = Matrix::new(2, 1, vec![3, 2]).try_sub(&Matrix::new(2, 1, vec![1, 2]));
+    /// assert_eq!(diff, Ok(Matrix::new(2, 1, vec![2, 0])));
+    /// ```
+    pub fn try_sub(&self, rhs: &Matrix<T>) -> Result<Matrix<T>, MatrixError> {
+        Operator::Subtract.check_shapes((self.rows, self.cols), (rhs.rows, rhs.cols))?;
+        Ok(self.zip_map(rhs, |num1, num2| num1 - num2))
+    }
+}
+
+impl<T: Element> Matrix<T> {
+    /// Combines two matrices with the same dimensions element by element
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix, whose elements are the first argument to `f`
+    /// * `other` - The "right" matrix, whose elements are the second argument to `f`
+    /// * `f` - The function applied to each pair of corresponding elements
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(2, 1, vec![1, 5]);
+    /// let our_mat2 = Matrix::new(2, 1, vec![3, 4]);
+    ///
+    /// let new_mat = our_mat1.zip_map(&our_mat2, |a, b| a * b);
+    /// assert_eq!(new_mat, Matrix::new(2, 1, vec![3, 20]));
+    /// ```
+    pub fn zip_map<F: Fn(T, T) -> T>(&self, other: &Matrix<T>, f: F) -> Matrix<T> {
+        assert_eq!(self.cols, other.cols);
+        assert_eq!(self.rows, other.rows);
 
+        // the elements only line up if both are stored the same way
+        let other = other.to_layout(self.layout);
         let matr_data = self
             .data
             .iter()
-            .zip(rhs.data.iter())
-            .map(|(num1, num2)| num1 - num2)
+            .zip(other.data.iter())
+            .map(|(&num1, &num2)| f(num1, num2))
             .collect();
 
         Matrix {
             cols: self.cols,
             rows: self.rows,
             data: matr_data,
+            layout: self.layout,
         }
     }
-}
-
-// In Rust, traits are not normally documented since they are used for
-// interoperability between crates and operands
-impl Index<[usize; 2]> for Matrix {
-    type Output = i32;
 
-    /// Indexes into the Matrix
+    /// Returns the transpose of this matrix. The elements are not moved; the
+    /// copy just reads them in the other layout
     ///
     /// # Arguments
-    /// self - reference to this Matrix
-    /// index - index of spot to get (first index is row, second index is column)
     ///
-    /// # Return
-    /// Returns the value at index
+    /// * `self` - The matrix to transpose
     ///
-    /// Author: Matthew Krohn
-    fn index(&self, [row_idx, col_idx]: [usize; 2]) -> &i32 {
-        assert!(
-            row_idx < self.rows,
-            "Row index is greater than row dimension."
-        );
-        assert!(
-            col_idx < self.cols,
-            "Column index is greater than column dimension."
-        );
-        &self.data[row_idx * self.cols + col_idx]
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(our_mat.transpose(), Matrix::new(2, 3, vec![1, 4, 2, 5, 3, 6]));
+    /// ```
+    pub fn transpose(&self) -> Matrix<T> {
+        let layout = match self.layout {
+            Layout::RowMajor => Layout::ColMajor,
+            Layout::ColMajor => Layout::RowMajor,
+        };
+        Matrix {
+            cols: self.rows,
+            rows: self.cols,
+            data: self.data.clone(),
+            layout,
+        }
     }
-}
 
-impl IndexMut<[usize; 2]> for Matrix {
-    /// Indexes into the Matrix - mutable
+    /// Checks whether two matrices have the same number of rows and columns,
+    /// so they can be added or subtracted
     ///
     /// # Arguments
-    /// self - reference to this Matrix
-    /// index - index of spot to get (first index is row, second index is column)
+    /// * self - reference to this Matrix
+    /// * other - the Matrix to compare with
     ///
-    /// # Return
-    /// Returns a mutable reference to the value at index
+    /// # Returns
+    /// true if the shapes match
+    pub fn same_shape(&self, other: &Matrix<T>) -> bool {
+        self.rows == other.rows && self.cols == other.cols
+    }
+
+    /// Checks whether this matrix can be multiplied by another, which needs
+    /// as many columns in this matrix as rows in the other
     ///
-    /// Author: Matthew Krohn
-    fn index_mut(&mut self, [row_idx, col_idx]: [usize; 2]) -> &i32 {
-        assert!(
-            row_idx < self.rows,
-            "Row index is greater than row dimension."
-        );
-        assert!(
-            col_idx < self.cols,
-            "Column index is greater than column dimension."
-        );
-        &mut self.data[row_idx * self.cols + col_idx]
+    /// # Arguments
+    /// * self - reference to the "left" Matrix
+    /// * other - the "right" Matrix
+    ///
+    /// # Returns
+    /// true if `self.mul_mat(other)` is allowed
+    pub fn can_multiply(&self, other: &Matrix<T>) -> bool {
+        self.cols == other.rows
     }
-}
 
-impl Display for Matrix {
-    /// Formats the matrix for display
+    /// Copies the matrix out as a vector of rows
     ///
     /// # Arguments
     /// * self - reference to this Matrix
-    /// * f - reference to the formatter to write to
-    /// # Return
-    /// Returns success of write
     ///
-    /// Author: Matthew Krohn
-    fn fmt(&self, f: &mut Formatter) -> Result {
-        for row in self.data.chunks_exact[self.cols] {
-            for &i in row {
-                write!(f, "{: >6} ", i)?;
-            }
-            f.write_str("\n")?;
-        }
-        Ok(())
+    /// # Returns
+    /// each row as a vector
+    pub fn to_rows(&self) -> Vec<Vec<T>> {
+        (0..self.rows)
+            .map(|i| self.row_iter(i).copied().collect())
+            .collect()
     }
-}
 
-impl Mul<i32> for Matrix {
-    type Output = Self;
+    /// Copies the matrix out as a vector of columns
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// each column as a vector
+    pub fn to_columns(&self) -> Vec<Vec<T>> {
+        (0..self.cols)
+            .map(|j| self.col_iter(j).copied().collect())
+            .collect()
+    }
 
-    /// Multiplies the Matrix elements by the scalar
+    /// Gets the element at a row-major flat index, counting along each row in
+    /// turn, whatever the storage layout
     ///
     /// # Arguments
     ///
-    /// * `self` - The matrix
-    /// * `rhs` - The scalar to multiply by
+    /// * `self` - The matrix to read from
+    /// * `index` - The flat index; element `[r, c]` is at `r * cols + c`
     ///
-    /// Author: Jennifer Kulich
-    fn mul(mut self, rhs: i32) -> Self {
-        self.data.iter_mut().for_each(|num| *num *= rhs);
-        self
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// assert_eq!(matrix.get_flat(2), Some(&3));
+    /// assert_eq!(matrix.get_flat(4), None);
+    /// ```
+    pub fn get_flat(&self, index: usize) -> Option<&T> {
+        if index >= self.data.len() {
+            return None;
+        }
+        Some(&self.data[self.offset(index / self.cols, index % self.cols)])
     }
-}
 
-impl Mul<Matrix> for i32 {
-    type Output = Matrix;
+    /// Sets the element at a row-major flat index; the write counterpart of
+    /// `get_flat`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to write into
+    /// * `index` - The flat index; must be less than `rows * cols`
+    /// * `value` - The new element
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let mut matrix = Matrix::new(2, 2, vec![0; 4]);
+    /// matrix.set_flat(1, 5);
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![0, 5, 0, 0]));
+    /// ```
+    pub fn set_flat(&mut self, index: usize, value: T) {
+        assert!(index < self.data.len(), "Flat index out of bounds");
+        let offset = self.offset(index / self.cols, index % self.cols);
+        self.data[offset] = value;
+    }
 
-    /// Multiplies the Matrix elements by the scalar
+    /// Joins two matrices with the same number of rows side by side
     ///
     /// # Arguments
     ///
-    /// * `self` - The scalar to multiply by
-    /// * `rhs` - The matrix
+    /// * `self` - The matrix on the left
+    /// * `rhs` - The matrix on the right
     ///
-    /// Author: Jennifer Kulich
-    fn mul(self, rhs: Matrix) -> Matrix {
-        rhs * self
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(1, 2, vec![1, 2]);
+    /// let our_mat2 = Matrix::new(2, 2, vec![3, 4, 5, 6]);
+    /// assert_eq!(our_mat1.hconcat(&our_mat2), Matrix::new(3, 2, vec![1, 3, 4, 2, 5, 6]));
+    /// ```
+    pub fn hconcat(&self, rhs: &Matrix<T>) -> Matrix<T> {
+        assert_eq!(self.rows, rhs.rows);
+        let data = (0..self.rows)
+            .flat_map(|i| self.row_iter(i).chain(rhs.row_iter(i)).copied())
+            .collect();
+        Matrix::new(self.cols + rhs.cols, self.rows, data)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Stacks two matrices with the same number of columns, one above the other
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix on top
+    /// * `rhs` - The matrix underneath
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(2, 1, vec![1, 2]);
+    /// let our_mat2 = Matrix::new(2, 2, vec![3, 4, 5, 6]);
+    /// assert_eq!(our_mat1.vconcat(&our_mat2), Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]));
+    /// ```
+    pub fn vconcat(&self, rhs: &Matrix<T>) -> Matrix<T> {
+        assert_eq!(self.cols, rhs.cols);
+        let data = (0..self.rows)
+            .flat_map(|i| self.row_iter(i))
+            .chain((0..rhs.rows).flat_map(|i| rhs.row_iter(i)))
+            .copied()
+            .collect();
+        Matrix::new(self.cols, self.rows + rhs.rows, data)
+    }
 
-    /// Test matrix multiple
-    /// Author: Jennifer Kulich
-    #[test]
-    fn test_mul_mat_i32() {
-        let our_mat = Matrix {
-            rows: 3,
-            cols: 3,
-            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
-        };
-        let ref_mat = Matrix {
-            rows: 3,
-            cols: 3,
-            data: vec![3, 6, 9, 12, 15, 18, 21, 24, 27],
-        };
+    /// Returns the element at a row and column, or an error instead of
+    /// panicking like indexing does if it is outside the matrix
+    ///
+    /// # Arguments
+    ///
+    /// * `row_num` - The row of the element; is 0-indexed
+    /// * `col_num` - The column of the element; is 0-indexed
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 1, vec![1, 2]);
+    /// assert_eq!(matrix.try_get(0, 1), Ok(2));
+    /// assert!(matrix.try_get(1, 0).is_err());
+    /// ```
+    pub fn try_get(&self, row_num: usize, col_num: usize) -> Result<T, MatrixError> {
+        Ok(self.data[self.checked_offset(row_num, col_num)?])
+    }
 
-        let new_mat_1 = our_mat.clone() * 3; // need clone since Mul taKendrs by value
-        let new_mat_2 = 3 * our_mat;
-        assert_eq!(ref_mat, new_mat_1);
-        assert_eq!(ref_mat, new_mat_2);
+    /// Replaces the element at a row and column, or returns an error instead
+    /// of panicking like indexing does if it is outside the matrix
+    ///
+    /// # Arguments
+    ///
+    /// * `row_num` - The row of the element; is 0-indexed
+    /// * `col_num` - The column of the element; is 0-indexed
+    /// * `value` - The new element
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let mut matrix = Matrix::new(2, 1, vec![1, 2]);
+    /// matrix.try_set(0, 1, 5).unwrap();
+    /// assert_eq!(matrix, Matrix::new(2, 1, vec![1, 5]));
+    /// ```
+    pub fn try_set(&mut self, row_num: usize, col_num: usize, value: T) -> Result<(), MatrixError> {
+        let offset = self.checked_offset(row_num, col_num)?;
+        self.data[offset] = value;
+        Ok(())
     }
 
-    /// Test matrix row and column iterators
-    /// Author: Jennifer Kulich
-    #[test]
-    fn test_row_and_col_iter_small() {
-        let our_mat = Matrix {
-            cols: 3,
-            rows: 2,
-            data: vec![1, 2, 3, 4, 5, 6],
-        };
+    /// Returns the position in `data` of an element like `offset`, checking
+    /// it is inside the matrix
+    ///
+    /// # Arguments
+    ///
+    /// * `row_num` - The row of the element
+    /// * `col_num` - The column of the element
+    ///
+    /// # Returns
+    /// The position, or IndexOutOfBounds
+    fn checked_offset(&self, row_num: usize, col_num: usize) -> Result<usize, MatrixError> {
+        if row_num < self.rows && col_num < self.cols {
+            Ok(self.offset(row_num, col_num))
+        } else {
+            Err(MatrixError::IndexOutOfBounds {
+                row: row_num,
+                col: col_num,
+                rows: self.rows,
+                cols: self.cols,
+            })
+        }
+    }
 
-        let mut rows: Vec<Vec<&i32>> = Vec::new();
-        let mut cols: Vec<Vec<&i32>> = Vec::new();
-        for row in 0..our_mat.rows {
-            rows.push(our_mat.row_iter(row).collect());
+    /// Returns a matrix of the same shape with `f` applied to every element,
+    /// which may change the element type
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to map
+    /// * `f` - The function applied to each element
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 1, vec![1, 2]);
+    /// assert_eq!(matrix.map(f64::from), Matrix::new(2, 1, vec![1.0, 2.0]));
+    /// ```
+    pub fn map<U: Element, F: Fn(T) -> U>(&self, f: F) -> Matrix<U> {
+        Matrix {
+            cols: self.cols,
+            rows: self.rows,
+            data: self.data.iter().map(|&num| f(num)).collect(),
+            layout: self.layout,
         }
-        for col in 0..our_mat.cols {
-            cols.push(our_mat.col_iter(col).collect());
+    }
+}
+
+impl Matrix {
+    /// Builds a matrix from the first `rows * cols` items of an iterator, row
+    /// by row, without collecting them into a Vec first
+    ///
+    /// # Arguments
+    ///
+    /// * `cols` - The number of columns
+    /// * `rows` - The number of rows
+    /// * `iter` - The elements; any after the first `rows * cols` are left
+    ///   unread, so it may be endless
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_iter_shaped(2, 2, (1..).map(|num| num * num)).unwrap();
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![1, 4, 9, 16]));
+    /// ```
+    ///
+    /// # Returns
+    /// The matrix, DimensionOverflow if the size can't be stored, or
+    /// DataLength if the iterator ends early
+    pub fn from_iter_shaped(
+        cols: usize,
+        rows: usize,
+        iter: impl Iterator<Item = i32>,
+    ) -> Result<Matrix, MatrixError> {
+        let len = cols
+            .checked_mul(rows)
+            .ok_or(MatrixError::DimensionOverflow { rows, cols })?;
+        // nothing is reserved up front, so a huge shape with a short iterator
+        // is DataLength instead of a failed allocation
+        Matrix::try_new(cols, rows, iter.take(len).collect())
+    }
+
+    /// Returns the `n`x`n` exchange matrix, with 1s on the anti-diagonal and
+    /// 0s elsewhere; multiplying by it reverses the order of the rows or
+    /// columns
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of rows and columns of the matrix
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::exchange(2);
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![0, 1, 1, 0]));
+    /// ```
+    pub fn exchange(n: usize) -> Matrix {
+        let mut matr = Matrix::new(n, n, vec![0; n * n]);
+        for i in 0..n {
+            matr[[i, n - 1 - i]] = 1;
         }
+        matr
+    }
 
-        assert_eq!(rows[0], vec!(&1, &2, &3));
-        assert_eq!(rows[1], vec!(&4, &5, &6));
+    /// Returns a matrix filled row by row with consecutive integers, starting
+    /// from `start`
+    ///
+    /// # Arguments
+    ///
+    /// * `cols` - The number of columns of the matrix
+    /// * `rows` - The number of rows of the matrix
+    /// * `start` - The first element
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_range(3, 2, 1);
+    /// assert_eq!(matrix, Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]));
+    /// ```
+    pub fn from_range(cols: usize, rows: usize, start: i32) -> Matrix {
+        let data = (start..).take(cols * rows).collect();
+        Matrix::new(cols, rows, data)
+    }
 
-        assert_eq!(cols[0], vec!(&1, &4));
-        assert_eq!(cols[1], vec!(&2, &5));
-        assert_eq!(cols[2], vec!(&3, &6));
+    /// Returns `I + perturbation`, the `n`x`n` identity with a perturbation added
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of rows and columns of the matrix
+    /// * `perturbation` - The `n`x`n` matrix to add to the identity
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let perturbation = Matrix::new(2, 2, vec![0, 3, -1, 2]);
+    /// let matrix = Matrix::identity_plus(2, &perturbation).unwrap();
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![1, 3, -1, 3]));
+    /// ```
+    pub fn identity_plus(n: usize, perturbation: &Matrix) -> Result<Matrix, MatrixError> {
+        if perturbation.rows != n || perturbation.cols != n {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (n, n),
+                found: (perturbation.rows, perturbation.cols),
+            });
+        }
+        Ok(Matrix::identity(n).add_mat(perturbation))
     }
 
-    /// Test Matrix index
-    /// Author: Jennifer Kulich
-    #[test]
-    fn test_index() {
-        let our_mat = Matrix {
-            cols: 3,
-            rows: 3,
-            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
-        };
-        assert_eq!(our_mat[[0, 0]], 1);
-        assert_eq!(our_mat[[0, 1]], 2);
-        assert_eq!(our_mat[[0, 2]], 3);
-        assert_eq!(our_mat[[1, 0]], 4);
-        assert_eq!(our_mat[[1, 1]], 5);
-        assert_eq!(our_mat[[1, 2]], 6);
-        assert_eq!(our_mat[[2, 0]], 7);
-        assert_eq!(our_mat[[2, 1]], 8);
-        assert_eq!(our_mat[[2, 2]], 9);
+    /// Reads a matrix from whitespace-delimited text, one row per line; the
+    /// number of columns comes from the first line and blank lines are skipped
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The reader to take the text from
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_text_reader("1 2\n3\t4\n".as_bytes()).unwrap();
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![1, 2, 3, 4]));
+    /// ```
+    pub fn from_text_reader<R: Read>(r: R) -> Result<Matrix, MatrixError> {
+        Matrix::from_delimited(r, None)
     }
 
-    /// Test mutation index
-    /// Author: Jennifer Kulich
-    #[test]
-    fn test_index_mut() {
-        let mut our_mat = Matrix {
-            cols: 1,
-            rows: 1,
-            data: vec![1],
-        };
-        our_mat[[0, 0]] = 2;
-        assert_eq!(our_mat[[0, 0]], 2);
+    /// Reads a matrix from CSV, one row per line, as written by `write_csv`;
+    /// the number of columns comes from the first line and blank lines are
+    /// skipped
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The reader to take the CSV from
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_csv_reader("1,2\n3, 4\n".as_bytes()).unwrap();
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![1, 2, 3, 4]));
+    /// ```
+    pub fn from_csv_reader<R: Read>(r: R) -> Result<Matrix, MatrixError> {
+        Matrix::from_delimited(r, Some(','))
     }
 
-    /// Test Matrix Multiply
-    /// Author: Jennifer Kulich
-    #[test]
-    fn test_matrix_multiply() {
-        let our_mat1 = Matrix {
-            cols: 3,
-            rows: 2,
-            data: vec![1, 2, 3, 4, 5, 6],
-        };
-        let our_mat2 = Matrix {
-            cols: 2,
-            rows: 3,
-            data: vec![1, 2, 3, 4, 5, 6],
-        };
-        let result_mat = Matrix {
-            cols: 2,
-            rows: 2,
-            data: vec![22, 28, 49, 64],
-        };
-        let new_mat = our_mat1.mul_mat(&our_mat2);
-        assert_eq!(new_mat, result_mat);
+    /// Reads a matrix from text, one row per line, with the elements split on
+    /// a delimiter or on whitespace
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The reader to take the text from
+    /// * `delimiter` - What separates the elements; None for any whitespace
+    fn from_delimited<R: Read>(r: R, delimiter: Option<char>) -> Result<Matrix, MatrixError> {
+        let mut data = Vec::new();
+        let mut cols = 0;
+        let mut rows = 0;
+
+        for (line_num, line) in BufReader::new(r).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let tokens: Vec<&str> = match delimiter {
+                Some(delimiter) => line.split(delimiter).map(str::trim).collect(),
+                None => line.split_whitespace().collect(),
+            };
+            let row = tokens
+                .into_iter()
+                .map(|token| {
+                    token.parse::<i32>().map_err(|e| MatrixError::Parse {
+                        line: line_num + 1,
+                        message: format!("{:?}: {}", token, e),
+                    })
+                })
+                .collect::<Result<Vec<i32>, MatrixError>>()?;
+
+            if rows == 0 {
+                cols = row.len();
+            } else if row.len() != cols {
+                return Err(MatrixError::RaggedRow {
+                    line: line_num + 1,
+                    expected: cols,
+                    found: row.len(),
+                });
+            }
+            data.extend(row);
+            rows += 1;
+        }
+
+        Ok(Matrix::new(cols, rows, data))
     }
 
-    // Author: Kendric Thompson
-    // This tests the adding of two matrices
-    #[test]
-    fn test_matrix_addition() {
-        let our_mat1 = Matrix {
-            cols: 3,
-            rows: 2,
-            data: vec![1, 2, 3, 4, 5, 6],
-        };
-        let our_mat2 = Matrix {
-            cols: 3,
-            rows: 2,
-            data: vec![1, 2, 3, 4, 5, 6],
+    /// Builds a matrix from a vector of rows
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - The rows of the matrix; every row must be the same length
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+    /// assert_eq!(matrix, Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]));
+    /// ```
+    pub fn from_rows(rows: Vec<Vec<i32>>) -> Result<Matrix, MatrixError> {
+        let count = rows.len();
+        let (len, data) = Matrix::flatten_equal_lengths(rows)?;
+        Ok(Matrix::new(len, count, data))
+    }
+
+    /// Builds a matrix from a vector of columns
+    ///
+    /// # Arguments
+    ///
+    /// * `cols` - The columns of the matrix; every column must be the same length
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_columns(vec![vec![1, 4], vec![2, 5], vec![3, 6]]).unwrap();
+    /// assert_eq!(matrix, Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]));
+    /// ```
+    pub fn from_columns(cols: Vec<Vec<i32>>) -> Result<Matrix, MatrixError> {
+        let count = cols.len();
+        let (len, data) = Matrix::flatten_equal_lengths(cols)?;
+        Ok(Matrix {
+            cols: count,
+            rows: len,
+            data,
+            layout: Layout::ColMajor,
+        }
+        .to_layout(Layout::RowMajor))
+    }
+
+    /// Joins vectors that must all be the same length into one
+    ///
+    /// # Arguments
+    /// * vectors - the vectors to join
+    ///
+    /// # Returns
+    /// the length of each vector and the joined elements, or RaggedRow
+    /// numbering the first vector (from 1) with a different length
+    fn flatten_equal_lengths(vectors: Vec<Vec<i32>>) -> Result<(usize, Vec<i32>), MatrixError> {
+        let len = vectors.first().map_or(0, Vec::len);
+        let mut data = Vec::with_capacity(len * vectors.len());
+        for (i, vector) in vectors.into_iter().enumerate() {
+            if vector.len() != len {
+                return Err(MatrixError::RaggedRow {
+                    line: i + 1,
+                    expected: len,
+                    found: vector.len(),
+                });
+            }
+            data.extend(vector);
+        }
+        Ok((len, data))
+    }
+
+    /// Builds a block diagonal matrix, with each block placed below and to the
+    /// right of the one before and zeros everywhere else
+    ///
+    /// # Arguments
+    ///
+    /// * `blocks` - The blocks, from top left to bottom right; they don't have
+    ///   to be square
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let block1 = Matrix::new(1, 1, vec![1]);
+    /// let block2 = Matrix::new(1, 1, vec![2]);
+    /// let matrix = Matrix::block_diagonal(&[&block1, &block2]);
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![1, 0, 0, 2]));
+    /// ```
+    pub fn block_diagonal(blocks: &[&Matrix]) -> Matrix {
+        let rows = blocks.iter().map(|block| block.rows).sum();
+        let cols = blocks.iter().map(|block| block.cols).sum();
+        let mut matr = Matrix::new(cols, rows, vec![0; rows * cols]);
+
+        let (mut row_start, mut col_start) = (0, 0);
+        for block in blocks {
+            matr.set_submatrix(row_start, col_start, block);
+            row_start += block.rows;
+            col_start += block.cols;
+        }
+        matr
+    }
+
+    /// Builds a Toeplitz matrix, where every diagonal running down and to the
+    /// right holds a single value, from its first column and first row
+    ///
+    /// # Arguments
+    ///
+    /// * `first_col` - The first column, top to bottom; sets the number of rows
+    /// * `first_row` - The first row, left to right; sets the number of columns.
+    ///   Its first element must match the first element of `first_col`
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::toeplitz(&[1, 2, 3], &[1, 4]).unwrap();
+    /// assert_eq!(matrix, Matrix::new(2, 3, vec![1, 4, 2, 1, 3, 2]));
+    /// ```
+    ///
+    /// # Returns
+    /// The matrix, or CornerMismatch if the column and row start differently
+    pub fn toeplitz(first_col: &[i32], first_row: &[i32]) -> Result<Matrix, MatrixError> {
+        if let (Some(&col_start), Some(&row_start)) = (first_col.first(), first_row.first()) {
+            if col_start != row_start {
+                return Err(MatrixError::CornerMismatch {
+                    first_col: col_start,
+                    first_row: row_start,
+                });
+            }
+        }
+
+        let (rows, cols) = (first_col.len(), first_row.len());
+        let data = (0..rows)
+            .flat_map(|i| {
+                (0..cols).map(move |j| {
+                    if i >= j {
+                        first_col[i - j]
+                    } else {
+                        first_row[j - i]
+                    }
+                })
+            })
+            .collect();
+        Matrix::try_new(cols, rows, data)
+    }
+
+    /// Builds a square circulant matrix, where each row is the row above it
+    /// rotated one place to the right
+    ///
+    /// # Arguments
+    ///
+    /// * `first_row` - The first row; its length sets the size of the matrix
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::circulant(&[1, 2]);
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![1, 2, 2, 1]));
+    /// ```
+    pub fn circulant(first_row: &[i32]) -> Matrix {
+        let n = first_row.len();
+        let data = (0..n)
+            .flat_map(|i| (0..n).map(move |j| first_row[(j + n - i) % n]))
+            .collect();
+        Matrix::new(n, n, data)
+    }
+
+    /// Parses the compact `RxC:elements` form of a matrix, where `R` and `C`
+    /// are the number of rows and columns and the elements are separated by
+    /// commas, row by row. A single trailing comma is allowed, but an empty
+    /// element anywhere else is a Parse error
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The compact matrix; must have exactly `R * C` elements
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_compact("2x3:1,2,3,4,5,6").unwrap();
+    /// assert_eq!(matrix, Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]));
+    /// ```
+    pub fn from_compact(s: &str) -> Result<Matrix, MatrixError> {
+        let invalid = || MatrixError::Format(format!("expected RxC:elements, found {:?}", s));
+        let (shape, elements) = s.split_once(':').ok_or_else(invalid)?;
+        let (rows, cols) = shape.split_once('x').ok_or_else(invalid)?;
+        let rows = rows.trim().parse::<usize>().map_err(|_| invalid())?;
+        let cols = cols.trim().parse::<usize>().map_err(|_| invalid())?;
+
+        let elements = elements.trim();
+        if elements.is_empty() {
+            return Matrix::try_new(cols, rows, Vec::new());
+        }
+        let elements = elements.strip_suffix(',').unwrap_or(elements);
+        let data = elements
+            .split(',')
+            .map(str::trim)
+            .map(|token| {
+                token.parse::<i32>().map_err(|e| MatrixError::Parse {
+                    line: 1,
+                    message: format!("{:?}: {}", token, e),
+                })
+            })
+            .collect::<Result<Vec<i32>, MatrixError>>()?;
+        Matrix::try_new(cols, rows, data)
+    }
+
+    /// Builds a 0/1 matrix from an ASCII picture, where `#` is 1 and a space or
+    /// `.` is 0; each line is a row, and empty lines are skipped
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The picture; every line must be the same length
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_ascii_art("#.\n.#").unwrap();
+    /// assert_eq!(matrix, Matrix::identity(2));
+    /// ```
+    pub fn from_ascii_art(s: &str) -> Result<Matrix, MatrixError> {
+        let mut data = Vec::new();
+        let mut cols = 0;
+        let mut rows = 0;
+
+        for (line_num, line) in s.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let row = line
+                .chars()
+                .map(|c| match c {
+                    '#' => Ok(1),
+                    ' ' | '.' => Ok(0),
+                    _ => Err(MatrixError::Parse {
+                        line: line_num + 1,
+                        message: format!("{:?} is not '#', '.' or ' '", c),
+                    }),
+                })
+                .collect::<Result<Vec<i32>, MatrixError>>()?;
+
+            if rows == 0 {
+                cols = row.len();
+            } else if row.len() != cols {
+                return Err(MatrixError::RaggedRow {
+                    line: line_num + 1,
+                    expected: cols,
+                    found: row.len(),
+                });
+            }
+            data.extend(row);
+            rows += 1;
+        }
+
+        Ok(Matrix::new(cols, rows, data))
+    }
+
+    /// Reads a 2D NumPy `.npy` array of little-endian 32 bit integers, as
+    /// written by `numpy.save` for an array with dtype `<i4`. Arrays saved in
+    /// Fortran order keep their column-major layout
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The `.npy` file contents
+    ///
+    /// ```no_run
+    /// # use sample_code::matrix::Matrix;
+    /// # use std::fs::File;
+    /// let file = File::open("matrix.npy").unwrap();
+    /// let matrix = Matrix::from_npy(file).unwrap();
+    /// ```
+    pub fn from_npy<R: Read>(mut r: R) -> Result<Matrix, MatrixError> {
+        let mut preamble = [0; 8];
+        r.read_exact(&mut preamble)?;
+        if &preamble[..6] != b"\x93NUMPY" {
+            return Err(MatrixError::Format("not a .npy file".to_string()));
+        }
+        let header_len = match preamble[6] {
+            1 => {
+                let mut len = [0; 2];
+                r.read_exact(&mut len)?;
+                usize::from(u16::from_le_bytes(len))
+            }
+            2 | 3 => {
+                let mut len = [0; 4];
+                r.read_exact(&mut len)?;
+                u32::from_le_bytes(len) as usize
+            }
+            version => {
+                return Err(MatrixError::Format(format!(
+                    "unsupported .npy version {}",
+                    version
+                )))
+            }
         };
-        let result_mat = Matrix {
-            cols: 3,
-            rows: 2,
-            data: vec![2, 4, 6, 8, 10, 12],
+        let mut header = vec![0; header_len];
+        r.read_exact(&mut header)?;
+        let header = String::from_utf8_lossy(&header);
+
+        // the header is a Python dict literal, like
+        // {'descr': '<i4', 'fortran_order': False, 'shape': (2, 3), }
+        let field = |key: &str| -> Result<&str, MatrixError> {
+            let missing = || MatrixError::Format(format!(".npy header has no {}", key));
+            let start = header.find(&format!("'{}':", key)).ok_or_else(missing)? + key.len() + 3;
+            let value = header[start..].trim_start();
+            let end = match value.chars().next() {
+                Some('\'') => value[1..].find('\'').map(|end| end + 2),
+                Some('(') => value.find(')').map(|end| end + 1),
+                _ => value.find([',', '}']),
+            };
+            Ok(value[..end.ok_or_else(missing)?].trim())
         };
-        let new_mat = our_mat1.add_mat(&our_mat2);
-        assert_eq!(new_mat, result_mat);
-    }
 
-    // Author: Kendric Thompson
-    // This tests the subbtracting of two matrices
-    #[test]
-    fn test_matrix_subtraction() {
-        let our_mat1 = Matrix {
-            cols: 3,
-            rows: 2,
-            data: vec![1, 2, 3, 4, 5, 6],
+        let descr = field("descr")?;
+        if descr != "'<i4'" {
+            return Err(MatrixError::Format(format!(
+                "expected dtype '<i4', found {}",
+                descr
+            )));
+        }
+        let layout = match field("fortran_order")? {
+            "False" => Layout::RowMajor,
+            "True" => Layout::ColMajor,
+            other => {
+                return Err(MatrixError::Format(format!(
+                    "invalid fortran_order {}",
+                    other
+                )))
+            }
         };
-        let our_mat2 = Matrix {
-            cols: 3,
-            rows: 2,
-            data: vec![1, 2, 3, 4, 5, 6],
+        let shape = field("shape")?;
+        let dims = shape
+            .trim_matches(['(', ')'])
+            .split(',')
+            .map(str::trim)
+            .filter(|dim| !dim.is_empty())
+            .map(|dim| dim.parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|e| MatrixError::Format(format!("invalid shape {}: {}", shape, e)))?;
+        let (rows, cols) = match dims[..] {
+            [rows, cols] => (rows, cols),
+            _ => {
+                return Err(MatrixError::Format(format!(
+                    "expected a 2D array, found shape {}",
+                    shape
+                )))
+            }
         };
-        let result_mat = Matrix {
-            cols: 3,
-            rows: 2,
-            data: vec![0, 0, 0, 0, 0, 0],
+
+        // the shape comes from the file, so check it before trusting it with
+        // an allocation, and only hold as many bytes as are really there
+        check_element_limit(rows, cols)?;
+        let len = rows
+            .checked_mul(cols)
+            .and_then(|len| len.checked_mul(4))
+            .ok_or(MatrixError::DimensionOverflow { rows, cols })?;
+        let mut bytes = Vec::new();
+        r.take(len as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let data = bytes
+            .chunks_exact(4)
+            .map(|num| i32::from_le_bytes([num[0], num[1], num[2], num[3]]))
+            .collect();
+
+        Ok(Matrix {
+            cols,
+            rows,
+            data,
+            layout,
+        })
+    }
+
+    /// Returns the companion matrix of a monic polynomial, whose eigenvalues
+    /// are the roots of the polynomial
+    ///
+    /// The first row holds the negated coefficients and the subdiagonal is all
+    /// ones, so `x^n + a1 x^(n-1) + ... + an` becomes an `n`x`n` matrix with
+    /// first row `[-a1, ..., -an]`
+    ///
+    /// # Arguments
+    ///
+    /// * `coeffs` - The coefficients from highest to lowest degree, starting with
+    ///   the leading 1; must describe a polynomial of at least degree 1
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// // x^2 - 3x + 2
+    /// let matrix = Matrix::companion(&[1, -3, 2]);
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![3, -2, 1, 0]));
+    /// ```
+    pub fn companion(coeffs: &[i32]) -> Matrix {
+        assert!(coeffs.len() >= 2, "Polynomial must be at least degree 1");
+        assert_eq!(coeffs[0], 1, "Polynomial must be monic");
+
+        let size = coeffs.len() - 1;
+        let mut matr = Matrix {
+            cols: size,
+            rows: size,
+            data: vec![0; size * size],
+            layout: Layout::RowMajor,
         };
-        let new_mat = our_mat1.sub_mat(&our_mat2);
-        assert_eq!(new_mat, result_mat);
+        for (col_num, coeff) in coeffs[1..].iter().enumerate() {
+            matr[[0, col_num]] = -coeff;
+        }
+        for row_num in 1..size {
+            matr[[row_num, row_num - 1]] = 1;
+        }
+        matr
+    }
+
+    /// Returns the Vandermonde matrix of the given points, where row `i` is
+    /// `[1, x_i, x_i^2, ..., x_i^degree]`
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The `x` values, one per row
+    /// * `degree` - The highest power, so the matrix has `degree + 1` columns
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::vandermonde(&[2, 3], 2);
+    /// assert_eq!(matrix, Matrix::new(3, 2, vec![1, 2, 4, 1, 3, 9]));
+    /// ```
+    pub fn vandermonde(points: &[i32], degree: usize) -> Matrix {
+        let data = points
+            .iter()
+            .flat_map(|&x| (0..=degree as u32).map(move |power| x.pow(power)))
+            .collect();
+        Matrix::new(degree + 1, points.len(), data)
+    }
+
+    /// Gets the trace (sum of the main diagonal) of a square Matrix
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// the sum of the diagonal elements, widened to avoid overflow
+    pub fn trace(&self) -> i64 {
+        assert_eq!(self.rows, self.cols, "Trace requires a square matrix");
+        (0..self.rows).map(|i| i64::from(self[[i, i]])).sum()
+    }
+
+    /// Gets the trace of `self * rhs` without computing the whole product,
+    /// since only its diagonal is needed
+    ///
+    /// # Arguments
+    /// * self - the "left" matrix in the multiplication
+    /// * rhs - the "right" matrix in the multiplication, whose shape must be
+    ///   the transpose of `self`'s so the product is square
+    ///
+    /// # Returns
+    /// the sum of the product's diagonal elements, widened to avoid overflow
+    pub fn trace_of_product(&self, rhs: &Matrix) -> i64 {
+        assert_eq!(self.cols, rhs.rows);
+        assert_eq!(self.rows, rhs.cols, "Product must be a square matrix");
+        (0..self.rows)
+            .map(|i| {
+                self.row_iter(i)
+                    .zip(rhs.col_iter(i))
+                    .map(|(&lhs, &rhs)| i64::from(lhs) * i64::from(rhs))
+                    .sum::<i64>()
+            })
+            .sum()
+    }
+
+    /// Gets the determinant of a square Matrix exactly, by Bareiss'
+    /// fraction-free elimination. Like LU decomposition it is O(n^3), but every
+    /// division is exact, so it stays in integers
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// the determinant, widened to avoid overflow
+    ///
+    /// # Panics
+    /// If the Matrix isn't square, or the determinant doesn't fit in an i64;
+    /// `try_determinant` returns an error instead
+    pub fn determinant(&self) -> i64 {
+        assert_eq!(self.rows, self.cols, "Determinant requires a square matrix");
+        match self.try_determinant() {
+            Ok(det) => det,
+            Err(_) => panic!("Determinant is too large for an i64"),
+        }
+    }
+
+    /// Gets the determinant of a square Matrix exactly like `determinant`,
+    /// returning an error instead of panicking
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// ```
+    /// # use sample_code::error::MatrixError;
+    /// # use sample_code::matrix::Matrix;
+    /// assert_eq!(Matrix::new(2, 2, vec![1, 2, 3, 4]).try_determinant(), Ok(-2));
+    /// let huge = Matrix::new(3, 3, vec![i32::MAX, 0, 0, 0, i32::MAX, 0, 0, 0, i32::MAX]);
+    /// assert_eq!(huge.try_determinant(), Err(MatrixError::Overflow { row: 0, col: 0 }));
+    /// ```
+    ///
+    /// # Returns
+    /// the determinant; a DimensionMismatch if the Matrix isn't square, or
+    /// Overflow if the determinant, or a step of working it out, is too large
+    pub fn try_determinant(&self) -> Result<i64, MatrixError> {
+        if self.rows != self.cols {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (self.rows, self.rows),
+                found: (self.rows, self.cols),
+            });
+        }
+        self.bareiss_determinant()
+            .and_then(|det| i64::try_from(det).ok())
+            .ok_or(MatrixError::Overflow { row: 0, col: 0 })
+    }
+
+    /// Works out the determinant of a square Matrix by Bareiss' elimination
+    /// in i128
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix, which must be square
+    ///
+    /// # Returns
+    /// the determinant, or None if a step of the elimination overflows an i128
+    fn bareiss_determinant(&self) -> Option<i128> {
+        let n = self.rows;
+        if n == 0 {
+            return Some(1);
+        }
+        let mut work: Vec<i128> = (0..n)
+            .flat_map(|i| self.row_iter(i).map(|&num| i128::from(num)))
+            .collect();
+
+        let mut sign = 1;
+        let mut prev_pivot = 1;
+        for k in 0..n - 1 {
+            if work[k * n + k] == 0 {
+                match (k + 1..n).find(|&i| work[i * n + k] != 0) {
+                    Some(pivot_row) => {
+                        for j in 0..n {
+                            work.swap(k * n + j, pivot_row * n + j);
+                        }
+                        sign = -sign;
+                    }
+                    None => return Some(0),
+                }
+            }
+            let pivot = work[k * n + k];
+            for i in k + 1..n {
+                for j in k + 1..n {
+                    // each entry becomes a (k + 2)-square minor, divided by
+                    // the previous pivot, which always divides it
+                    let cross = work[i * n + j]
+                        .checked_mul(pivot)?
+                        .checked_sub(work[i * n + k].checked_mul(work[k * n + j])?)?;
+                    work[i * n + j] = cross / prev_pivot;
+                }
+            }
+            prev_pivot = pivot;
+        }
+
+        Some(sign * work[n * n - 1])
+    }
+
+    /// Gets the determinant of a square Matrix by LU decomposition with partial
+    /// pivoting, which is also O(n^3) but, unlike `determinant`, can't overflow
+    ///
+    /// The elimination is done in floating point, so the result can be off by
+    /// rounding error. For large matrices the row updates of each elimination
+    /// step run concurrently
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// the determinant
+    pub fn determinant_lu(&self) -> f64 {
+        self.lu_determinant(self.rows >= PARALLEL_LU_SIZE)
+    }
+
+    /// Gets the sign of the determinant, for when only the orientation matters.
+    /// The sign is exact whenever the fraction-free elimination behind
+    /// `determinant` fits in an i128, even if the determinant is too large for
+    /// the i64 `determinant` returns. Past that it falls back to the sign of
+    /// `determinant_lu`, which rounding can get wrong for a nearly singular
+    /// matrix
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// -1, 0 or 1, or None if the Matrix isn't square
+    pub fn determinant_sign(&self) -> Option<i32> {
+        if self.rows != self.cols {
+            return None;
+        }
+        if let Some(det) = self.bareiss_determinant() {
+            return Some(det.signum() as i32);
+        }
+        // the determinant of an integer matrix is an integer, so anything
+        // smaller than a half is rounding error on a singular matrix
+        let det = self.lu_determinant(self.rows >= PARALLEL_LU_SIZE);
+        if det.abs() < 0.5 {
+            Some(0)
+        } else {
+            Some(det.signum() as i32)
+        }
+    }
+
+    /// Gets the inverse of a square Matrix exactly, when it is also an integer
+    /// Matrix. That's only the case when the determinant is 1 or -1; use
+    /// `inverse_float` for the fractional inverse of any other invertible
+    /// Matrix
+    ///
+    /// The inverse is found by fraction-free Gauss-Jordan elimination of
+    /// `[self | I]` in i128, which leaves `d * I` on the left and `d * self⁻¹`
+    /// on the right, where `d` is plus or minus the determinant
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to invert
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![2, 1, 1, 1]);
+    /// assert_eq!(matrix.inverse(), Ok(Matrix::new(2, 2, vec![1, -1, -1, 2])));
+    /// ```
+    ///
+    /// # Returns
+    /// The inverse; a DimensionMismatch if the Matrix isn't square, Singular
+    /// if it has no inverse, FractionalInverse if the inverse isn't all whole
+    /// numbers, or Overflow if an element doesn't fit in an i32
+    pub fn inverse(&self) -> Result<Matrix, MatrixError> {
+        if self.rows != self.cols {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (self.rows, self.rows),
+                found: (self.rows, self.cols),
+            });
+        }
+        let n = self.rows;
+        let width = 2 * n;
+        let mut work: Vec<i128> = (0..n)
+            .flat_map(|i| {
+                let identity_row = (0..n).map(move |j| i128::from(i == j));
+                self.row_iter(i)
+                    .map(|&num| i128::from(num))
+                    .chain(identity_row)
+            })
+            .collect();
+
+        let mut prev_pivot = 1;
+        for k in 0..n {
+            let pivot_row = (k..n)
+                .find(|&i| work[i * width + k] != 0)
+                .ok_or(MatrixError::Singular)?;
+            if pivot_row != k {
+                for j in 0..width {
+                    work.swap(k * width + j, pivot_row * width + j);
+                }
+            }
+            let pivot = work[k * width + k];
+            for i in (0..n).filter(|&i| i != k) {
+                let factor = work[i * width + k];
+                for j in 0..width {
+                    let cross = pivot
+                        .checked_mul(work[i * width + j])
+                        .zip(factor.checked_mul(work[k * width + j]))
+                        .and_then(|(lhs, rhs)| lhs.checked_sub(rhs))
+                        .ok_or(MatrixError::Overflow { row: i, col: j % n })?;
+                    work[i * width + j] = cross / prev_pivot;
+                }
+            }
+            prev_pivot = pivot;
+        }
+
+        let mut data = Vec::with_capacity(n * n);
+        for i in 0..n {
+            for j in 0..n {
+                let scaled = work[i * width + n + j];
+                if scaled % prev_pivot != 0 {
+                    return Err(MatrixError::FractionalInverse);
+                }
+                let elem = i32::try_from(scaled / prev_pivot)
+                    .map_err(|_| MatrixError::Overflow { row: i, col: j })?;
+                data.push(elem);
+            }
+        }
+        Ok(Matrix::new(n, n, data))
+    }
+
+    /// Gets the inverse of a square Matrix in floating point, by Gauss-Jordan
+    /// elimination with partial pivoting, so fractional elements are kept
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to invert
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// # use sample_code::float_matrix::FloatMatrix;
+    /// let matrix = Matrix::new(2, 2, vec![2, 0, 0, 4]);
+    /// let inverse = matrix.inverse_float().unwrap();
+    /// assert_eq!(inverse, FloatMatrix::new(2, 2, vec![0.5, 0.0, 0.0, 0.25]));
+    /// ```
+    ///
+    /// # Returns
+    /// The inverse; a DimensionMismatch if the Matrix isn't square, or
+    /// Singular if it has no inverse
+    pub fn inverse_float(&self) -> Result<FloatMatrix, MatrixError> {
+        if self.rows != self.cols {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (self.rows, self.rows),
+                found: (self.rows, self.cols),
+            });
+        }
+        FloatMatrix::from(self)
+            .inverse()
+            .ok_or(MatrixError::Singular)
+    }
+
+    /// Gets the determinant of a square Matrix by LU decomposition, choosing
+    /// whether to update rows concurrently
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    /// * parallel - whether each elimination step's row updates run concurrently
+    ///
+    /// # Returns
+    /// the determinant
+    fn lu_determinant(&self, parallel: bool) -> f64 {
+        assert_eq!(self.rows, self.cols, "Determinant requires a square matrix");
+        let n = self.rows;
+        let mut lu: Vec<f64> = (0..n)
+            .flat_map(|i| self.row_iter(i).map(|&num| f64::from(num)))
+            .collect();
+        let threads = std::thread::available_parallelism().map_or(1, |count| count.get());
+
+        let mut det = 1.0;
+        for k in 0..n {
+            // pivot on the largest remaining element in this column
+            let pivot_row = (k..n)
+                .max_by(|&a, &b| lu[a * n + k].abs().total_cmp(&lu[b * n + k].abs()))
+                .unwrap();
+            if lu[pivot_row * n + k] == 0.0 {
+                return 0.0;
+            }
+            if pivot_row != k {
+                for j in 0..n {
+                    lu.swap(k * n + j, pivot_row * n + j);
+                }
+                det = -det;
+            }
+            det *= lu[k * n + k];
+
+            let (upper, lower) = lu.split_at_mut((k + 1) * n);
+            let pivot = &upper[k * n..];
+            let eliminate = |rows: &mut [f64]| {
+                for row in rows.chunks_exact_mut(n) {
+                    let factor = row[k] / pivot[k];
+                    for j in k..n {
+                        row[j] -= factor * pivot[j];
+                    }
+                }
+            };
+
+            let remaining = n - k - 1;
+            if parallel && threads > 1 && remaining > 1 {
+                let rows_per_thread = remaining.div_ceil(threads);
+                crossbeam::scope(|spawner| {
+                    for rows in lower.chunks_mut(rows_per_thread * n) {
+                        spawner.spawn(move |_| eliminate(rows));
+                    }
+                })
+                .unwrap();
+            } else {
+                eliminate(lower);
+            }
+        }
+        det
+    }
+
+    /// Returns a new matrix that is the result of two compatible matrices being
+    /// multiplied
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+    /// let our_mat2 = Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]);
+    /// let result_mat = Matrix::new(2, 2, vec![22, 28, 49, 64]);
+    ///
+    /// let new_mat = our_mat1.mul_mat(&our_mat2);
+    /// assert_eq!(new_mat, result_mat);
+    /// ```
+    /// Author: Matthew Krohn
+    pub fn mul_mat(&self, rhs: &Matrix) -> Matrix {
+        self.mul_mat_with(rhs, MulStrategy::choose(self, rhs))
+    }
+
+    /// Multiplies two matrices like `mul_mat`, returning an error instead of
+    /// panicking if they can't be multiplied, or of wrapping if an element of
+    /// the product doesn't fit in an i32
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// # use sample_code::error::MatrixError;
+    /// let err = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]).try_mul(&Matrix::identity(2));
+    /// assert_eq!(err, Err(MatrixError::DimensionMismatch { expected: (3, 2), found: (2, 2) }));
+    /// ```
+    ///
+    /// # Returns
+    /// The product, DimensionMismatch, or Overflow giving the first element
+    /// that doesn't fit
+    pub fn try_mul(&self, rhs: &Matrix) -> Result<Matrix, MatrixError> {
+        Operator::Multiply.check_shapes((self.rows, self.cols), (rhs.rows, rhs.cols))?;
+        let threads = match MulStrategy::choose(self, rhs) {
+            MulStrategy::Parallel => {
+                std::thread::available_parallelism().map_or(1, |count| count.get())
+            }
+            _ => 1,
+        };
+        self.checked_mul_mat(rhs, threads)
+    }
+
+    /// Multiplies two compatible matrices with the given algorithm; every
+    /// strategy gives the same result as `mul_mat`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    /// * `strategy` - The algorithm to multiply with
+    ///
+    /// ```
+    /// # use sample_code::matrix::{Matrix, MulStrategy};
+    /// let our_mat1 = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+    /// let our_mat2 = Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]);
+    /// let product = our_mat1.mul_mat_with(&our_mat2, MulStrategy::Strassen);
+    /// assert_eq!(product, Matrix::new(2, 2, vec![22, 28, 49, 64]));
+    /// ```
+    pub fn mul_mat_with(&self, rhs: &Matrix, strategy: MulStrategy) -> Matrix {
+        if let Err(e) =
+            Operator::Multiply.check_shapes((self.rows, self.cols), (rhs.rows, rhs.cols))
+        {
+            panic!("{}", e);
+        }
+        match strategy {
+            MulStrategy::Sequential => self.mul_mat_sequential(rhs),
+            MulStrategy::Parallel => {
+                let threads = std::thread::available_parallelism().map_or(1, |count| count.get());
+                self.mul_mat_threads(rhs, threads)
+            }
+            MulStrategy::Blocked => self.mul_mat_blocked(rhs),
+            MulStrategy::Strassen => self.mul_mat_strassen(rhs),
+        }
+    }
+
+    /// Multiplies two compatible matrices on the current thread, a
+    /// `MUL_BLOCK`-sized block of each at a time
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    fn mul_mat_blocked(&self, rhs: &Matrix) -> Matrix {
+        let (lhs, rhs) = (
+            self.to_layout(Layout::RowMajor),
+            rhs.to_layout(Layout::RowMajor),
+        );
+        let (rows, inner, cols) = (lhs.rows, lhs.cols, rhs.cols);
+        let mut matr_data = vec![0; rows * cols];
+
+        for row_block in (0..rows).step_by(MUL_BLOCK) {
+            for inner_block in (0..inner).step_by(MUL_BLOCK) {
+                for col_block in (0..cols).step_by(MUL_BLOCK) {
+                    let col_end = (col_block + MUL_BLOCK).min(cols);
+                    for i in row_block..(row_block + MUL_BLOCK).min(rows) {
+                        for k in inner_block..(inner_block + MUL_BLOCK).min(inner) {
+                            let lhs_elem = lhs.data[i * inner + k];
+                            let rhs_row = &rhs.data[k * cols + col_block..k * cols + col_end];
+                            let out_row = &mut matr_data[i * cols + col_block..i * cols + col_end];
+                            for (out, &rhs_elem) in out_row.iter_mut().zip(rhs_row) {
+                                *out += lhs_elem * rhs_elem;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Matrix::new(cols, rows, matr_data)
+    }
+
+    /// Multiplies two compatible matrices by Strassen's algorithm, zero-padding
+    /// both to a square whose side halves evenly down to `STRASSEN_CUTOFF`.
+    /// Intermediate sums wrap, so the product matches `mul_mat` whenever its
+    /// elements fit in an i32
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    fn mul_mat_strassen(&self, rhs: &Matrix) -> Matrix {
+        let size = self.rows.max(self.cols).max(rhs.cols);
+        let (mut side, mut halvings) = (size, 0);
+        while side > STRASSEN_CUTOFF {
+            side = side.div_ceil(2);
+            halvings += 1;
+        }
+        let side = side << halvings;
+
+        let pad = |matr: &Matrix| {
+            let mut padded = vec![0; side * side];
+            for i in 0..matr.rows {
+                for (j, &elem) in matr.row_iter(i).enumerate() {
+                    padded[i * side + j] = elem;
+                }
+            }
+            padded
+        };
+        let product = strassen_square(&pad(self), &pad(rhs), side);
+
+        let matr_data = (0..self.rows)
+            .flat_map(|i| product[i * side..i * side + rhs.cols].iter().copied())
+            .collect();
+        Matrix::new(rhs.cols, self.rows, matr_data)
+    }
+
+    /// Multiplies two compatible matrices, splitting the rows of the product
+    /// into one band per thread; gives the same result as `mul_mat`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    /// * `threads` - How many threads to use; 1 or 0 multiplies on the current
+    ///   thread, like `mul_mat_sequential`
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+    /// let our_mat2 = Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(our_mat1.mul_mat_threads(&our_mat2, 2), our_mat1.mul_mat(&our_mat2));
+    /// ```
+    pub fn mul_mat_threads(&self, rhs: &Matrix, threads: usize) -> Matrix {
+        if threads <= 1 || self.rows < 2 || rhs.cols == 0 {
+            return self.mul_mat_sequential(rhs);
+        }
+        assert_eq!(self.cols, rhs.rows);
+        let mut matr_data = vec![0; self.rows * rhs.cols];
+
+        let band_rows = self.rows.div_ceil(threads);
+        crossbeam::scope(|spawner| {
+            for (band_num, band) in matr_data.chunks_mut(band_rows * rhs.cols).enumerate() {
+                spawner.spawn(move |_| {
+                    for (offset, part) in band.chunks_exact_mut(rhs.cols).enumerate() {
+                        self.mul_row(rhs, band_num * band_rows + offset, part);
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        Matrix::new(rhs.cols, self.rows, matr_data)
+    }
+
+    /// Multiplies two compatible matrices like `mul_mat_threads`, but returns
+    /// an error instead of wrapping when an element of the product doesn't
+    /// fit in an i32
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    /// * `threads` - How many threads to use; 1 or 0 multiplies on the current
+    ///   thread
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(1, 1, vec![i32::MAX]);
+    /// let our_mat2 = Matrix::new(1, 1, vec![2]);
+    /// assert!(our_mat1.checked_mul_mat(&our_mat2, 1).is_err());
+    /// ```
+    ///
+    /// # Returns
+    /// The product, or Overflow giving the first element, in row order, that
+    /// doesn't fit
+    pub fn checked_mul_mat(&self, rhs: &Matrix, threads: usize) -> Result<Matrix, MatrixError> {
+        assert_eq!(self.cols, rhs.rows);
+        let mut matr_data = vec![0; self.rows * rhs.cols];
+
+        if threads <= 1 || self.rows < 2 || rhs.cols == 0 {
+            for (row_num, part) in matr_data.chunks_exact_mut(rhs.cols.max(1)).enumerate() {
+                self.checked_mul_row(rhs, row_num, part)?;
+            }
+        } else {
+            let band_rows = self.rows.div_ceil(threads);
+            crossbeam::scope(|spawner| {
+                let workers: Vec<_> = matr_data
+                    .chunks_mut(band_rows * rhs.cols)
+                    .enumerate()
+                    .map(|(band_num, band)| {
+                        spawner.spawn(move |_| -> Result<(), MatrixError> {
+                            for (offset, part) in band.chunks_exact_mut(rhs.cols).enumerate() {
+                                self.checked_mul_row(rhs, band_num * band_rows + offset, part)?;
+                            }
+                            Ok(())
+                        })
+                    })
+                    .collect();
+                // the bands are in row order, so the first error is the first overflow
+                workers
+                    .into_iter()
+                    .try_for_each(|worker| worker.join().unwrap())
+            })
+            .unwrap()?;
+        }
+
+        Ok(Matrix::new(rhs.cols, self.rows, matr_data))
+    }
+
+    /// Fills one row of a product like `mul_row`, summing in an i64 so only a
+    /// result that doesn't fit in an i32 is an overflow
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    /// * `row_num` - The row of the product being filled
+    /// * `part` - The product's row to write into
+    ///
+    /// # Returns
+    /// Ok, or Overflow giving the first element of the row that doesn't fit
+    fn checked_mul_row(
+        &self,
+        rhs: &Matrix,
+        row_num: usize,
+        part: &mut [i32],
+    ) -> Result<(), MatrixError> {
+        for (col_num, cell) in part.iter_mut().enumerate() {
+            let overflow = MatrixError::Overflow {
+                row: row_num,
+                col: col_num,
+            };
+            let dot = self
+                .row_iter(row_num)
+                .zip(rhs.col_iter(col_num))
+                .try_fold(0i64, |acc, (&lhs, &rhs)| {
+                    acc.checked_add(i64::from(lhs) * i64::from(rhs))
+                })
+                .ok_or_else(|| overflow.clone())?;
+            *cell = i32::try_from(dot).map_err(|_| overflow)?;
+        }
+        Ok(())
+    }
+
+    /// Divides two matrices with the same dimensions element by element,
+    /// truncating toward zero, without panicking on a zero divisor
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix, holding the dividends
+    /// * `rhs` - The "right" matrix, holding the divisors
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(2, 1, vec![7, -9]);
+    /// let our_mat2 = Matrix::new(2, 1, vec![2, 3]);
+    /// assert_eq!(our_mat1.try_div_mat(&our_mat2), Ok(Matrix::new(2, 1, vec![3, -3])));
+    /// ```
+    pub fn try_div_mat(&self, rhs: &Matrix) -> Result<Matrix, MatrixError> {
+        if self.rows != rhs.rows || self.cols != rhs.cols {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (self.rows, self.cols),
+                found: (rhs.rows, rhs.cols),
+            });
+        }
+
+        let mut data = Vec::with_capacity(self.data.len());
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let divisor = rhs[[row, col]];
+                if divisor == 0 {
+                    return Err(MatrixError::DivisionByZero { row, col });
+                }
+                data.push(self[[row, col]] / divisor);
+            }
+        }
+        Ok(Matrix::new(self.cols, self.rows, data))
+    }
+
+    /// Writes the matrix as CSV, one line per row
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to write
+    /// * `writer` - Where to write the CSV text
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// let mut out = Vec::new();
+    /// matrix.write_csv(&mut out).unwrap();
+    /// assert_eq!(out, b"1,2\n3,4\n");
+    /// ```
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for row_num in 0..self.rows {
+            let line: Vec<String> = self.row_iter(row_num).map(|num| num.to_string()).collect();
+            writeln!(writer, "{}", line.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the matrix as bincode, through the same `Serialize` impl as
+    /// JSON, so it can be read back with `from_bincode_reader`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to write
+    /// * `writer` - Where to write the bytes
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// let mut out = Vec::new();
+    /// matrix.write_bincode(&mut out).unwrap();
+    /// assert_eq!(Matrix::from_bincode_reader(&out[..]), Ok(matrix));
+    /// ```
+    pub fn write_bincode<W: Write>(&self, writer: W) -> io::Result<()> {
+        bincode::serialize_into(writer, self).map_err(|e| match *e {
+            bincode::ErrorKind::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        })
+    }
+
+    /// Reads a matrix written by `write_bincode`
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The reader to take the bincode from
+    ///
+    /// # Returns
+    /// The matrix, or Format if it isn't a matrix this version can read
+    pub fn from_bincode_reader<R: Read>(r: R) -> Result<Matrix, MatrixError> {
+        let repr: MatrixRepr =
+            bincode::deserialize_from(r).map_err(|e| MatrixError::Format(e.to_string()))?;
+        Matrix::try_from(repr).map_err(MatrixError::Format)
+    }
+
+    /// Formats the matrix like `Display`, with the trace and determinant added
+    /// as a footer when the matrix is square. A determinant too large for an
+    /// i64 is shown as its floating point estimate from `determinant_lu`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to format
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// assert!(matrix.display_with_summary().ends_with("Trace: 5\nDeterminant: -2\n"));
+    /// ```
+    pub fn display_with_summary(&self) -> String {
+        let mut output = self.to_string();
+        if self.rows == self.cols {
+            output.push_str(&format!("Trace: {}\n", self.trace()));
+            match self.try_determinant() {
+                Ok(det) => output.push_str(&format!("Determinant: {}\n", det)),
+                Err(_) => output.push_str(&format!(
+                    "Determinant: about {:e} (too large for an i64)\n",
+                    self.determinant_lu()
+                )),
+            }
+        }
+        output
+    }
+
+    /// Formats the matrix like `Display`, with a header line giving its shape
+    /// as rows by columns
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to format
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]);
+    /// assert!(matrix.display_with_header().starts_with("Matrix 3x2:\n"));
+    /// ```
+    pub fn display_with_header(&self) -> String {
+        format!("Matrix {}x{}:\n{}", self.rows, self.cols, self)
+    }
+
+    /// Returns a copy of the matrix mirrored left to right, reversing the order
+    /// of the elements within each row
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to flip
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(3, 1, vec![1, 2, 3]);
+    /// assert_eq!(matrix.flip_horizontal(), Matrix::new(3, 1, vec![3, 2, 1]));
+    /// ```
+    pub fn flip_horizontal(&self) -> Matrix {
+        let data = (0..self.rows)
+            .flat_map(|i| (0..self.cols).rev().map(move |j| self[[i, j]]))
+            .collect();
+        Matrix::new(self.cols, self.rows, data)
+    }
+
+    /// Returns a copy of the matrix mirrored top to bottom, reversing the order
+    /// of the rows
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to flip
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(1, 3, vec![1, 2, 3]);
+    /// assert_eq!(matrix.flip_vertical(), Matrix::new(1, 3, vec![3, 2, 1]));
+    /// ```
+    pub fn flip_vertical(&self) -> Matrix {
+        let data = (0..self.rows)
+            .rev()
+            .flat_map(|i| self.row_iter(i).copied())
+            .collect();
+        Matrix::new(self.cols, self.rows, data)
+    }
+
+    /// Returns a copy of the matrix rotated a quarter turn clockwise; an `r`x`c`
+    /// matrix becomes `c`x`r`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to rotate
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// assert_eq!(matrix.rotate_90_cw(), Matrix::new(2, 2, vec![3, 1, 4, 2]));
+    /// ```
+    pub fn rotate_90_cw(&self) -> Matrix {
+        // each new row is an old column, read from the bottom up
+        let data = (0..self.cols)
+            .flat_map(|j| (0..self.rows).rev().map(move |i| self[[i, j]]))
+            .collect();
+        Matrix::new(self.rows, self.cols, data)
+    }
+
+    /// Returns a copy of the matrix rotated a quarter turn counter-clockwise; an
+    /// `r`x`c` matrix becomes `c`x`r`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to rotate
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// assert_eq!(matrix.rotate_90_ccw(), Matrix::new(2, 2, vec![2, 4, 1, 3]));
+    /// ```
+    pub fn rotate_90_ccw(&self) -> Matrix {
+        // each new row is an old column, starting from the rightmost
+        let data = (0..self.cols)
+            .rev()
+            .flat_map(|j| self.col_iter(j).copied())
+            .collect();
+        Matrix::new(self.rows, self.cols, data)
+    }
+
+    /// Returns a copy of the matrix with its rows rotated cyclically; row `i`
+    /// moves to row `i + by`, and rows pushed off one end wrap around to the
+    /// other. A negative `by` shifts upwards
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to shift
+    /// * `by` - How many rows to shift down by
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(1, 3, vec![1, 2, 3]);
+    /// assert_eq!(matrix.shift_rows(1), Matrix::new(1, 3, vec![3, 1, 2]));
+    /// assert_eq!(matrix.shift_rows(-1), Matrix::new(1, 3, vec![2, 3, 1]));
+    /// ```
+    pub fn shift_rows(&self, by: isize) -> Matrix {
+        let start = cyclic_start(self.rows, by);
+        let data = (0..self.rows)
+            .flat_map(|i| self.row_iter((start + i) % self.rows).copied())
+            .collect();
+        Matrix::new(self.cols, self.rows, data)
+    }
+
+    /// Returns a copy of the matrix with its columns rotated cyclically; column
+    /// `j` moves to column `j + by`, and columns pushed off one end wrap around
+    /// to the other. A negative `by` shifts to the left
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to shift
+    /// * `by` - How many columns to shift right by
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(3, 1, vec![1, 2, 3]);
+    /// assert_eq!(matrix.shift_cols(1), Matrix::new(3, 1, vec![3, 1, 2]));
+    /// ```
+    pub fn shift_cols(&self, by: isize) -> Matrix {
+        let start = cyclic_start(self.cols, by);
+        let data = (0..self.rows)
+            .flat_map(|i| (0..self.cols).map(move |j| self[[i, (start + j) % self.cols]]))
+            .collect();
+        Matrix::new(self.cols, self.rows, data)
+    }
+
+    /// Counts how many times each distinct value appears in the matrix
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to count the elements of
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 2, 1]);
+    /// let counts = matrix.histogram();
+    /// assert_eq!(counts[&1], 2);
+    /// assert_eq!(counts[&2], 2);
+    /// ```
+    pub fn histogram(&self) -> BTreeMap<i32, usize> {
+        let mut counts = BTreeMap::new();
+        for &num in &self.data {
+            *counts.entry(num).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the anti-diagonal of a square matrix, from the top-right element
+    /// to the bottom-left
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to take the anti-diagonal of
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// assert_eq!(matrix.anti_diagonal(), vec![2, 3]);
+    /// ```
+    pub fn anti_diagonal(&self) -> Vec<i32> {
+        assert_eq!(
+            self.rows, self.cols,
+            "Anti-diagonal requires a square matrix"
+        );
+        (0..self.rows)
+            .map(|i| self[[i, self.cols - 1 - i]])
+            .collect()
+    }
+
+    /// Multiplies the Matrix elements by the scalar, clamping to `i32::MIN` or
+    /// `i32::MAX` instead of overflowing
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix
+    /// * `scalar` - The scalar to multiply by
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 1, vec![i32::MAX, -2]);
+    /// let new_mat = matrix.saturating_mul_scalar(2);
+    /// assert_eq!(new_mat, Matrix::new(2, 1, vec![i32::MAX, -4]));
+    /// ```
+    pub fn saturating_mul_scalar(&self, scalar: i32) -> Matrix {
+        let mut matr = self.clone();
+        matr.data
+            .iter_mut()
+            .for_each(|num| *num = num.saturating_mul(scalar));
+        matr
+    }
+
+    /// Checks whether the matrix is orthogonal, meaning `AᵀA` is the identity,
+    /// comparing in floating point within `epsilon`; non-square matrices are
+    /// never orthogonal
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to check
+    /// * `epsilon` - How far each element of `AᵀA` may be from the identity's
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let rotation = Matrix::new(2, 2, vec![0, -1, 1, 0]);
+    /// assert!(rotation.is_orthogonal(1e-9));
+    /// ```
+    pub fn is_orthogonal(&self, epsilon: f64) -> bool {
+        if self.rows != self.cols {
+            return false;
+        }
+        // element [i, j] of AᵀA is the dot product of columns i and j
+        (0..self.cols).all(|i| {
+            (0..self.cols).all(|j| {
+                let dot: f64 = self
+                    .col_iter(i)
+                    .zip(self.col_iter(j))
+                    .map(|(&lhs, &rhs)| f64::from(lhs) * f64::from(rhs))
+                    .sum();
+                let expected = if i == j { 1.0 } else { 0.0 };
+                (dot - expected).abs() <= epsilon
+            })
+        })
+    }
+
+    /// Linearly rescales the elements from their current `[min, max]` into
+    /// `[new_min, new_max]`
+    ///
+    /// The arithmetic is done on i128s, wide enough that scaling the full i32
+    /// range can't overflow, so each scaled value is truncated
+    /// toward zero relative to `new_min` (rounded down when `new_max >= new_min`).
+    /// If every element is equal they all map to `new_min`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to rescale
+    /// * `new_min` - The value the smallest element maps to
+    /// * `new_max` - The value the largest element maps to
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(3, 1, vec![0, 5, 10]);
+    /// assert_eq!(matrix.scale_to_range(0, 100), Matrix::new(3, 1, vec![0, 50, 100]));
+    /// ```
+    pub fn scale_to_range(&self, new_min: i32, new_max: i32) -> Matrix {
+        let mut matr = self.clone();
+        let (min, max) = match (self.data.iter().min(), self.data.iter().max()) {
+            (Some(&min), Some(&max)) => (i128::from(min), i128::from(max)),
+            _ => return matr,
+        };
+        let old_range = max - min;
+        let new_range = i128::from(new_max) - i128::from(new_min);
+
+        matr.data.iter_mut().for_each(|num| {
+            let offset = if old_range == 0 {
+                0
+            } else {
+                (i128::from(*num) - min) * new_range / old_range
+            };
+            // the offset is at most new_range, so this lands between new_min and new_max
+            *num = (i128::from(new_min) + offset) as i32;
+        });
+        matr
+    }
+
+    /// Reads a matrix from JSON, checking the number of elements matches the
+    /// shape
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The JSON text, with `rows`, `cols` and `data` keys
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_json_str(r#"{"rows": 1, "cols": 2, "data": [1, 2]}"#).unwrap();
+    /// assert_eq!(matrix, Matrix::new(2, 1, vec![1, 2]));
+    /// ```
+    pub fn from_json_str(s: &str) -> Result<Matrix, MatrixError> {
+        // the shape is checked on the repr so it fails with TooManyElements
+        // or DataLength rather than a Format error
+        let repr: MatrixRepr =
+            serde_json::from_str(s).map_err(|e| MatrixError::Format(e.to_string()))?;
+        check_element_limit(repr.rows, repr.cols)?;
+        Self::check_data_len(repr.cols, repr.rows, repr.data.len())?;
+        Matrix::try_from(repr).map_err(MatrixError::Format)
+    }
+
+    /// Reads a matrix in JSON, CSV or whitespace-delimited text, either as
+    /// hinted or worked out from the text itself
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The reader to take the text from
+    /// * `hint` - The format to read, or FormatHint::Auto to sniff it
+    ///
+    /// ```
+    /// # use sample_code::matrix::{FormatHint, Matrix};
+    /// let matrix = Matrix::from_reader_auto("[[1, 2], [3, 4]]".as_bytes(), FormatHint::Auto).unwrap();
+    /// assert_eq!(matrix, Matrix::new(2, 2, vec![1, 2, 3, 4]));
+    /// ```
+    pub fn from_reader_auto<R: Read>(mut r: R, hint: FormatHint) -> Result<Matrix, MatrixError> {
+        let mut text = String::new();
+        r.read_to_string(&mut text)?;
+
+        let format = match hint {
+            FormatHint::Auto => {
+                let trimmed = text.trim_start();
+                if trimmed.starts_with(['{', '[']) {
+                    FormatHint::Json
+                } else if trimmed
+                    .lines()
+                    .next()
+                    .is_some_and(|line| line.contains(','))
+                {
+                    FormatHint::Csv
+                } else {
+                    FormatHint::Text
+                }
+            }
+            hint => hint,
+        };
+
+        match format {
+            FormatHint::Json if text.trim_start().starts_with('[') => {
+                let rows: Vec<Vec<i32>> =
+                    serde_json::from_str(&text).map_err(|e| MatrixError::Format(e.to_string()))?;
+                Matrix::from_rows(rows)
+            }
+            FormatHint::Json => Matrix::from_json_str(&text),
+            FormatHint::Csv => Matrix::from_csv_reader(text.as_bytes()),
+            FormatHint::Text | FormatHint::Auto => Matrix::from_text_reader(text.as_bytes()),
+        }
+    }
+
+    /// Serializes the matrix as TOML
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to serialize
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 1, vec![1, 2]);
+    /// assert_eq!(matrix.to_toml().unwrap(), "version = 1\nrows = 1\ncols = 2\ndata = [1, 2]\n");
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> Result<String, MatrixError> {
+        toml::to_string(self).map_err(|e| MatrixError::Format(e.to_string()))
+    }
+
+    /// Reads a matrix from TOML
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The TOML text, with `rows`, `cols` and `data` keys
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::from_toml("rows = 1\ncols = 2\ndata = [1, 2]").unwrap();
+    /// assert_eq!(matrix, Matrix::new(2, 1, vec![1, 2]));
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_toml(s: &str) -> Result<Matrix, MatrixError> {
+        toml::from_str(s).map_err(|e| MatrixError::Format(e.to_string()))
+    }
+
+    /// Surrounds the matrix with borders of `fill`, with a separate width for
+    /// each side
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to pad
+    /// * `top` - The number of rows to add above
+    /// * `bottom` - The number of rows to add below
+    /// * `left` - The number of columns to add on the left
+    /// * `right` - The number of columns to add on the right
+    /// * `fill` - The value of every added element
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(1, 1, vec![5]);
+    /// let padded = matrix.pad(0, 1, 1, 0, 0);
+    /// assert_eq!(padded, Matrix::new(2, 2, vec![0, 5, 0, 0]));
+    /// ```
+    pub fn pad(&self, top: usize, bottom: usize, left: usize, right: usize, fill: i32) -> Matrix {
+        let cols = left + self.cols + right;
+        let rows = top + self.rows + bottom;
+        let mut matr = Matrix::new(cols, rows, vec![fill; cols * rows]);
+        for row_num in 0..self.rows {
+            for (col_num, &num) in self.row_iter(row_num).enumerate() {
+                matr[[top + row_num, left + col_num]] = num;
+            }
+        }
+        matr
+    }
+
+    /// Slides a smaller, odd-sized kernel over the matrix and sums the products
+    /// at each position where it fits entirely (a "valid" 2D cross-correlation,
+    /// so the kernel is not flipped and there is no padding)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to convolve
+    /// * `kernel` - The kernel, with an odd number of rows and columns
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    /// let kernel = Matrix::new(3, 3, vec![0, 0, 0, 0, 2, 0, 0, 0, 0]);
+    /// assert_eq!(matrix.convolve(&kernel), Matrix::new(1, 1, vec![10]));
+    /// ```
+    pub fn convolve(&self, kernel: &Matrix) -> Matrix {
+        assert!(
+            kernel.rows % 2 == 1 && kernel.cols % 2 == 1,
+            "Kernel dimensions must be odd"
+        );
+        assert!(
+            kernel.rows <= self.rows && kernel.cols <= self.cols,
+            "Kernel must fit within the matrix"
+        );
+
+        let rows = self.rows - kernel.rows + 1;
+        let cols = self.cols - kernel.cols + 1;
+        let mut data = Vec::with_capacity(rows * cols);
+        for row_num in 0..rows {
+            for col_num in 0..cols {
+                let mut sum = 0;
+                for i in 0..kernel.rows {
+                    for j in 0..kernel.cols {
+                        sum += self[[row_num + i, col_num + j]] * kernel[[i, j]];
+                    }
+                }
+                data.push(sum);
+            }
+        }
+        Matrix::new(cols, rows, data)
+    }
+
+    /// Computes `self * self.transpose()`. The result is symmetric, so only
+    /// the upper triangle is worked out and then mirrored into the lower one
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to multiply by its own transpose
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(our_mat.self_gram_t(), Matrix::new(2, 2, vec![14, 32, 32, 77]));
+    /// ```
+    pub fn self_gram_t(&self) -> Matrix {
+        let n = self.rows;
+        let mut result = Matrix::new(n, n, vec![0; n * n]);
+        for i in 0..n {
+            for j in i..n {
+                let dot: i32 = self
+                    .row_iter(i)
+                    .zip(self.row_iter(j))
+                    .map(|(lhs, rhs)| lhs * rhs)
+                    .sum();
+                result[[i, j]] = dot;
+                result[[j, i]] = dot;
+            }
+        }
+        result
+    }
+
+    /// Returns a copy of the matrix keeping only the cells the predicate
+    /// accepts; every other cell is replaced by `fill`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to mask
+    /// * `keep` - Given the row and column of a cell, says whether to keep it
+    /// * `fill` - The value for cells that aren't kept
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// let lower = matrix.mask(|row, col| col <= row, 0);
+    /// assert_eq!(lower, Matrix::new(2, 2, vec![1, 0, 3, 4]));
+    /// ```
+    pub fn mask<F: Fn(usize, usize) -> bool>(&self, keep: F, fill: i32) -> Matrix {
+        let data = (0..self.rows)
+            .flat_map(|i| {
+                let keep = &keep;
+                self.row_iter(i)
+                    .enumerate()
+                    .map(move |(j, &num)| if keep(i, j) { num } else { fill })
+            })
+            .collect();
+        Matrix::new(self.cols, self.rows, data)
+    }
+
+    /// Computes `self.transpose() * rhs` directly from the columns of both
+    /// matrices, without building the transpose
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix whose transpose is the "left" of the multiplication
+    /// * `rhs` - The "right" matrix, with as many rows as `self`
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]);
+    /// let our_mat2 = Matrix::new(1, 3, vec![1, 0, 2]);
+    /// assert_eq!(our_mat1.transpose_mul(&our_mat2), Matrix::new(1, 2, vec![11, 14]));
+    /// ```
+    pub fn transpose_mul(&self, rhs: &Matrix) -> Matrix {
+        assert_eq!(self.rows, rhs.rows);
+        let data = (0..self.cols)
+            .flat_map(|i| {
+                (0..rhs.cols).map(move |j| {
+                    self.col_iter(i)
+                        .zip(rhs.col_iter(j))
+                        .map(|(lhs, rhs)| lhs * rhs)
+                        .sum()
+                })
+            })
+            .collect();
+        Matrix::new(rhs.cols, self.cols, data)
+    }
+
+    /// Returns a copy of the matrix with each element replaced by its remainder
+    /// when divided by `divisor`
+    ///
+    /// This is Rust's `%`, so a remainder takes the sign of the element, not of
+    /// the divisor: `-7` gives `-1` for a divisor of `3` or `-3`. Use
+    /// `rem_euclid` on the elements for a result that is never negative
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix whose elements are divided
+    /// * `divisor` - What to divide by; must not be zero
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(3, 1, vec![7, -7, 6]);
+    /// assert_eq!(matrix.rem_scalar(3), Matrix::new(3, 1, vec![1, -1, 0]));
+    /// ```
+    pub fn rem_scalar(&self, divisor: i32) -> Matrix {
+        assert_ne!(divisor, 0, "Remainder by zero");
+        // wrapping only matters for i32::MIN % -1, whose remainder is 0 but
+        // which overflows computing the quotient
+        let data = self
+            .data
+            .iter()
+            .map(|num| num.wrapping_rem(divisor))
+            .collect();
+        Matrix {
+            cols: self.cols,
+            rows: self.rows,
+            data,
+            layout: self.layout,
+        }
+    }
+
+    /// Returns a copy of the block of this matrix starting at the given row and
+    /// column
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to copy from
+    /// * `row_start` - The first row of the block
+    /// * `col_start` - The first column of the block
+    /// * `rows` - The number of rows in the block
+    /// * `cols` - The number of columns in the block
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    /// assert_eq!(matrix.submatrix(1, 1, 2, 2), Matrix::new(2, 2, vec![5, 6, 8, 9]));
+    /// ```
+    pub fn submatrix(
+        &self,
+        row_start: usize,
+        col_start: usize,
+        rows: usize,
+        cols: usize,
+    ) -> Matrix {
+        assert!(row_start + rows <= self.rows, "Block rows out of bounds");
+        assert!(col_start + cols <= self.cols, "Block columns out of bounds");
+        let data = (row_start..row_start + rows)
+            .flat_map(|i| self.row_iter(i).skip(col_start).take(cols))
+            .copied()
+            .collect();
+        Matrix::new(cols, rows, data)
+    }
+
+    /// Copies a block into this matrix, with its top left corner at the given
+    /// row and column; the write counterpart of `submatrix`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to write into
+    /// * `row_start` - The row for the first row of the block
+    /// * `col_start` - The column for the first column of the block
+    /// * `block` - The matrix to copy in; must fit within `self`
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let mut matrix = Matrix::new(3, 2, vec![0; 6]);
+    /// matrix.set_submatrix(0, 1, &Matrix::new(2, 1, vec![1, 2]));
+    /// assert_eq!(matrix, Matrix::new(3, 2, vec![0, 1, 2, 0, 0, 0]));
+    /// ```
+    pub fn set_submatrix(&mut self, row_start: usize, col_start: usize, block: &Matrix) {
+        assert!(
+            row_start + block.rows <= self.rows,
+            "Block rows out of bounds"
+        );
+        assert!(
+            col_start + block.cols <= self.cols,
+            "Block columns out of bounds"
+        );
+        for i in 0..block.rows {
+            for j in 0..block.cols {
+                self[[row_start + i, col_start + j]] = block[[i, j]];
+            }
+        }
+    }
+
+    /// Computes the similarity transform `P⁻¹ * A * P` in floating point. The
+    /// result has the same trace, determinant and eigenvalues as `A`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix `A` to transform
+    /// * `p` - The change of basis `P`, the same size as `A`
+    ///
+    /// # Returns
+    /// the transformed matrix, or None if `P` is singular
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// let p = Matrix::new(2, 2, vec![1, 1, 0, 1]);
+    /// let transformed = matrix.similarity_transform(&p).unwrap();
+    /// assert_eq!(transformed.trace(), 5.0);
+    /// ```
+    pub fn similarity_transform(&self, p: &Matrix) -> Option<FloatMatrix> {
+        assert_eq!(
+            self.rows, self.cols,
+            "Similarity transform requires a square matrix"
+        );
+        assert_eq!((p.rows, p.cols), (self.rows, self.cols));
+        let p = FloatMatrix::from(p);
+        let p_inverse = p.inverse()?;
+        Some(p_inverse.mul_mat(&FloatMatrix::from(self)).mul_mat(&p))
+    }
+
+    /// Gets the sum of all the elements, in an i64 so it can't overflow
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// the sum
+    pub fn sum(&self) -> i64 {
+        self.data.iter().map(|&num| i64::from(num)).sum()
+    }
+
+    /// Gets the sum of all the elements like `sum`, splitting the elements
+    /// between one thread per available core
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// the sum, exactly as `sum` gives it
+    pub fn sum_parallel(&self) -> i64 {
+        let threads = std::thread::available_parallelism().map_or(1, |count| count.get());
+        self.sum_in_chunks(threads)
+    }
+
+    /// Sums the elements in `threads` chunks, each on its own thread, then
+    /// adds up the partial sums
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    /// * threads - how many chunks to split the elements into
+    ///
+    /// # Returns
+    /// the sum
+    fn sum_in_chunks(&self, threads: usize) -> i64 {
+        if threads <= 1 || self.data.len() < threads {
+            return self.sum();
+        }
+        let chunk_size = self.data.len().div_ceil(threads);
+        crossbeam::scope(|spawner| {
+            let workers: Vec<_> = self
+                .data
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    spawner.spawn(move |_| chunk.iter().map(|&num| i64::from(num)).sum::<i64>())
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .sum()
+        })
+        .unwrap()
+    }
+
+    /// Formats the transpose of this matrix without building it, by writing
+    /// each column as a row
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix whose transpose to display
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 1, vec![1, 2]);
+    /// assert_eq!(matrix.transpose_display().to_string(), matrix.transpose().to_string());
+    /// ```
+    pub fn transpose_display(&self) -> impl Display + '_ {
+        TransposeDisplay(self)
+    }
+
+    /// Builds an Operation adding `rhs` to this matrix, ready to evaluate
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The left operand
+    /// * `rhs` - The matrix to add
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat = Matrix::new(2, 1, vec![1, 2]);
+    /// let op = our_mat.clone().then_add(&our_mat).then_multiply(&Matrix::new(1, 2, vec![1, 1]));
+    /// assert_eq!(op.do_operation(), Matrix::new(1, 1, vec![6]));
+    /// ```
+    pub fn then_add(self, rhs: &Matrix) -> Operation {
+        self.then(Operator::Add, rhs)
+    }
+
+    /// Builds an Operation subtracting `rhs` from this matrix, ready to evaluate
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The left operand
+    /// * `rhs` - The matrix to subtract
+    pub fn then_subtract(self, rhs: &Matrix) -> Operation {
+        self.then(Operator::Subtract, rhs)
+    }
+
+    /// Builds an Operation multiplying this matrix by `rhs`, ready to evaluate
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The left operand
+    /// * `rhs` - The matrix to multiply by
+    pub fn then_multiply(self, rhs: &Matrix) -> Operation {
+        self.then(Operator::Multiply, rhs)
+    }
+
+    /// Builds an Operation applying `operator` to this matrix and `rhs`, ready
+    /// to evaluate
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The left operand
+    /// * `operator` - The Operator to apply
+    /// * `rhs` - The right operand
+    pub fn then(self, operator: Operator, rhs: &Matrix) -> Operation {
+        Operation::new(
+            Operand::Matrix(self),
+            operator,
+            Operand::Matrix(rhs.clone()),
+        )
+    }
+
+    /// Gets the indices of the rows whose elements are all zero
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// the zero rows, in order
+    pub fn zero_rows(&self) -> Vec<usize> {
+        (0..self.rows)
+            .filter(|&i| self.row_iter(i).all(|&num| num == 0))
+            .collect()
+    }
+
+    /// Gets the indices of the columns whose elements are all zero
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// the zero columns, in order
+    pub fn zero_cols(&self) -> Vec<usize> {
+        (0..self.cols)
+            .filter(|&j| self.col_iter(j).all(|&num| num == 0))
+            .collect()
+    }
+
+    /// Downsamples the matrix by taking the largest element of each block
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to downsample
+    /// * `pool_rows` - The number of rows in each block; must divide the rows
+    /// * `pool_cols` - The number of columns in each block; must divide the columns
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(4, 2, vec![1, 5, 2, 0, 3, 4, 8, 1]);
+    /// assert_eq!(matrix.max_pool(2, 2), Matrix::new(2, 1, vec![5, 8]));
+    /// ```
+    pub fn max_pool(&self, pool_rows: usize, pool_cols: usize) -> Matrix {
+        self.pool(pool_rows, pool_cols, |block| block.max().unwrap())
+    }
+
+    /// Downsamples the matrix by averaging the elements of each block. The
+    /// average is an integer division, so it is rounded toward zero: a block
+    /// summing to 7 over 2 elements gives 3, and one summing to -7 gives -3
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to downsample
+    /// * `pool_rows` - The number of rows in each block; must divide the rows
+    /// * `pool_cols` - The number of columns in each block; must divide the columns
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(4, 2, vec![1, 5, 2, 0, 3, 4, 8, 1]);
+    /// assert_eq!(matrix.avg_pool(2, 2), Matrix::new(2, 1, vec![3, 2]));
+    /// ```
+    pub fn avg_pool(&self, pool_rows: usize, pool_cols: usize) -> Matrix {
+        let block_size = (pool_rows * pool_cols) as i64;
+        // the sum is an i64 so a block of large elements can't overflow, and
+        // the average always fits back in an i32
+        self.pool(pool_rows, pool_cols, |block| {
+            (block.map(i64::from).sum::<i64>() / block_size) as i32
+        })
+    }
+
+    /// Splits the matrix into non-overlapping blocks and reduces each block to
+    /// one element of the result
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    /// * pool_rows - the number of rows in each block; must divide the rows
+    /// * pool_cols - the number of columns in each block; must divide the columns
+    /// * reduce - gets the result element from the elements of a block
+    ///
+    /// # Returns
+    /// a Matrix with one element for each block
+    fn pool<F>(&self, pool_rows: usize, pool_cols: usize, reduce: F) -> Matrix
+    where
+        F: Fn(&mut dyn Iterator<Item = i32>) -> i32,
+    {
+        assert!(pool_rows > 0 && pool_cols > 0, "Pool size must not be zero");
+        assert_eq!(self.rows % pool_rows, 0, "Pool rows must divide the rows");
+        assert_eq!(
+            self.cols % pool_cols,
+            0,
+            "Pool columns must divide the columns"
+        );
+
+        let out_rows = self.rows / pool_rows;
+        let out_cols = self.cols / pool_cols;
+        let data = (0..out_rows)
+            .flat_map(|i| (0..out_cols).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                let mut block = (i * pool_rows..(i + 1) * pool_rows).flat_map(|row| {
+                    self.row_iter(row)
+                        .skip(j * pool_cols)
+                        .take(pool_cols)
+                        .copied()
+                });
+                reduce(&mut block)
+            })
+            .collect();
+        Matrix::new(out_cols, out_rows, data)
+    }
+
+    /// Checks whether this is a permutation matrix: square, with only 0s and
+    /// 1s, and exactly one 1 in each row and each column
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// true if the matrix is a permutation matrix
+    pub fn is_permutation_matrix(&self) -> bool {
+        let single_one = |line: &mut dyn Iterator<Item = &i32>| {
+            let mut ones = 0;
+            for &num in line {
+                match num {
+                    0 => {}
+                    1 => ones += 1,
+                    _ => return false,
+                }
+            }
+            ones == 1
+        };
+        self.rows == self.cols
+            && (0..self.rows).all(|i| single_one(&mut self.row_iter(i)))
+            && (0..self.cols).all(|j| single_one(&mut self.col_iter(j)))
+    }
+
+    /// Returns a copy of the matrix with `value` added to every element
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to shift
+    /// * `value` - The constant to add
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 1, vec![1, -2]);
+    /// assert_eq!(matrix.add_scalar(3), Matrix::new(2, 1, vec![4, 1]));
+    /// ```
+    pub fn add_scalar(&self, value: i32) -> Matrix {
+        let mut matr = self.clone();
+        matr.data.iter_mut().for_each(|num| *num += value);
+        matr
+    }
+
+    /// Returns a copy of the matrix with `value` subtracted from every element
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to shift
+    /// * `value` - The constant to subtract
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 1, vec![1, -2]);
+    /// assert_eq!(matrix.sub_scalar(3), Matrix::new(2, 1, vec![-2, -5]));
+    /// ```
+    pub fn sub_scalar(&self, value: i32) -> Matrix {
+        let mut matr = self.clone();
+        matr.data.iter_mut().for_each(|num| *num -= value);
+        matr
+    }
+
+    /// Returns the transpose of this matrix with its elements copied into
+    /// row-major order, unlike `transpose` which only changes the layout. The
+    /// rows of the result are split into bands, one per available core, and
+    /// each band is copied tile by tile
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to transpose
+    ///
+    /// ```
+    /// # use sample_code::matrix::{Layout, Matrix};
+    /// let our_mat = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+    /// let transposed = our_mat.transpose_parallel();
+    /// assert_eq!(transposed, our_mat.transpose());
+    /// assert_eq!(transposed.layout(), Layout::RowMajor);
+    /// ```
+    pub fn transpose_parallel(&self) -> Matrix {
+        let threads = std::thread::available_parallelism().map_or(1, |count| count.get());
+        self.transpose_in_bands(threads)
+    }
+
+    /// Copies the transpose into row-major order, splitting the rows of the
+    /// result into `threads` bands that are each copied on their own thread
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    /// * threads - how many bands to split the rows of the result into
+    ///
+    /// # Returns
+    /// the row-major transpose
+    fn transpose_in_bands(&self, threads: usize) -> Matrix {
+        let (out_rows, out_cols) = (self.cols, self.rows);
+        let mut data = vec![0; out_rows * out_cols];
+        if data.is_empty() {
+            return Matrix::new(out_cols, out_rows, data);
+        }
+
+        let band_rows = out_rows.div_ceil(threads.max(1));
+        let copy_band = |band_num: usize, band: &mut [i32]| {
+            let first_row = band_num * band_rows;
+            let rows = band.len() / out_cols;
+            for tile_row in (0..rows).step_by(TRANSPOSE_TILE) {
+                for tile_col in (0..out_cols).step_by(TRANSPOSE_TILE) {
+                    for i in tile_row..(tile_row + TRANSPOSE_TILE).min(rows) {
+                        for j in tile_col..(tile_col + TRANSPOSE_TILE).min(out_cols) {
+                            band[i * out_cols + j] = self.data[self.offset(j, first_row + i)];
+                        }
+                    }
+                }
+            }
+        };
+
+        crossbeam::scope(|spawner| {
+            for (band_num, band) in data.chunks_mut(band_rows * out_cols).enumerate() {
+                spawner.spawn(move |_| copy_band(band_num, band));
+            }
+        })
+        .unwrap();
+
+        Matrix::new(out_cols, out_rows, data)
+    }
+
+    /// Removes the all-zero rows and columns from the edges of the matrix,
+    /// leaving the smallest block that holds every non-zero element. Zero
+    /// rows and columns inside that block are kept
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to trim
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(4, 3, vec![0, 0, 0, 0, 0, 1, 0, 2, 0, 0, 0, 0]);
+    /// assert_eq!(matrix.trim_zero_borders(), Matrix::new(3, 1, vec![1, 0, 2]));
+    /// ```
+    ///
+    /// # Returns
+    /// The trimmed matrix, or a 0x0 matrix if every element is zero
+    pub fn trim_zero_borders(&self) -> Matrix {
+        let non_zero_rows: Vec<usize> = (0..self.rows)
+            .filter(|&i| self.row_iter(i).any(|&num| num != 0))
+            .collect();
+        let non_zero_cols: Vec<usize> = (0..self.cols)
+            .filter(|&j| self.col_iter(j).any(|&num| num != 0))
+            .collect();
+        match (
+            non_zero_rows.first(),
+            non_zero_rows.last(),
+            non_zero_cols.first(),
+            non_zero_cols.last(),
+        ) {
+            (Some(&top), Some(&bottom), Some(&left), Some(&right)) => {
+                self.submatrix(top, left, bottom - top + 1, right - left + 1)
+            }
+            _ => Matrix::new(0, 0, Vec::new()),
+        }
+    }
+
+    /// Compares two matrices element by element
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The first matrix to compare
+    /// * `other` - The second matrix to compare; must have the same dimensions
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let actual = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// let expected = Matrix::new(2, 2, vec![1, 0, 3, 4]);
+    /// assert_eq!(actual.eq_mask(&expected), Matrix::new(2, 2, vec![1, 0, 1, 1]));
+    /// ```
+    ///
+    /// # Returns
+    /// A matrix of the same shape holding 1 where the elements are equal and
+    /// 0 where they differ
+    pub fn eq_mask(&self, other: &Matrix) -> Matrix {
+        self.zip_map(other, |num1, num2| (num1 == num2) as i32)
+    }
+
+    /// Splits the matrix into non-overlapping tiles of the same size
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to split
+    /// * `tile_rows` - The number of rows in each tile; must divide the rows
+    /// * `tile_cols` - The number of columns in each tile; must divide the columns
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(4, 1, vec![1, 2, 3, 4]);
+    /// let tiles = matrix.tiles(1, 2);
+    /// assert_eq!(tiles, vec![Matrix::new(2, 1, vec![1, 2]), Matrix::new(2, 1, vec![3, 4])]);
+    /// ```
+    ///
+    /// # Returns
+    /// The tiles, left to right along each row of tiles, top to bottom
+    pub fn tiles(&self, tile_rows: usize, tile_cols: usize) -> Vec<Matrix> {
+        assert!(tile_rows > 0 && tile_cols > 0, "Tile size must not be zero");
+        assert_eq!(self.rows % tile_rows, 0, "Tile rows must divide the rows");
+        assert_eq!(
+            self.cols % tile_cols,
+            0,
+            "Tile columns must divide the columns"
+        );
+
+        (0..self.rows)
+            .step_by(tile_rows)
+            .flat_map(|row_start| {
+                (0..self.cols).step_by(tile_cols).map(move |col_start| {
+                    self.submatrix(row_start, col_start, tile_rows, tile_cols)
+                })
+            })
+            .collect()
+    }
+
+    /// Folds each row of the matrix down to a single value
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to reduce
+    /// * `init` - The starting value of each fold
+    /// * `f` - Combines the value so far with the next element, left to right
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(matrix.reduce_rows(0, |acc, num| acc + num), vec![6, 15]);
+    /// ```
+    ///
+    /// # Returns
+    /// One value per row, top to bottom
+    pub fn reduce_rows<F: Fn(i32, i32) -> i32>(&self, init: i32, f: F) -> Vec<i32> {
+        (0..self.rows)
+            .map(|i| self.row_iter(i).fold(init, |acc, &num| f(acc, num)))
+            .collect()
+    }
+
+    /// Folds each column of the matrix down to a single value
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to reduce
+    /// * `init` - The starting value of each fold
+    /// * `f` - Combines the value so far with the next element, top to bottom
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(matrix.reduce_cols(0, |acc, num| acc + num), vec![5, 7, 9]);
+    /// ```
+    ///
+    /// # Returns
+    /// One value per column, left to right
+    pub fn reduce_cols<F: Fn(i32, i32) -> i32>(&self, init: i32, f: F) -> Vec<i32> {
+        (0..self.cols)
+            .map(|j| self.col_iter(j).fold(init, |acc, &num| f(acc, num)))
+            .collect()
+    }
+
+    /// Gathers the minimum, maximum, sum, mean and count of the elements in a
+    /// single pass over them
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// the aggregates, or None if the matrix has no elements
+    pub fn stats(&self) -> Option<MatrixStats> {
+        let (&first, rest) = self.data.split_first()?;
+        let mut stats = MatrixStats {
+            min: first,
+            max: first,
+            sum: i64::from(first),
+            mean: 0.0,
+            count: self.data.len(),
+        };
+        for &num in rest {
+            stats.min = stats.min.min(num);
+            stats.max = stats.max.max(num);
+            stats.sum += i64::from(num);
+        }
+        stats.mean = stats.sum as f64 / stats.count as f64;
+        Some(stats)
+    }
+
+    /// Combines two matrices element by element like `zip_map`, splitting the
+    /// elements between one thread per available core for large matrices
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix, whose elements are the first argument to `f`
+    /// * `other` - The "right" matrix; must have the same dimensions
+    /// * `f` - The function applied to each pair of corresponding elements
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(2, 1, vec![1, 5]);
+    /// let our_mat2 = Matrix::new(2, 1, vec![3, 4]);
+    /// assert_eq!(our_mat1.par_zip_map(&our_mat2, |a, b| a * b), Matrix::new(2, 1, vec![3, 20]));
+    /// ```
+    pub fn par_zip_map<F: Fn(i32, i32) -> i32 + Sync>(&self, other: &Matrix, f: F) -> Matrix {
+        if self.data.len() < PARALLEL_ZIP_SIZE {
+            return self.zip_map(other, f);
+        }
+        let threads = std::thread::available_parallelism().map_or(1, |count| count.get());
+        self.zip_map_in_chunks(other, f, threads)
+    }
+
+    /// Combines two matrices element by element in `threads` chunks, each on
+    /// its own thread
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix, whose elements are the first argument to `f`
+    /// * `other` - The "right" matrix; must have the same dimensions
+    /// * `f` - The function applied to each pair of corresponding elements
+    /// * `threads` - How many chunks to split the elements into
+    fn zip_map_in_chunks<F: Fn(i32, i32) -> i32 + Sync>(
+        &self,
+        other: &Matrix,
+        f: F,
+        threads: usize,
+    ) -> Matrix {
+        if threads <= 1 || self.data.len() < threads {
+            return self.zip_map(other, f);
+        }
+        assert_eq!(self.cols, other.cols);
+        assert_eq!(self.rows, other.rows);
+
+        // the elements only line up if both are stored the same way
+        let other = other.to_layout(self.layout);
+        let mut matr_data = vec![0; self.data.len()];
+        let chunk_size = self.data.len().div_ceil(threads);
+        crossbeam::scope(|spawner| {
+            let chunks = matr_data
+                .chunks_mut(chunk_size)
+                .zip(self.data.chunks(chunk_size))
+                .zip(other.data.chunks(chunk_size));
+            for ((out, lhs), rhs) in chunks {
+                let f = &f;
+                spawner.spawn(move |_| {
+                    for ((cell, &num1), &num2) in out.iter_mut().zip(lhs).zip(rhs) {
+                        *cell = f(num1, num2);
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        Matrix {
+            cols: self.cols,
+            rows: self.rows,
+            data: matr_data,
+            layout: self.layout,
+        }
+    }
+
+    /// Computes the 2D inclusive prefix sum, or integral image, of the matrix:
+    /// each cell holds the sum of every cell above and to the left of it,
+    /// including itself
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix to sum
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let matrix = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+    /// assert_eq!(matrix.prefix_sum_2d(), Matrix::new(2, 2, vec![1, 3, 4, 10]));
+    /// ```
+    ///
+    /// # Panics
+    /// If a sum doesn't fit in an i32; the sums are accumulated in an i64, so
+    /// only a final sum out of range panics
+    pub fn prefix_sum_2d(&self) -> Matrix {
+        let mut sums = vec![0i64; self.rows * self.cols];
+        for i in 0..self.rows {
+            let mut row_sum = 0i64;
+            for (j, &num) in self.row_iter(i).enumerate() {
+                row_sum += i64::from(num);
+                let above = if i == 0 {
+                    0
+                } else {
+                    sums[(i - 1) * self.cols + j]
+                };
+                sums[i * self.cols + j] = above + row_sum;
+            }
+        }
+
+        let data = sums
+            .iter()
+            .enumerate()
+            .map(|(index, &sum)| {
+                i32::try_from(sum).unwrap_or_else(|_| {
+                    panic!(
+                        "{}",
+                        MatrixError::Overflow {
+                            row: index / self.cols,
+                            col: index % self.cols,
+                        }
+                    )
+                })
+            })
+            .collect();
+        Matrix::new(self.cols, self.rows, data)
+    }
+
+    /// Checks whether each row's diagonal element is at least as large, in
+    /// absolute value, as the sum of the row's other elements, the
+    /// precondition of the iterative solvers on FloatMatrix. Non-square
+    /// matrices never are
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    ///
+    /// # Returns
+    /// true if the matrix is diagonally dominant
+    pub fn is_diagonally_dominant(&self) -> bool {
+        self.rows == self.cols
+            && (0..self.rows).all(|i| {
+                let off_diagonal: u64 = self
+                    .row_iter(i)
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, num)| u64::from(num.unsigned_abs()))
+                    .sum();
+                u64::from(self[[i, i]].unsigned_abs()) >= off_diagonal
+            })
+    }
+}
+
+/// Finds which of `len` positions ends up first after shifting them all
+/// cyclically by `by`
+///
+/// # Arguments
+/// * len - the number of rows or columns being shifted
+/// * by - how far each one moves; negative moves them back
+///
+/// # Returns
+/// the position to read the first element from, or 0 if there are none
+fn cyclic_start(len: usize, by: isize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    // unsigned_abs keeps isize::MIN from overflowing
+    let offset = by.unsigned_abs() % len;
+    if by < 0 {
+        offset
+    } else {
+        (len - offset) % len
+    }
+}
+
+/// Multiplies two row-major `side`x`side` matrices by Strassen's algorithm,
+/// splitting each into quarters until they are no larger than
+/// `STRASSEN_CUTOFF`; every sum and product wraps
+///
+/// # Arguments
+/// * lhs - the elements of the "left" matrix
+/// * rhs - the elements of the "right" matrix
+/// * side - the number of rows and columns of each
+///
+/// # Returns
+/// the elements of the product, row by row
+fn strassen_square(lhs: &[i32], rhs: &[i32], side: usize) -> Vec<i32> {
+    if side <= STRASSEN_CUTOFF || side % 2 == 1 {
+        let mut product = vec![0i32; side * side];
+        for i in 0..side {
+            for k in 0..side {
+                let lhs_elem = lhs[i * side + k];
+                let out_row = &mut product[i * side..(i + 1) * side];
+                for (out, &rhs_elem) in out_row.iter_mut().zip(&rhs[k * side..(k + 1) * side]) {
+                    *out = out.wrapping_add(lhs_elem.wrapping_mul(rhs_elem));
+                }
+            }
+        }
+        return product;
+    }
+
+    let half = side / 2;
+    let quarter = |matr: &[i32], row: usize, col: usize| -> Vec<i32> {
+        (0..half)
+            .flat_map(|i| {
+                let start = (row * half + i) * side + col * half;
+                matr[start..start + half].iter().copied()
+            })
+            .collect()
+    };
+    let add = |a: &[i32], b: &[i32]| -> Vec<i32> {
+        a.iter().zip(b).map(|(x, y)| x.wrapping_add(*y)).collect()
+    };
+    let sub = |a: &[i32], b: &[i32]| -> Vec<i32> {
+        a.iter().zip(b).map(|(x, y)| x.wrapping_sub(*y)).collect()
+    };
+
+    let (a11, a12, a21, a22) = (
+        quarter(lhs, 0, 0),
+        quarter(lhs, 0, 1),
+        quarter(lhs, 1, 0),
+        quarter(lhs, 1, 1),
+    );
+    let (b11, b12, b21, b22) = (
+        quarter(rhs, 0, 0),
+        quarter(rhs, 0, 1),
+        quarter(rhs, 1, 0),
+        quarter(rhs, 1, 1),
+    );
+
+    let m1 = strassen_square(&add(&a11, &a22), &add(&b11, &b22), half);
+    let m2 = strassen_square(&add(&a21, &a22), &b11, half);
+    let m3 = strassen_square(&a11, &sub(&b12, &b22), half);
+    let m4 = strassen_square(&a22, &sub(&b21, &b11), half);
+    let m5 = strassen_square(&add(&a11, &a12), &b22, half);
+    let m6 = strassen_square(&sub(&a21, &a11), &add(&b11, &b12), half);
+    let m7 = strassen_square(&sub(&a12, &a22), &add(&b21, &b22), half);
+
+    let c11 = add(&sub(&add(&m1, &m4), &m5), &m7);
+    let c12 = add(&m3, &m5);
+    let c21 = add(&m2, &m4);
+    let c22 = add(&add(&sub(&m1, &m2), &m3), &m6);
+
+    let mut product = vec![0; side * side];
+    for (quad, row, col) in [(c11, 0, 0), (c12, 0, 1), (c21, 1, 0), (c22, 1, 1)] {
+        for (i, quad_row) in quad.chunks_exact(half).enumerate() {
+            let start = (row * half + i) * side + col * half;
+            product[start..start + half].copy_from_slice(quad_row);
+        }
+    }
+    product
+}
+
+/// Computes the weighted sum `w_1 * M_1 + w_2 * M_2 + ...` of matrices that
+/// all have the same dimensions
+///
+/// # Arguments
+///
+/// * `terms` - The (weight, matrix) pairs to sum; must not be empty
+///
+/// ```
+/// # use sample_code::matrix::{Matrix, weighted_sum};
+/// let our_mat1 = Matrix::new(2, 1, vec![1, 2]);
+/// let our_mat2 = Matrix::new(2, 1, vec![3, 4]);
+/// let sum = weighted_sum(&[(2, &our_mat1), (-1, &our_mat2)]);
+/// assert_eq!(sum, Matrix::new(2, 1, vec![-1, 0]));
+/// ```
+pub fn weighted_sum(terms: &[(i32, &Matrix)]) -> Matrix {
+    assert!(!terms.is_empty(), "Weighted sum needs at least one term");
+    let (first_weight, first) = terms[0];
+    let mut sum = first.clone() * first_weight;
+    for &(weight, matr) in &terms[1..] {
+        assert_eq!(
+            (matr.rows, matr.cols),
+            (first.rows, first.cols),
+            "Weighted sum terms must have the same dimensions"
+        );
+        sum = sum.zip_map(matr, |acc, num| acc + weight * num);
+    }
+    sum
+}
+
+// In Rust, traits are not normally documented since they are used for
+// interoperability between crates and operands
+impl Matrix<bool> {
+    /// Logical AND of two matrices with the same dimensions
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix
+    /// * `rhs` - The "right" matrix
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(3, 1, vec![true, false, true]);
+    /// let our_mat2 = Matrix::new(3, 1, vec![true, true, false]);
+    /// assert_eq!(our_mat1.logical_and(&our_mat2), Matrix::new(3, 1, vec![true, false, false]));
+    /// ```
+    pub fn logical_and(&self, rhs: &Matrix<bool>) -> Matrix<bool> {
+        self.zip_map(rhs, |val1, val2| val1 && val2)
+    }
+
+    /// Logical OR of two matrices with the same dimensions
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix
+    /// * `rhs` - The "right" matrix
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// let our_mat1 = Matrix::new(3, 1, vec![true, false, true]);
+    /// let our_mat2 = Matrix::new(3, 1, vec![true, false, false]);
+    /// assert_eq!(our_mat1.logical_or(&our_mat2), Matrix::new(3, 1, vec![true, false, true]));
+    /// ```
+    pub fn logical_or(&self, rhs: &Matrix<bool>) -> Matrix<bool> {
+        self.zip_map(rhs, |val1, val2| val1 || val2)
+    }
+
+    /// Boolean matrix product, where each element is the OR of the ANDs of a
+    /// row of `self` and a column of `rhs`
+    ///
+    /// For an adjacency matrix `A`, `A.boolean_mul(&A)` says which nodes can be
+    /// reached in exactly two steps
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The "left" matrix in the multiplication
+    /// * `rhs` - The "right" matrix in the multiplication
+    ///
+    /// ```
+    /// # use sample_code::matrix::Matrix;
+    /// // an integer adjacency matrix can be read as booleans with `map`
+    /// let our_mat1 = Matrix::new(2, 1, vec![1, 0]).map(|num| num != 0);
+    /// let our_mat2 = Matrix::new(1, 2, vec![false, true]);
+    /// assert_eq!(our_mat1.boolean_mul(&our_mat2), Matrix::new(1, 1, vec![false]));
+    /// ```
+    pub fn boolean_mul(&self, rhs: &Matrix<bool>) -> Matrix<bool> {
+        assert_eq!(self.cols, rhs.rows);
+        let data = (0..self.rows)
+            .flat_map(|i| {
+                (0..rhs.cols).map(move |j| {
+                    self.row_iter(i)
+                        .zip(rhs.col_iter(j))
+                        .any(|(&lhs, &rhs)| lhs && rhs)
+                })
+            })
+            .collect();
+        Matrix::new(rhs.cols, self.rows, data)
+    }
+}
+
+impl<T: Element> Index<[usize; 2]> for Matrix<T> {
+    type Output = T;
+
+    /// Indexes into the Matrix
+    ///
+    /// # Arguments
+    /// self - reference to this Matrix
+    /// index - index of spot to get (first index is row, second index is column)
+    ///
+    /// # Return
+    /// Returns the value at index
+    ///
+    /// Author: Matthew Krohn
+    fn index(&self, [row_idx, col_idx]: [usize; 2]) -> &T {
+        match self.checked_offset(row_idx, col_idx) {
+            Ok(offset) => &self.data[offset],
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+impl<T: Element> IndexMut<[usize; 2]> for Matrix<T> {
+    /// Indexes into the Matrix - mutable
+    ///
+    /// # Arguments
+    /// self - reference to this Matrix
+    /// index - index of spot to get (first index is row, second index is column)
+    ///
+    /// # Return
+    /// Returns a mutable reference to the value at index
+    ///
+    /// Author: Matthew Krohn
+    fn index_mut(&mut self, [row_idx, col_idx]: [usize; 2]) -> &mut T {
+        match self.checked_offset(row_idx, col_idx) {
+            Ok(offset) => &mut self.data[offset],
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+impl<T: Element> TryFrom<MatrixRepr<T>> for Matrix<T> {
+    type Error = String;
+
+    /// Builds a Matrix from its serialized form, if the version can be read,
+    /// it has as many elements as its shape holds and it is within any
+    /// element limit
+    fn try_from(repr: MatrixRepr<T>) -> Result<Matrix<T>, String> {
+        check_format_version(repr.version)?;
+        check_element_limit(repr.rows, repr.cols).map_err(|e| e.to_string())?;
+        Self::check_data_len(repr.cols, repr.rows, repr.data.len()).map_err(|e| e.to_string())?;
+        Ok(Matrix {
+            rows: repr.rows,
+            cols: repr.cols,
+            data: repr.data,
+            layout: repr.layout,
+        })
+    }
+}
+
+impl<T: Element + Serialize> Serialize for Matrix<T> {
+    /// Serializes the Matrix with the current format version; the layout is
+    /// left out when it is the default, row-major, except in binary formats
+    /// like bincode, which can't tell a missing field from the next one
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let row_major = self.layout.is_row_major() && serializer.is_human_readable();
+        let mut state = serializer.serialize_struct("Matrix", if row_major { 4 } else { 5 })?;
+        state.serialize_field("version", &FORMAT_VERSION)?;
+        state.serialize_field("rows", &self.rows)?;
+        state.serialize_field("cols", &self.cols)?;
+        state.serialize_field("data", &self.data)?;
+        if row_major {
+            state.skip_field("layout")?;
+        } else {
+            state.serialize_field("layout", &self.layout)?;
+        }
+        state.end()
+    }
+}
+
+impl<T: Element> PartialEq for Matrix<T> {
+    /// Compares the logical elements of two matrices, regardless of layout
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    /// * other - reference to the Matrix to compare against
+    ///
+    /// # Return
+    /// Returns whether the matrices have the same shape and elements
+    fn eq(&self, other: &Matrix<T>) -> bool {
+        self.rows == other.rows
+            && self.cols == other.cols
+            && self.data == other.to_layout(self.layout).data
+    }
+}
+
+impl<T: Element + Eq> Eq for Matrix<T> {}
+
+impl Hash for Matrix {
+    /// Hashes the shape and logical elements of the matrix, so matrices that
+    /// compare equal hash the same whatever their layout
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    /// * state - the hasher to feed
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rows.hash(state);
+        self.cols.hash(state);
+        for row_num in 0..self.rows {
+            for elem in self.row_iter(row_num) {
+                elem.hash(state);
+            }
+        }
+    }
+}
+
+impl<T: Element> Display for Matrix<T> {
+    /// Formats the matrix for display, with each element shown to the
+    /// formatter's precision if it has one
+    ///
+    /// # Arguments
+    /// * self - reference to this Matrix
+    /// * f - reference to the formatter to write to
+    /// # Return
+    /// Returns success of write
+    ///
+    /// Author: Matthew Krohn
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for row_num in 0..self.rows {
+            for &i in self.row_iter(row_num) {
+                // a precision only changes how floating point elements look
+                match f.precision() {
+                    Some(precision) => write!(f, "{: >6.*} ", precision, i)?,
+                    None => write!(f, "{: >6} ", i)?,
+                }
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// A Matrix read without knowing its element type ahead of time, holding the
+/// narrowest Element its data fits in
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum AnyMatrix {
+    I32(Matrix),
+    I64(Matrix<i64>),
+    F64(Matrix<f64>),
+}
+
+impl AnyMatrix {
+    /// Reads a matrix from JSON, choosing the element type from its data: i32
+    /// if every element is a whole number that fits, i64 if some whole number
+    /// doesn't, and f64 if any element has a fraction or exponent
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The JSON text, with `rows`, `cols` and `data` keys
+    ///
+    /// ```
+    /// # use sample_code::matrix::{AnyMatrix, Matrix};
+    /// let matrix = AnyMatrix::from_json_str(r#"{"rows": 1, "cols": 2, "data": [0.5, 2]}"#).unwrap();
+    /// assert_eq!(matrix, AnyMatrix::F64(Matrix::new(2, 1, vec![0.5, 2.0])));
+    /// ```
+    pub fn from_json_str(s: &str) -> Result<AnyMatrix, MatrixError> {
+        let repr = serde_json::from_str(s).map_err(|e| MatrixError::Format(e.to_string()))?;
+        AnyMatrix::from_repr(repr)
+    }
+
+    /// Builds a matrix from its serialized form, choosing the element type the
+    /// same way as `from_json_str`
+    ///
+    /// # Arguments
+    ///
+    /// * `repr` - The matrix as it was read, with its elements still as JSON
+    ///   numbers
+    fn from_repr(repr: MatrixRepr<serde_json::Number>) -> Result<AnyMatrix, MatrixError> {
+        check_element_limit(repr.rows, repr.cols)?;
+        Matrix::<i32>::check_data_len(repr.cols, repr.rows, repr.data.len())?;
+        let as_i32 =
+            |num: &serde_json::Number| num.as_i64().and_then(|num| i32::try_from(num).ok());
+        if repr.data.iter().any(serde_json::Number::is_f64) {
+            repr.convert(serde_json::Number::as_f64).map(AnyMatrix::F64)
+        } else if repr.data.iter().all(|num| as_i32(num).is_some()) {
+            repr.convert(as_i32).map(AnyMatrix::I32)
+        } else {
+            repr.convert(serde_json::Number::as_i64).map(AnyMatrix::I64)
+        }
+    }
+
+    /// Returns the (rows, cols) shape of the matrix
+    pub fn shape(&self) -> (usize, usize) {
+        match self {
+            AnyMatrix::I32(matr) => (matr.rows(), matr.cols()),
+            AnyMatrix::I64(matr) => (matr.rows(), matr.cols()),
+            AnyMatrix::F64(matr) => (matr.rows(), matr.cols()),
+        }
+    }
+
+    /// Returns the matrix with i64 elements, or None if it holds f64s
+    fn to_i64(&self) -> Option<Matrix<i64>> {
+        match self {
+            AnyMatrix::I32(matr) => Some(matr.map(i64::from)),
+            AnyMatrix::I64(matr) => Some(matr.clone()),
+            AnyMatrix::F64(_) => None,
+        }
+    }
+
+    /// Returns the matrix with f64 elements; an i64 too large to be exact is
+    /// rounded to the nearest f64
+    fn to_f64(&self) -> Matrix<f64> {
+        match self {
+            AnyMatrix::I32(matr) => matr.map(f64::from),
+            AnyMatrix::I64(matr) => matr.map(|num| num as f64),
+            AnyMatrix::F64(matr) => matr.clone(),
+        }
+    }
+
+    /// Takes the matrix in row-major order, like `Matrix::into_row_major`
+    pub(crate) fn into_row_major(self) -> AnyMatrix {
+        match self {
+            AnyMatrix::I32(matr) => AnyMatrix::I32(matr.into_row_major()),
+            AnyMatrix::I64(matr) => AnyMatrix::I64(matr.into_row_major()),
+            AnyMatrix::F64(matr) => AnyMatrix::F64(matr.into_row_major()),
+        }
+    }
+
+    /// Applies an Operator to two matrices, first widening them both to the
+    /// wider of their element types
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The left operand
+    /// * `operator` - The Operator to apply
+    /// * `rhs` - The right operand
+    /// * `threads` - How many threads an i32 multiplication may use
+    ///
+    /// ```
+    /// # use sample_code::matrix::{AnyMatrix, Matrix};
+    /// # use sample_code::operation::Operator;
+    /// let lhs = AnyMatrix::I32(Matrix::new(2, 1, vec![1, 2]));
+    /// let rhs = AnyMatrix::F64(Matrix::new(2, 1, vec![0.5, 0.25]));
+    /// let sum = lhs.try_apply(Operator::Add, &rhs, 1).unwrap();
+    /// assert_eq!(sum, AnyMatrix::F64(Matrix::new(2, 1, vec![1.5, 2.25])));
+    /// ```
+    pub fn try_apply(
+        &self,
+        operator: Operator,
+        rhs: &AnyMatrix,
+        threads: usize,
+    ) -> Result<AnyMatrix, MatrixError> {
+        match (self, rhs) {
+            (AnyMatrix::I32(lhs), AnyMatrix::I32(rhs)) => operator
+                .try_apply_with_threads(lhs, rhs, threads)
+                .map(AnyMatrix::I32),
+            _ => match self.to_i64().zip(rhs.to_i64()) {
+                Some((lhs, rhs)) => operator.try_apply_elements(&lhs, &rhs).map(AnyMatrix::I64),
+                None => operator
+                    .try_apply_elements(&self.to_f64(), &rhs.to_f64())
+                    .map(AnyMatrix::F64),
+            },
+        }
+    }
+}
+
+impl Display for AnyMatrix {
+    /// Formats the matrix for display, in the same format as Matrix
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            AnyMatrix::I32(matr) => matr.fmt(f),
+            AnyMatrix::I64(matr) => matr.fmt(f),
+            AnyMatrix::F64(matr) => matr.fmt(f),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AnyMatrix {
+    /// Reads the matrix with the narrowest element type its data fits in, like
+    /// `AnyMatrix::from_json_str`
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<AnyMatrix, D::Error> {
+        let repr = MatrixRepr::deserialize(deserializer)?;
+        AnyMatrix::from_repr(repr).map_err(de::Error::custom)
+    }
+}
+
+/// Displays the transpose of the Matrix it borrows
+struct TransposeDisplay<'a>(&'a Matrix);
+
+impl Display for TransposeDisplay<'_> {
+    /// Formats the transpose for display, in the same format as Matrix
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for col_num in 0..self.0.cols {
+            for &i in self.0.col_iter(col_num) {
+                write!(f, "{: >6} ", i)?;
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl Mul<i32> for Matrix {
+    type Output = Self;
+
+    /// Multiplies the Matrix elements by the scalar
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The matrix
+    /// * `rhs` - The scalar to multiply by
+    ///
+    /// Author: Jennifer Kulich
+    fn mul(mut self, rhs: i32) -> Self {
+        self.data.iter_mut().for_each(|num| *num *= rhs);
+        self
+    }
+}
+
+impl Mul<Matrix> for i32 {
+    type Output = Matrix;
+
+    /// Multiplies the Matrix elements by the scalar
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The scalar to multiply by
+    /// * `rhs` - The matrix
+    ///
+    /// Author: Jennifer Kulich
+    fn mul(self, rhs: Matrix) -> Matrix {
+        rhs * self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test matrix multiple
+    /// Author: Jennifer Kulich
+    #[test]
+    fn test_mul_mat_i32() {
+        let our_mat = Matrix {
+            rows: 3,
+            cols: 3,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+            layout: Layout::RowMajor,
+        };
+        let ref_mat = Matrix {
+            rows: 3,
+            cols: 3,
+            data: vec![3, 6, 9, 12, 15, 18, 21, 24, 27],
+            layout: Layout::RowMajor,
+        };
+
+        let new_mat_1 = our_mat.clone() * 3; // need clone since Mul taKendrs by value
+        let new_mat_2 = 3 * our_mat;
+        assert_eq!(ref_mat, new_mat_1);
+        assert_eq!(ref_mat, new_mat_2);
+    }
+
+    /// Test matrix row and column iterators
+    /// Author: Jennifer Kulich
+    #[test]
+    fn test_row_and_col_iter_small() {
+        let our_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+
+        let mut rows: Vec<Vec<&i32>> = Vec::new();
+        let mut cols: Vec<Vec<&i32>> = Vec::new();
+        for row in 0..our_mat.rows {
+            rows.push(our_mat.row_iter(row).collect());
+        }
+        for col in 0..our_mat.cols {
+            cols.push(our_mat.col_iter(col).collect());
+        }
+
+        assert_eq!(rows[0], vec!(&1, &2, &3));
+        assert_eq!(rows[1], vec!(&4, &5, &6));
+
+        assert_eq!(cols[0], vec!(&1, &4));
+        assert_eq!(cols[1], vec!(&2, &5));
+        assert_eq!(cols[2], vec!(&3, &6));
+    }
+
+    /// Test Matrix index
+    /// Author: Jennifer Kulich
+    #[test]
+    fn test_index() {
+        let our_mat = Matrix {
+            cols: 3,
+            rows: 3,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat[[0, 0]], 1);
+        assert_eq!(our_mat[[0, 1]], 2);
+        assert_eq!(our_mat[[0, 2]], 3);
+        assert_eq!(our_mat[[1, 0]], 4);
+        assert_eq!(our_mat[[1, 1]], 5);
+        assert_eq!(our_mat[[1, 2]], 6);
+        assert_eq!(our_mat[[2, 0]], 7);
+        assert_eq!(our_mat[[2, 1]], 8);
+        assert_eq!(our_mat[[2, 2]], 9);
+    }
+
+    /// Test mutation index
+    /// Author: Jennifer Kulich
+    #[test]
+    fn test_index_mut() {
+        let mut our_mat = Matrix {
+            cols: 1,
+            rows: 1,
+            data: vec![1],
+            layout: Layout::RowMajor,
+        };
+        our_mat[[0, 0]] = 2;
+        assert_eq!(our_mat[[0, 0]], 2);
+    }
+
+    /// Test Matrix Multiply
+    /// Author: Jennifer Kulich
+    #[test]
+    fn test_matrix_multiply() {
+        let our_mat1 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let our_mat2 = Matrix {
+            cols: 2,
+            rows: 3,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let result_mat = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![22, 28, 49, 64],
+            layout: Layout::RowMajor,
+        };
+        let new_mat = our_mat1.mul_mat(&our_mat2);
+        assert_eq!(new_mat, result_mat);
+    }
+
+    // Author: Kendric Thompson
+    // This tests the adding of two matrices
+    #[test]
+    fn test_matrix_addition() {
+        let our_mat1 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let our_mat2 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let result_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![2, 4, 6, 8, 10, 12],
+            layout: Layout::RowMajor,
+        };
+        let new_mat = our_mat1.add_mat(&our_mat2);
+        assert_eq!(new_mat, result_mat);
+    }
+
+    // Author: Kendric Thompson
+    // This tests the subbtracting of two matrices
+    #[test]
+    fn test_matrix_subtraction() {
+        let our_mat1 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let our_mat2 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let result_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![0, 0, 0, 0, 0, 0],
+            layout: Layout::RowMajor,
+        };
+        let new_mat = our_mat1.sub_mat(&our_mat2);
+        assert_eq!(new_mat, result_mat);
+    }
+
+    /// Test writing a Matrix as CSV
+    #[test]
+    fn test_write_csv() {
+        let our_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, -2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let mut out = Vec::new();
+        our_mat.write_csv(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "1,-2,3\n4,5,6\n");
+    }
+
+    /// Test a Matrix written as bincode reads back, whatever its layout
+    #[test]
+    fn test_write_bincode() {
+        let our_mat = Matrix {
+            cols: 2,
+            rows: 1,
+            data: vec![1, -1],
+            layout: Layout::RowMajor,
+        };
+        let mut out = Vec::new();
+        our_mat.write_bincode(&mut out).unwrap();
+        assert_eq!(out, bincode::serialize(&our_mat).unwrap());
+        assert_eq!(Matrix::from_bincode_reader(&out[..]), Ok(our_mat.clone()));
+
+        let col_major = our_mat.to_layout(Layout::ColMajor);
+        let mut out = Vec::new();
+        col_major.write_bincode(&mut out).unwrap();
+        let read = Matrix::from_bincode_reader(&out[..]).unwrap();
+        assert_eq!(read, our_mat);
+        assert_eq!(read.layout(), Layout::ColMajor);
+
+        // the version is checked, and the data must fill the shape
+        let mut newer = out.clone();
+        newer[..4].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            Matrix::from_bincode_reader(&newer[..]),
+            Err(MatrixError::Format(_))
+        ));
+        assert!(matches!(
+            Matrix::from_bincode_reader(&out[..out.len() - 4]),
+            Err(MatrixError::Format(_))
+        ));
+    }
+
+    /// Test companion matrix of x^2 - 3x + 2
+    #[test]
+    fn test_companion() {
+        let our_mat = Matrix::companion(&[1, -3, 2]);
+        let result_mat = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![3, -2, 1, 0],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat, result_mat);
+
+        // degree 3: ones on the subdiagonal, zeros elsewhere below the first row
+        let our_mat = Matrix::companion(&[1, 0, 5, -7]);
+        assert_eq!(our_mat.row_iter(0).collect::<Vec<_>>(), vec![&0, &-5, &7]);
+        assert_eq!(our_mat.row_iter(1).collect::<Vec<_>>(), vec![&1, &0, &0]);
+        assert_eq!(our_mat.row_iter(2).collect::<Vec<_>>(), vec![&0, &1, &0]);
+    }
+
+    /// Test companion matrix rejects a constant polynomial
+    #[test]
+    #[should_panic]
+    fn test_companion_degree_zero() {
+        Matrix::companion(&[1]);
+    }
+
+    /// Test element-wise max through zip_map
+    #[test]
+    fn test_zip_map_max() {
+        let our_mat1 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 8, 3, -4, 5, 0],
+            layout: Layout::RowMajor,
+        };
+        let our_mat2 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![6, 5, 4, -3, 2, 0],
+            layout: Layout::RowMajor,
+        };
+        let result_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![6, 8, 4, -3, 5, 0],
+            layout: Layout::RowMajor,
+        };
+        let new_mat = our_mat1.zip_map(&our_mat2, std::cmp::max);
+        assert_eq!(new_mat, result_mat);
+    }
+
+    /// Test zip_map rejects mismatched dimensions
+    #[test]
+    #[should_panic]
+    fn test_zip_map_mismatch() {
+        let our_mat1 = Matrix::new(2, 1, vec![1, 2]);
+        let our_mat2 = Matrix::new(1, 2, vec![1, 2]);
+        our_mat1.zip_map(&our_mat2, |a, b| a + b);
+    }
+
+    /// Test trace and determinant
+    #[test]
+    fn test_trace_and_determinant() {
+        let our_mat = Matrix {
+            cols: 3,
+            rows: 3,
+            data: vec![2, 0, 1, 1, 3, 2, 1, 1, 2],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat.trace(), 7);
+        assert_eq!(our_mat.determinant(), 6);
+
+        let singular = Matrix {
+            cols: 3,
+            rows: 3,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(singular.determinant(), 0);
+    }
+
+    /// Test the debug summary footer of a square and non-square Matrix
+    #[test]
+    fn test_display_with_summary() {
+        let our_mat = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![1, 2, 3, 4],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(
+            our_mat.display_with_summary(),
+            "     1      2 \n     3      4 \nTrace: 5\nDeterminant: -2\n"
+        );
+
+        let our_mat = Matrix {
+            cols: 2,
+            rows: 1,
+            data: vec![1, 2],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat.display_with_summary(), our_mat.to_string());
+
+        let huge = Matrix::new(3, 3, vec![i32::MAX, 0, 0, 0, i32::MAX, 0, 0, 0, i32::MAX]);
+        let summary = huge.display_with_summary();
+        assert!(
+            summary.ends_with("e27 (too large for an i64)\n"),
+            "{}",
+            summary
+        );
+        assert!(summary.contains("Determinant: about 9.9"), "{}", summary);
+    }
+
+    /// Test identity plus a perturbation
+    #[test]
+    fn test_identity_plus() {
+        let perturbation = Matrix {
+            cols: 3,
+            rows: 3,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+            layout: Layout::RowMajor,
+        };
+        let new_mat = Matrix::identity_plus(3, &perturbation).unwrap();
+        for row in 0..3 {
+            for col in 0..3 {
+                let expected = if row == col { 1 } else { 0 } + perturbation[[row, col]];
+                assert_eq!(new_mat[[row, col]], expected);
+            }
+        }
+
+        assert_eq!(
+            Matrix::identity_plus(2, &perturbation),
+            Err(MatrixError::DimensionMismatch {
+                expected: (2, 2),
+                found: (3, 3),
+            })
+        );
+    }
+
+    /// Test indexing and iterating agree across layouts
+    #[test]
+    fn test_layout_conversion() {
+        let row_major = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+        let col_major = row_major.to_layout(Layout::ColMajor);
+
+        assert_eq!(col_major.layout(), Layout::ColMajor);
+        assert_eq!(col_major.data, vec![1, 4, 2, 5, 3, 6]);
+        for row in 0..2 {
+            for col in 0..3 {
+                assert_eq!(row_major[[row, col]], col_major[[row, col]]);
+            }
+            assert!(row_major.row_iter(row).eq(col_major.row_iter(row)));
+        }
+        for col in 0..3 {
+            assert!(row_major.col_iter(col).eq(col_major.col_iter(col)));
+        }
+
+        assert_eq!(col_major, row_major);
+        assert_eq!(col_major.to_layout(Layout::RowMajor).data, row_major.data);
+        assert_eq!(col_major.to_string(), row_major.to_string());
+        assert_eq!(col_major.add_mat(&row_major), 2 * row_major);
+    }
+
+    /// Test the layout is only serialized when it isn't the default
+    #[test]
+    fn test_layout_serde() {
+        let row_major = Matrix::new(2, 1, vec![1, 2]);
+        let json = serde_json::to_string(&row_major).unwrap();
+        assert_eq!(json, r#"{"version":1,"rows":1,"cols":2,"data":[1,2]}"#);
+
+        let col_major = row_major.to_layout(Layout::ColMajor);
+        let json = serde_json::to_string(&col_major).unwrap();
+        let read_back: Matrix = serde_json::from_str(&json).unwrap();
+        assert_eq!(read_back.layout(), Layout::ColMajor);
+        assert_eq!(read_back, row_major);
+    }
+
+    /// Test horizontal and vertical flips
+    #[test]
+    fn test_flips() {
+        let our_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let horizontal = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![3, 2, 1, 6, 5, 4],
+            layout: Layout::RowMajor,
+        };
+        let vertical = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![4, 5, 6, 1, 2, 3],
+            layout: Layout::RowMajor,
+        };
+
+        assert_eq!(our_mat.flip_horizontal(), horizontal);
+        assert_eq!(our_mat.flip_vertical(), vertical);
+        assert_eq!(our_mat.flip_horizontal().flip_horizontal(), our_mat);
+        assert_eq!(our_mat.flip_vertical().flip_vertical(), our_mat);
+    }
+
+    /// Test quarter turn rotations
+    #[test]
+    fn test_rotate_90() {
+        let our_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let clockwise = Matrix {
+            cols: 2,
+            rows: 3,
+            data: vec![4, 1, 5, 2, 6, 3],
+            layout: Layout::RowMajor,
+        };
+        let counter_clockwise = Matrix {
+            cols: 2,
+            rows: 3,
+            data: vec![3, 6, 2, 5, 1, 4],
+            layout: Layout::RowMajor,
+        };
+
+        assert_eq!(our_mat.rotate_90_cw(), clockwise);
+        assert_eq!(our_mat.rotate_90_ccw(), counter_clockwise);
+        assert_eq!(our_mat.rotate_90_cw().rotate_90_ccw(), our_mat);
+
+        let mut rotated = our_mat.clone();
+        for _ in 0..4 {
+            rotated = rotated.rotate_90_cw();
+        }
+        assert_eq!(rotated, our_mat);
+    }
+
+    /// Test element histogram
+    #[test]
+    fn test_histogram() {
+        let our_mat = Matrix {
+            cols: 3,
+            rows: 3,
+            data: vec![5, -1, 5, 0, 5, -1, 7, 0, 5],
+            layout: Layout::RowMajor,
+        };
+        let counts: Vec<(i32, usize)> = our_mat.histogram().into_iter().collect();
+        assert_eq!(counts, vec![(-1, 2), (0, 2), (5, 4), (7, 1)]);
+    }
+
+    /// Test reading whitespace-delimited text
+    #[test]
+    fn test_from_text_reader() {
+        let expected = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, -2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+
+        let tabs = "1\t-2\t3\n4\t5\t6\n";
+        assert_eq!(
+            Matrix::from_text_reader(tabs.as_bytes()),
+            Ok(expected.clone())
+        );
+
+        let spaces = "  1  -2 3\n\n4 5    6";
+        assert_eq!(Matrix::from_text_reader(spaces.as_bytes()), Ok(expected));
+    }
+
+    /// Test text input with ragged rows or bad elements is rejected
+    #[test]
+    fn test_from_text_reader_errors() {
+        assert_eq!(
+            Matrix::from_text_reader("1 2 3\n4 5\n".as_bytes()),
+            Err(MatrixError::RaggedRow {
+                line: 2,
+                expected: 3,
+                found: 2,
+            })
+        );
+
+        match Matrix::from_text_reader("1 2\n3 x\n".as_bytes()) {
+            Err(MatrixError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    /// Test element-wise division reports where a zero divisor is
+    #[test]
+    fn test_try_div_mat() {
+        let our_mat1 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![2, 4, 6, 8, 10, 12],
+            layout: Layout::RowMajor,
+        };
+        let our_mat2 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 0, 6],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(
+            our_mat1.try_div_mat(&our_mat2),
+            Err(MatrixError::DivisionByZero { row: 1, col: 1 })
+        );
+
+        let our_mat2 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, -6],
+            layout: Layout::RowMajor,
+        };
+        let result_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![2, 2, 2, 2, 2, -2],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat1.try_div_mat(&our_mat2), Ok(result_mat));
+    }
+
+    /// Test the anti-diagonal of a 3x3
+    #[test]
+    fn test_anti_diagonal() {
+        let our_mat = Matrix {
+            cols: 3,
+            rows: 3,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat.anti_diagonal(), vec![3, 5, 7]);
+    }
+
+    /// Test saturating scalar multiply clamps instead of wrapping
+    #[test]
+    fn test_saturating_mul_scalar() {
+        let our_mat = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![i32::MAX - 1, i32::MIN / 2, 3, -1],
+            layout: Layout::RowMajor,
+        };
+        let ref_mat = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![i32::MAX, i32::MIN, 6, -2],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat.saturating_mul_scalar(2), ref_mat);
+
+        let ref_mat = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![i32::MIN, i32::MAX, -9, 3],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat.saturating_mul_scalar(-3), ref_mat);
+    }
+
+    /// Test a small Vandermonde matrix
+    #[test]
+    fn test_vandermonde() {
+        let our_mat = Matrix::vandermonde(&[1, 2, -3, 0], 3);
+        assert_eq!(our_mat.rows(), 4);
+        assert_eq!(our_mat.cols(), 4);
+        assert_eq!(
+            our_mat.col_iter(0).collect::<Vec<_>>(),
+            vec![&1, &1, &1, &1]
+        );
+        assert_eq!(
+            our_mat.row_iter(1).collect::<Vec<_>>(),
+            vec![&1, &2, &4, &8]
+        );
+        assert_eq!(our_mat[[2, 2]], 9);
+        assert_eq!(our_mat[[2, 3]], -27);
+        assert_eq!(our_mat[[3, 1]], 0);
+    }
+
+    /// Test sequential multiply matches the concurrent one
+    #[test]
+    fn test_mul_mat_sequential() {
+        let our_mat1 = Matrix::vandermonde(&[1, 2, 3, 4, 5], 3);
+        let our_mat2 = Matrix::vandermonde(&[-1, 0, 1, 2], 5);
+
+        let sequential = our_mat1.mul_mat_sequential(&our_mat2);
+        assert_eq!(sequential, our_mat1.mul_mat(&our_mat2));
+        assert_eq!(sequential.rows(), 5);
+        assert_eq!(sequential.cols(), 6);
+    }
+
+    /// Test a product with no columns is empty with every strategy
+    #[test]
+    fn test_mul_mat_zero_width() {
+        let our_mat1 = Matrix::new(1, 1, vec![1]);
+        let our_mat2 = Matrix::new(0, 1, vec![]);
+        assert_eq!(our_mat1.mul_mat(&our_mat2), Matrix::new(0, 1, vec![]));
+        assert_eq!(
+            our_mat1.mul_mat_threads(&our_mat2, 4),
+            Matrix::new(0, 1, vec![])
+        );
+
+        let our_mat1 = Matrix::new(0, 2, vec![]);
+        let our_mat2 = Matrix::new(0, 0, vec![]);
+        for strategy in [
+            MulStrategy::Sequential,
+            MulStrategy::Parallel,
+            MulStrategy::Blocked,
+            MulStrategy::Strassen,
+        ] {
+            let product = our_mat1.mul_mat_with(&our_mat2, strategy);
+            assert_eq!((product.rows(), product.cols()), (2, 0));
+        }
+        assert_eq!(our_mat1.try_mul(&our_mat2), Ok(Matrix::new(0, 2, vec![])));
+    }
+
+    /// Test the trace of a product matches multiplying first
+    #[test]
+    fn test_trace_of_product() {
+        let our_mat1 = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![1, 2, 3, 4, 5, 6],
+            layout: Layout::RowMajor,
+        };
+        let our_mat2 = Matrix {
+            cols: 2,
+            rows: 3,
+            data: vec![7, -8, 9, 10, -11, 12],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(
+            our_mat1.trace_of_product(&our_mat2),
+            our_mat1.mul_mat(&our_mat2).trace()
+        );
+        assert_eq!(
+            our_mat2.trace_of_product(&our_mat1),
+            our_mat2.mul_mat(&our_mat1).trace()
+        );
+    }
+
+    /// Test orthogonality of rotations and a shear
+    #[test]
+    fn test_is_orthogonal() {
+        // a quarter turn about the z axis
+        let rotation = Matrix {
+            cols: 3,
+            rows: 3,
+            data: vec![0, -1, 0, 1, 0, 0, 0, 0, 1],
+            layout: Layout::RowMajor,
+        };
+        assert!(rotation.is_orthogonal(1e-9));
+        assert!(Matrix::identity(4).is_orthogonal(1e-9));
+
+        let shear = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![1, 1, 0, 1],
+            layout: Layout::RowMajor,
+        };
+        assert!(!shear.is_orthogonal(1e-9));
+        assert!(!Matrix::new(2, 1, vec![1, 0]).is_orthogonal(1e-9));
+    }
+
+    /// Test horizontal and vertical concatenation
+    #[test]
+    fn test_concat() {
+        let our_mat1 = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![1, 2, 3, 4],
+            layout: Layout::RowMajor,
+        };
+        let our_mat2 = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![5, 6, 7, 8],
+            layout: Layout::RowMajor,
+        };
+        let horizontal = Matrix {
+            cols: 4,
+            rows: 2,
+            data: vec![1, 2, 5, 6, 3, 4, 7, 8],
+            layout: Layout::RowMajor,
+        };
+        let vertical = Matrix {
+            cols: 2,
+            rows: 4,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat1.hconcat(&our_mat2), horizontal);
+        assert_eq!(our_mat1.vconcat(&our_mat2), vertical);
+    }
+
+    /// Test parsing an ASCII picture
+    #[test]
+    fn test_from_ascii_art() {
+        let art = "\
+.#.
+###
+# .
+";
+        let expected = Matrix {
+            cols: 3,
+            rows: 3,
+            data: vec![0, 1, 0, 1, 1, 1, 1, 0, 0],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(Matrix::from_ascii_art(art), Ok(expected));
+
+        assert_eq!(
+            Matrix::from_ascii_art("##\n#"),
+            Err(MatrixError::RaggedRow {
+                line: 2,
+                expected: 2,
+                found: 1,
+            })
+        );
+        match Matrix::from_ascii_art("#x") {
+            Err(MatrixError::Parse { line, .. }) => assert_eq!(line, 1),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    /// Test rescaling into [0, 100]
+    #[test]
+    fn test_scale_to_range() {
+        let our_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![2, 3, 4, 6, 10, 2],
+            layout: Layout::RowMajor,
+        };
+        let ref_mat = Matrix {
+            cols: 3,
+            rows: 2,
+            data: vec![0, 12, 25, 50, 100, 0],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat.scale_to_range(0, 100), ref_mat);
+
+        let constant = Matrix::new(2, 1, vec![7, 7]);
+        assert_eq!(
+            constant.scale_to_range(-5, 5),
+            Matrix::new(2, 1, vec![-5, -5])
+        );
+
+        let full_range = Matrix::new(3, 1, vec![i32::MIN, 0, i32::MAX]);
+        assert_eq!(full_range.scale_to_range(i32::MIN, i32::MAX), full_range);
+        assert_eq!(
+            full_range.scale_to_range(i32::MAX, i32::MIN),
+            Matrix::new(3, 1, vec![i32::MAX, -1, i32::MIN])
+        );
+    }
+
+    /// Builds a matrix of pseudo-random elements in `-range..=range`
+    fn random_matrix(cols: usize, rows: usize, range: i32, seed: u64) -> Matrix {
+        let mut state = seed;
+        let data = (0..cols * rows)
+            .map(|_| {
+                // a linear congruential generator is plenty for test data
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                ((state >> 33) % (2 * range as u64 + 1)) as i32 - range
+            })
+            .collect();
+        Matrix::new(cols, rows, data)
+    }
+
+    /// Test the LU determinant matches cofactor expansion
+    #[test]
+    fn test_determinant_lu() {
+        for seed in 0..20 {
+            let our_mat = random_matrix(5, 5, 9, seed);
+            let expected = our_mat.determinant() as f64;
+            for &parallel in &[false, true] {
+                let found = our_mat.lu_determinant(parallel);
+                assert!(
+                    (found - expected).abs() <= 1e-6 * expected.abs().max(1.0),
+                    "seed {}: expected {}, found {}",
+                    seed,
+                    expected,
+                    found
+                );
+            }
+        }
+
+        let singular = Matrix::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(singular.determinant_lu().abs() < 1e-9);
+        assert_eq!(Matrix::identity(PARALLEL_LU_SIZE).determinant_lu(), 1.0);
+    }
+
+    /// Benchmark the LU determinant of a 200x200, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn bench_determinant_lu() {
+        let our_mat = random_matrix(200, 200, 9, 42);
+        for &parallel in &[false, true] {
+            let start = std::time::Instant::now();
+            for _ in 0..10 {
+                our_mat.lu_determinant(parallel);
+            }
+            println!(
+                "200x200 LU determinant, parallel: {}: {:?} per run",
+                parallel,
+                start.elapsed() / 10
+            );
+        }
+    }
+
+    /// Test a Matrix survives a round trip through TOML
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_round_trip() {
+        let our_mat = Matrix::new(3, 2, vec![1, -2, 3, 4, 5, 6]);
+        let text = our_mat.to_toml().unwrap();
+        assert_eq!(
+            text,
+            "version = 1\nrows = 2\ncols = 3\ndata = [1, -2, 3, 4, 5, 6]\n"
+        );
+        assert_eq!(Matrix::from_toml(&text), Ok(our_mat));
+
+        let col_major = Matrix::new(2, 2, vec![1, 2, 3, 4]).to_layout(Layout::ColMajor);
+        let read_back = Matrix::from_toml(&col_major.to_toml().unwrap()).unwrap();
+        assert_eq!(read_back.layout(), Layout::ColMajor);
+        assert_eq!(read_back, col_major);
+
+        assert!(Matrix::from_toml("rows = 1").is_err());
+    }
+
+    /// Test padding with different widths on each side
+    #[test]
+    fn test_pad() {
+        let our_mat = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![1, 2, 3, 4],
+            layout: Layout::RowMajor,
+        };
+        let padded = our_mat.pad(1, 0, 2, 1, -1);
+        let ref_mat = Matrix {
+            cols: 5,
+            rows: 3,
+            data: vec![-1, -1, -1, -1, -1, -1, -1, 1, 2, -1, -1, -1, 3, 4, -1],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(padded.rows(), 3);
+        assert_eq!(padded.cols(), 5);
+        assert_eq!(padded, ref_mat);
+        assert_eq!(our_mat.pad(0, 0, 0, 0, 9), our_mat);
+    }
+
+    /// Test convolving with an edge-detection kernel
+    #[test]
+    fn test_convolve() {
+        let our_mat = Matrix {
+            cols: 4,
+            rows: 4,
+            data: vec![3, 0, 1, 2, 1, 5, 8, 9, 2, 7, 2, 5, 0, 1, 3, 1],
+            layout: Layout::RowMajor,
+        };
+        let laplacian = Matrix {
+            cols: 3,
+            rows: 3,
+            data: vec![-1, -1, -1, -1, 8, -1, -1, -1, -1],
+            layout: Layout::RowMajor,
+        };
+        // each output is 8 * center minus its eight neighbours
+        let ref_mat = Matrix {
+            cols: 2,
+            rows: 2,
+            data: vec![16, 33, 34, -23],
+            layout: Layout::RowMajor,
+        };
+        assert_eq!(our_mat.convolve(&laplacian), ref_mat);
+
+        // a flat region has no edges
+        let flat = Matrix::new(3, 3, vec![4; 9]);
+        assert_eq!(flat.convolve(&laplacian), Matrix::new(1, 1, vec![0]));
+    }
+
+    /// Test one step of reachability on a small graph
+    #[test]
+    fn test_boolean_reachability() {
+        // edges 0 -> 1, 1 -> 2, 2 -> 3, 3 -> 3
+        let adjacency = Matrix::new(4, 4, vec![0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 1])
+            .map(|num| num != 0);
+        let two_steps = Matrix::new(4, 4, vec![0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1])
+            .map(|num| num != 0);
+        let within_two = Matrix::new(4, 4, vec![0, 1, 1, 0, 0, 0, 1, 1, 0, 0, 0, 1, 0, 0, 0, 1])
+            .map(|num| num != 0);
+
+        let step = adjacency.boolean_mul(&adjacency);
+        assert_eq!(step, two_steps);
+        assert_eq!(adjacency.logical_or(&step), within_two);
+        let both = Matrix::new(4, 4, vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1])
+            .map(|num| num != 0);
+        assert_eq!(adjacency.logical_and(&step), both);
+
+        // the layout of either operand doesn't change the product
+        let col_major = adjacency.to_layout(Layout::ColMajor);
+        assert_eq!(col_major.boolean_mul(&adjacency), two_steps);
+    }
+
+    /// Test transposing swaps rows and columns in either layout
+    #[test]
+    fn test_transpose() {
+        let our_mat = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+        let expected = Matrix::new(2, 3, vec![1, 4, 2, 5, 3, 6]);
+
+        assert_eq!(our_mat.transpose(), expected);
+        assert_eq!(our_mat.to_layout(Layout::ColMajor).transpose(), expected);
+        assert_eq!(our_mat.transpose().transpose(), our_mat);
+    }
+
+    /// Test the symmetric product matches the full multiply
+    #[test]
+    fn test_self_gram_t() {
+        for &(cols, rows) in [(3, 2), (2, 3), (5, 5), (1, 4)].iter() {
+            let our_mat = random_matrix(cols, rows, 10, (cols * rows) as u64);
+            assert_eq!(our_mat.self_gram_t(), our_mat.mul_mat(&our_mat.transpose()));
+        }
+    }
+
+    /// Test the shape header is rows by columns and followed by the grid
+    #[test]
+    fn test_display_with_header() {
+        let our_mat = Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            our_mat.display_with_header(),
+            "Matrix 3x2:\n     1      2 \n     3      4 \n     5      6 \n"
+        );
+        assert_eq!(
+            our_mat.transpose().display_with_header().lines().next(),
+            Some("Matrix 2x3:")
+        );
+    }
+
+    /// Test the determinant sign of positive, negative, singular and
+    /// non-square matrices
+    #[test]
+    fn test_determinant_sign() {
+        assert_eq!(
+            Matrix::new(2, 2, vec![2, 1, 1, 3]).determinant_sign(),
+            Some(1)
+        );
+        assert_eq!(
+            Matrix::new(2, 2, vec![1, 2, 3, 4]).determinant_sign(),
+            Some(-1)
+        );
+        assert_eq!(
+            Matrix::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).determinant_sign(),
+            Some(0)
+        );
+        assert_eq!(
+            Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]).determinant_sign(),
+            None
+        );
+
+        // agrees with the exact determinant
+        for seed in 0..20 {
+            let our_mat = random_matrix(4, 4, 5, seed);
+            assert_eq!(
+                our_mat.determinant_sign(),
+                Some(our_mat.determinant().signum() as i32)
+            );
+        }
+
+        // exact where floating point rounding loses the -1
+        let close = Matrix::new(
+            2,
+            2,
+            vec![i32::MAX, i32::MAX - 1, i32::MAX - 1, i32::MAX - 2],
+        );
+        assert_eq!(close.determinant_sign(), Some(-1));
+
+        // too large for an i64, but not for the sign
+        let huge = Matrix::new(3, 3, vec![i32::MAX, 0, 0, 0, i32::MIN, 0, 0, 0, i32::MAX]);
+        assert!(huge.try_determinant().is_err());
+        assert_eq!(huge.determinant_sign(), Some(-1));
+    }
+
+    /// Test a weighted sum matches scaling then adding
+    #[test]
+    fn test_weighted_sum() {
+        let our_mat1 = random_matrix(3, 2, 10, 1);
+        let our_mat2 = random_matrix(3, 2, 10, 2).to_layout(Layout::ColMajor);
+
+        let expected = (2 * our_mat1.clone()).add_mat(&(3 * our_mat2.clone()));
+        assert_eq!(weighted_sum(&[(2, &our_mat1), (3, &our_mat2)]), expected);
+        assert_eq!(weighted_sum(&[(-1, &our_mat1)]), -1 * our_mat1);
+    }
+
+    /// Test a weighted sum of matrices of different shapes panics
+    #[test]
+    #[should_panic]
+    fn test_weighted_sum_mismatch() {
+        let our_mat1 = Matrix::new(2, 1, vec![1, 2]);
+        let our_mat2 = Matrix::new(1, 2, vec![1, 2]);
+        weighted_sum(&[(1, &our_mat1), (1, &our_mat2)]);
+    }
+
+    /// Test masking everything above the diagonal
+    #[test]
+    fn test_mask() {
+        let our_mat = Matrix::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        let expected = Matrix::new(3, 3, vec![1, 0, 0, 4, 5, 0, 7, 8, 9]);
+
+        assert_eq!(our_mat.mask(|row, col| col <= row, 0), expected);
+        assert_eq!(
+            our_mat
+                .to_layout(Layout::ColMajor)
+                .mask(|row, col| col <= row, 0),
+            expected
+        );
+        assert_eq!(our_mat.mask(|_, _| true, -1), our_mat);
+        assert_eq!(
+            our_mat.mask(|_, _| false, -1),
+            Matrix::new(3, 3, vec![-1; 9])
+        );
+    }
+
+    /// Test a range matrix has the right shape and counts up row by row
+    #[test]
+    fn test_from_range() {
+        let our_mat = Matrix::from_range(3, 2, 1);
+        assert_eq!((our_mat.rows(), our_mat.cols()), (2, 3));
+        assert_eq!(our_mat, Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]));
+        assert_eq!(our_mat.row_iter(1).collect::<Vec<_>>(), vec![&4, &5, &6]);
+
+        assert_eq!(
+            Matrix::from_range(2, 2, -2),
+            Matrix::new(2, 2, vec![-2, -1, 0, 1])
+        );
+        assert_eq!(Matrix::from_range(0, 3, 5), Matrix::new(0, 3, vec![]));
+    }
+
+    /// Test the flat array can be given as `values`, and is written as `data`
+    #[test]
+    fn test_values_alias() {
+        let our_mat: Matrix =
+            serde_json::from_str(r#"{"rows":2,"cols":2,"values":[1,2,3,4]}"#).unwrap();
+        assert_eq!(our_mat, Matrix::new(2, 2, vec![1, 2, 3, 4]));
+        assert_eq!(
+            serde_json::to_string(&our_mat).unwrap(),
+            r#"{"version":1,"rows":2,"cols":2,"data":[1,2,3,4]}"#
+        );
+
+        assert!(
+            serde_json::from_str::<Matrix>(r#"{"rows":1,"cols":1,"data":[1],"values":[1]}"#)
+                .is_err()
+        );
+    }
+
+    /// Test multiplying by a transpose matches transposing first
+    #[test]
+    fn test_transpose_mul() {
+        let our_mat1 = random_matrix(3, 4, 10, 5);
+        let our_mat2 = random_matrix(2, 4, 10, 6);
+        assert_eq!(
+            our_mat1.transpose_mul(&our_mat2),
+            our_mat1.transpose().mul_mat(&our_mat2)
+        );
+        assert_eq!(
+            our_mat1
+                .to_layout(Layout::ColMajor)
+                .transpose_mul(&our_mat2),
+            our_mat1.transpose().mul_mat(&our_mat2)
+        );
+    }
+
+    /// Builds the bytes of a version 1.0 `.npy` file
+    fn npy_bytes(header: &str, data: &[i32]) -> Vec<u8> {
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend(&(header.len() as u16).to_le_bytes());
+        bytes.extend(header.as_bytes());
+        for num in data {
+            bytes.extend(&num.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Test reading the `.npy` fixture
+    #[test]
+    fn test_from_npy() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("data")
+            .join("small.npy");
+        let our_mat = Matrix::from_npy(std::fs::File::open(path).unwrap()).unwrap();
+        assert_eq!(our_mat, Matrix::new(3, 2, vec![1, -2, 3, 4, 5, -6]));
+    }
+
+    /// Test Fortran ordered arrays are read column by column, and other
+    /// dtypes and shapes are rejected
+    #[test]
+    fn test_from_npy_header() {
+        let bytes = npy_bytes(
+            "{'descr': '<i4', 'fortran_order': True, 'shape': (2, 2), }\n",
+            &[1, 3, 2, 4],
+        );
+        let our_mat = Matrix::from_npy(&bytes[..]).unwrap();
+        assert_eq!(our_mat.layout(), Layout::ColMajor);
+        assert_eq!(our_mat, Matrix::new(2, 2, vec![1, 2, 3, 4]));
+
+        let bytes = npy_bytes(
+            "{'descr': '<f8', 'fortran_order': False, 'shape': (1, 1), }\n",
+            &[0, 0],
+        );
+        assert!(matches!(
+            Matrix::from_npy(&bytes[..]),
+            Err(MatrixError::Format(_))
+        ));
+
+        let bytes = npy_bytes(
+            "{'descr': '<i4', 'fortran_order': False, 'shape': (3,), }\n",
+            &[1, 2, 3],
+        );
+        assert!(matches!(
+            Matrix::from_npy(&bytes[..]),
+            Err(MatrixError::Format(_))
+        ));
+
+        // the data is cut short
+        let bytes = npy_bytes(
+            "{'descr': '<i4', 'fortran_order': False, 'shape': (2, 2), }\n",
+            &[1, 2, 3],
+        );
+        assert!(matches!(
+            Matrix::from_npy(&bytes[..]),
+            Err(MatrixError::Io(_))
+        ));
+
+        assert!(Matrix::from_npy(&b"not numpy"[..]).is_err());
+
+        // a shape too large to allocate fails before anything is allocated
+        let huge = format!(
+            "{{'descr': '<i4', 'fortran_order': False, 'shape': ({}, 2), }}\n",
+            usize::MAX
+        );
+        assert_eq!(
+            Matrix::from_npy(&npy_bytes(&huge, &[])[..]),
+            Err(MatrixError::DimensionOverflow {
+                rows: usize::MAX,
+                cols: 2
+            })
+        );
+        let bytes = npy_bytes(
+            "{'descr': '<i4', 'fortran_order': False, 'shape': (100000, 100000), }\n",
+            &[],
+        );
+        assert!(matches!(
+            with_element_limit(Some(100), || Matrix::from_npy(&bytes[..])),
+            Err(MatrixError::TooManyElements { limit: 100, .. })
+        ));
+        assert!(matches!(
+            Matrix::from_npy(&bytes[..]),
+            Err(MatrixError::Io(_))
+        ));
+    }
+
+    /// Test dimensions whose product overflows give an error, not a wrapped
+    /// element count
+    #[test]
+    fn test_try_new() {
+        assert_eq!(
+            Matrix::<i32>::try_new(usize::MAX, 2, vec![]),
+            Err(MatrixError::DimensionOverflow {
+                rows: 2,
+                cols: usize::MAX
+            })
+        );
+        // wraps around to 0 with unchecked multiplication
+        let half = usize::MAX / 2 + 1;
+        assert_eq!(
+            Matrix::<i32>::try_new(2, half, vec![]),
+            Err(MatrixError::DimensionOverflow {
+                rows: half,
+                cols: 2
+            })
+        );
+
+        assert_eq!(
+            Matrix::try_new(2, 2, vec![1, 2, 3]),
+            Err(MatrixError::DataLength {
+                expected: 4,
+                found: 3
+            })
+        );
+        assert_eq!(
+            Matrix::try_new(2, 1, vec![1, 2]),
+            Ok(Matrix::new(2, 1, vec![1, 2]))
+        );
+    }
+
+    /// Test remainders take the sign of the element
+    #[test]
+    fn test_rem_scalar() {
+        let our_mat = Matrix::new(3, 2, vec![7, -7, 6, -1, 0, i32::MIN]);
+        assert_eq!(
+            our_mat.rem_scalar(3),
+            Matrix::new(3, 2, vec![1, -1, 0, -1, 0, -2])
+        );
+        assert_eq!(
+            our_mat.rem_scalar(-3),
+            Matrix::new(3, 2, vec![1, -1, 0, -1, 0, -2])
+        );
+        assert_eq!(our_mat.rem_scalar(-1), Matrix::new(3, 2, vec![0; 6]));
+        assert_eq!(
+            our_mat.to_layout(Layout::ColMajor).rem_scalar(3),
+            our_mat.rem_scalar(3)
+        );
+    }
+
+    /// Test a remainder by zero panics
+    #[test]
+    #[should_panic]
+    fn test_rem_scalar_zero() {
+        Matrix::new(1, 1, vec![1]).rem_scalar(0);
+    }
+
+    /// Test copying a block out of a matrix
+    #[test]
+    fn test_submatrix() {
+        let our_mat = Matrix::from_range(4, 4, 1);
+        assert_eq!(
+            our_mat.submatrix(1, 2, 2, 2),
+            Matrix::new(2, 2, vec![7, 8, 11, 12])
+        );
+        assert_eq!(
+            our_mat.to_layout(Layout::ColMajor).submatrix(1, 2, 2, 2),
+            Matrix::new(2, 2, vec![7, 8, 11, 12])
+        );
+        assert_eq!(our_mat.submatrix(0, 0, 4, 4), our_mat);
+    }
+
+    /// Test writing a block leaves the cells around it untouched
+    #[test]
+    fn test_set_submatrix() {
+        let mut our_mat = Matrix::from_range(4, 4, 1);
+        let block = Matrix::new(2, 2, vec![-1, -2, -3, -4]);
+        our_mat.set_submatrix(1, 1, &block);
+        assert_eq!(
+            our_mat,
+            Matrix::new(
+                4,
+                4,
+                vec![1, 2, 3, 4, 5, -1, -2, 8, 9, -3, -4, 12, 13, 14, 15, 16]
+            )
+        );
+        assert_eq!(our_mat.submatrix(1, 1, 2, 2), block);
+
+        let mut our_mat = Matrix::from_range(4, 4, 1).to_layout(Layout::ColMajor);
+        our_mat.set_submatrix(2, 2, &block);
+        assert_eq!(our_mat.submatrix(2, 2, 2, 2), block);
+        assert_eq!(our_mat[[1, 1]], 6);
+    }
+
+    /// Test a block that would hang off the edge panics
+    #[test]
+    #[should_panic]
+    fn test_set_submatrix_out_of_bounds() {
+        let mut our_mat = Matrix::from_range(4, 4, 1);
+        our_mat.set_submatrix(3, 0, &Matrix::new(2, 2, vec![0; 4]));
+    }
+
+    /// Test a similarity transform keeps the trace and determinant
+    #[test]
+    fn test_similarity_transform() {
+        let our_mat = Matrix::new(3, 3, vec![2, -1, 0, 1, 3, 4, 0, 5, -2]);
+        let p = Matrix::new(3, 3, vec![1, 2, 0, 0, 1, 1, 1, 0, 1]);
+        let transformed = our_mat.similarity_transform(&p).unwrap();
+
+        assert!((transformed.trace() - our_mat.trace() as f64).abs() < 1e-9);
+        assert!((transformed.determinant() - our_mat.determinant() as f64).abs() < 1e-9);
+
+        let singular = Matrix::new(3, 3, vec![1, 2, 3, 2, 4, 6, 0, 0, 1]);
+        assert_eq!(our_mat.similarity_transform(&singular), None);
+    }
+
+    /// Test the parallel sum matches the sequential one, including past the
+    /// range of an i32
+    #[test]
+    fn test_sum_parallel() {
+        let our_mat = random_matrix(300, 200, 1000, 7);
+        for threads in 1..6 {
+            assert_eq!(our_mat.sum_in_chunks(threads), our_mat.sum());
+        }
+        assert_eq!(our_mat.sum_parallel(), our_mat.sum());
+
+        let our_mat = Matrix::new(4, 1, vec![i32::MAX; 4]);
+        assert_eq!(our_mat.sum_in_chunks(3), 4 * i64::from(i32::MAX));
+        assert_eq!(Matrix::new(0, 0, vec![]).sum_in_chunks(4), 0);
+    }
+
+    /// Benchmark summing a 2000x2000, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn bench_sum_parallel() {
+        let our_mat = random_matrix(2000, 2000, 1000, 42);
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            our_mat.sum();
+        }
+        println!("2000x2000 sum: {:?} per run", start.elapsed() / 10);
+
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            our_mat.sum_parallel();
+        }
+        println!("2000x2000 parallel sum: {:?} per run", start.elapsed() / 10);
+    }
+
+    /// Test the transpose view formats the same as the transpose
+    #[test]
+    fn test_transpose_display() {
+        let our_mat = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            our_mat.transpose_display().to_string(),
+            our_mat.transpose().to_string()
+        );
+        assert_eq!(
+            our_mat.transpose_display().to_string(),
+            "     1      4 \n     2      5 \n     3      6 \n"
+        );
+        let col_major = our_mat.to_layout(Layout::ColMajor);
+        assert_eq!(
+            col_major.transpose_display().to_string(),
+            our_mat.transpose().to_string()
+        );
+    }
+
+    /// Test parsing the compact RxC:elements form
+    #[test]
+    fn test_from_compact() {
+        assert_eq!(
+            Matrix::from_compact("2x2:1,2,3,4"),
+            Ok(Matrix::new(2, 2, vec![1, 2, 3, 4]))
+        );
+        assert_eq!(
+            Matrix::from_compact("1x3: -1, 0, 7"),
+            Ok(Matrix::new(3, 1, vec![-1, 0, 7]))
+        );
+        assert_eq!(Matrix::from_compact("0x0:"), Ok(Matrix::new(0, 0, vec![])));
+
+        assert_eq!(
+            Matrix::from_compact("2x2:1,2,3"),
+            Err(MatrixError::DataLength {
+                expected: 4,
+                found: 3
+            })
+        );
+        assert!(matches!(
+            Matrix::from_compact("2x2:1,2,three,4"),
+            Err(MatrixError::Parse { line: 1, .. })
+        ));
+        assert_eq!(
+            Matrix::from_compact("2x2:1,2,3,4,"),
+            Ok(Matrix::new(2, 2, vec![1, 2, 3, 4]))
+        );
+        // an empty element is pointed at, not dropped
+        for s in ["2x2:1,2,,4", "2x2:,1,2,3", "2x2:1,2,3,4,,", "1x1:,"].iter() {
+            match Matrix::from_compact(s) {
+                Err(MatrixError::Parse { line: 1, message }) => {
+                    assert!(message.starts_with("\"\""), "{}", message)
+                }
+                other => panic!("expected a parse error for {:?}, found {:?}", s, other),
+            }
+        }
+        for s in ["1,2,3,4", "2:1,2", "2by2:1,2,3,4", "ax2:1,2"].iter() {
+            assert!(matches!(
+                Matrix::from_compact(s),
+                Err(MatrixError::Format(_))
+            ));
+        }
+    }
+
+    /// Test the shape compatibility checks
+    #[test]
+    fn test_shape_compatibility() {
+        let two_by_three = Matrix::from_range(3, 2, 0);
+        let three_by_two = Matrix::from_range(2, 3, 0);
+
+        assert!(two_by_three.same_shape(&Matrix::new(3, 2, vec![0; 6])));
+        assert!(two_by_three.same_shape(&two_by_three.to_layout(Layout::ColMajor)));
+        assert!(!two_by_three.same_shape(&three_by_two));
+
+        assert!(two_by_three.can_multiply(&three_by_two));
+        assert!(three_by_two.can_multiply(&two_by_three));
+        assert!(!two_by_three.can_multiply(&two_by_three));
+    }
+
+    /// Test finding the rows and columns that are all zero
+    #[test]
+    fn test_zero_rows_cols() {
+        let our_mat = Matrix::new(3, 3, vec![1, 0, 2, 0, 0, 0, 3, 0, 4]);
+        assert_eq!(our_mat.zero_rows(), vec![1]);
+        assert_eq!(our_mat.zero_cols(), vec![1]);
+
+        let col_major = our_mat.to_layout(Layout::ColMajor);
+        assert_eq!(col_major.zero_rows(), vec![1]);
+        assert_eq!(col_major.zero_cols(), vec![1]);
+
+        assert!(Matrix::identity(3).zero_rows().is_empty());
+        assert_eq!(Matrix::new(2, 1, vec![0, 0]).zero_cols(), vec![0, 1]);
+    }
+
+    /// Test reading a Matrix from valid and invalid JSON
+    #[test]
+    fn test_from_json_str() {
+        assert_eq!(
+            Matrix::from_json_str(r#"{"rows": 2, "cols": 1, "data": [1, 2]}"#),
+            Ok(Matrix::new(1, 2, vec![1, 2]))
+        );
+        assert_eq!(
+            Matrix::from_json_str(
+                r#"{"rows": 1, "cols": 2, "data": [1, 2], "layout": "ColMajor"}"#
+            ),
+            Ok(Matrix::new(2, 1, vec![1, 2]))
+        );
+
+        assert!(matches!(
+            Matrix::from_json_str(r#"{"rows": 1, "cols": 2, "data": [1, 2"#),
+            Err(MatrixError::Format(_))
+        ));
+        assert!(matches!(
+            Matrix::from_json_str(r#"{"rows": 1, "data": [1, 2]}"#),
+            Err(MatrixError::Format(_))
+        ));
+        assert_eq!(
+            Matrix::from_json_str(r#"{"rows": 2, "cols": 2, "data": [1, 2]}"#),
+            Err(MatrixError::DataLength {
+                expected: 4,
+                found: 2
+            })
+        );
+    }
+
+    /// Test max-pooling a 4x4 in 2x2 blocks
+    #[test]
+    fn test_max_pool() {
+        let our_mat = Matrix::new(
+            4,
+            4,
+            vec![1, 3, 2, 1, 4, -2, 0, 6, -5, -1, 7, 7, -3, -8, 0, 2],
+        );
+        let expected = Matrix::new(2, 2, vec![4, 6, -1, 7]);
+        assert_eq!(our_mat.max_pool(2, 2), expected);
+        assert_eq!(our_mat.to_layout(Layout::ColMajor).max_pool(2, 2), expected);
+        assert_eq!(our_mat.max_pool(1, 1), our_mat);
+        assert_eq!(our_mat.max_pool(4, 4), Matrix::new(1, 1, vec![7]));
+        assert_eq!(our_mat.max_pool(1, 4), Matrix::new(1, 4, vec![3, 6, 7, 2]));
+    }
+
+    /// Test pooling with blocks that don't divide the matrix panics
+    #[test]
+    #[should_panic]
+    fn test_max_pool_uneven() {
+        Matrix::from_range(3, 3, 0).max_pool(2, 2);
+    }
+
+    /// Test average-pooling a 4x4 in 2x2 blocks rounds toward zero
+    #[test]
+    fn test_avg_pool() {
+        let our_mat = Matrix::new(
+            4,
+            4,
+            vec![1, 3, 2, 1, 4, -2, 0, 6, -5, -1, 7, 7, -3, -8, 0, 2],
+        );
+        // block sums are 6, 9, -17 and 16
+        let expected = Matrix::new(2, 2, vec![1, 2, -4, 4]);
+        assert_eq!(our_mat.avg_pool(2, 2), expected);
+        assert_eq!(our_mat.to_layout(Layout::ColMajor).avg_pool(2, 2), expected);
+        assert_eq!(our_mat.avg_pool(1, 1), our_mat);
+
+        let our_mat = Matrix::new(2, 1, vec![i32::MAX, i32::MAX]);
+        assert_eq!(our_mat.avg_pool(1, 2), Matrix::new(1, 1, vec![i32::MAX]));
+    }
+
+    /// Test building a Matrix from rows or columns round trips
+    #[test]
+    fn test_from_rows_columns() {
+        let our_mat = Matrix::from_range(3, 2, 1);
+        let rows = vec![vec![1, 2, 3], vec![4, 5, 6]];
+        let cols = vec![vec![1, 4], vec![2, 5], vec![3, 6]];
+
+        assert_eq!(our_mat.to_rows(), rows);
+        assert_eq!(our_mat.to_columns(), cols);
+        assert_eq!(Matrix::from_rows(rows.clone()), Ok(our_mat.clone()));
+        assert_eq!(Matrix::from_columns(cols.clone()), Ok(our_mat.clone()));
+        assert_eq!(
+            Matrix::from_columns(our_mat.to_columns())
+                .unwrap()
+                .to_rows(),
+            rows
+        );
+        assert_eq!(
+            Matrix::from_columns(cols).unwrap().layout(),
+            Layout::RowMajor
+        );
+
+        assert_eq!(
+            Matrix::from_columns(vec![vec![1, 2], vec![3]]),
+            Err(MatrixError::RaggedRow {
+                line: 2,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(Matrix::from_columns(vec![]), Ok(Matrix::new(0, 0, vec![])));
+        assert_eq!(
+            Matrix::from_rows(vec![vec![], vec![]]),
+            Ok(Matrix::new(0, 2, vec![]))
+        );
+    }
+
+    /// Test recognising permutation matrices
+    #[test]
+    fn test_is_permutation_matrix() {
+        assert!(Matrix::new(3, 3, vec![0, 1, 0, 0, 0, 1, 1, 0, 0]).is_permutation_matrix());
+        assert!(Matrix::identity(4).is_permutation_matrix());
+        assert!(Matrix::identity(4)
+            .to_layout(Layout::ColMajor)
+            .is_permutation_matrix());
+
+        // a repeated 1 in a column
+        assert!(!Matrix::new(3, 3, vec![0, 1, 0, 0, 1, 0, 1, 0, 0]).is_permutation_matrix());
+        // a row with two 1s
+        assert!(!Matrix::new(2, 2, vec![1, 1, 0, 0]).is_permutation_matrix());
+        assert!(!Matrix::new(2, 2, vec![2, 0, 0, 1]).is_permutation_matrix());
+        assert!(!Matrix::new(2, 2, vec![-1, 0, 0, 1]).is_permutation_matrix());
+        assert!(!Matrix::new(2, 1, vec![1, 0]).is_permutation_matrix());
+    }
+
+    /// Test adding and subtracting a constant shifts every element
+    #[test]
+    fn test_add_sub_scalar() {
+        let our_mat = Matrix::new(3, 2, vec![1, -2, 0, 4, -5, 6]);
+        assert_eq!(
+            our_mat.add_scalar(2),
+            Matrix::new(3, 2, vec![3, 0, 2, 6, -3, 8])
+        );
+        assert_eq!(
+            our_mat.add_scalar(-2),
+            Matrix::new(3, 2, vec![-1, -4, -2, 2, -7, 4])
+        );
+        assert_eq!(our_mat.sub_scalar(2), our_mat.add_scalar(-2));
+        assert_eq!(our_mat.sub_scalar(-2), our_mat.add_scalar(2));
+        assert_eq!(our_mat.add_scalar(0), our_mat);
+
+        let col_major = our_mat.to_layout(Layout::ColMajor);
+        assert_eq!(col_major.add_scalar(2), our_mat.add_scalar(2));
+    }
+
+    /// Test a Matrix without a version is read as version 0, and one from a
+    /// newer version is rejected
+    #[test]
+    fn test_format_version() {
+        let expected = Matrix::new(2, 1, vec![1, 2]);
+        let version_0: Matrix =
+            serde_json::from_str(r#"{"rows":1,"cols":2,"data":[1,2]}"#).unwrap();
+        assert_eq!(version_0, expected);
+        let version_1: Matrix =
+            serde_json::from_str(r#"{"version":1,"rows":1,"cols":2,"data":[1,2]}"#).unwrap();
+        assert_eq!(version_1, expected);
+
+        let err = serde_json::from_str::<Matrix>(r#"{"version":2,"rows":1,"cols":2,"data":[1,2]}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("unsupported format version 2"));
+    }
+
+    /// Test the parallel transpose matches the layout-swapping one
+    #[test]
+    fn test_transpose_parallel() {
+        let our_mat = random_matrix(150, 97, 100, 3);
+        let expected = our_mat.transpose();
+        for threads in 1..5 {
+            let transposed = our_mat.transpose_in_bands(threads);
+            assert_eq!(transposed, expected);
+            assert_eq!(transposed.layout(), Layout::RowMajor);
+        }
+        assert_eq!(
+            our_mat.to_layout(Layout::ColMajor).transpose_parallel(),
+            expected
+        );
+        assert_eq!(
+            Matrix::new(0, 3, vec![]).transpose_parallel(),
+            Matrix::new(3, 0, vec![])
+        );
+    }
+
+    /// Benchmark transposing a 2000x2000 into row-major order, run with
+    /// `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn bench_transpose_parallel() {
+        let our_mat = random_matrix(2000, 2000, 100, 42);
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            our_mat.transpose().to_layout(Layout::RowMajor);
+        }
+        println!(
+            "2000x2000 element by element transpose: {:?} per run",
+            start.elapsed() / 10
+        );
+
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            our_mat.transpose_parallel();
+        }
+        println!(
+            "2000x2000 parallel tiled transpose: {:?} per run",
+            start.elapsed() / 10
+        );
+    }
+
+    /// Test assembling blocks along the diagonal
+    #[test]
+    fn test_block_diagonal() {
+        let block1 = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+        let block2 = Matrix::new(2, 2, vec![5, 6, 7, 8]).to_layout(Layout::ColMajor);
+        let our_mat = Matrix::block_diagonal(&[&block1, &block2]);
+        assert_eq!(
+            our_mat,
+            Matrix::new(4, 4, vec![1, 2, 0, 0, 3, 4, 0, 0, 0, 0, 5, 6, 0, 0, 7, 8])
+        );
+        assert_eq!(our_mat.submatrix(0, 2, 2, 2), Matrix::new(2, 2, vec![0; 4]));
+        assert_eq!(our_mat.submatrix(2, 0, 2, 2), Matrix::new(2, 2, vec![0; 4]));
+
+        let row = Matrix::new(2, 1, vec![1, 2]);
+        let col = Matrix::new(1, 2, vec![3, 4]);
+        assert_eq!(
+            Matrix::block_diagonal(&[&row, &col]),
+            Matrix::new(3, 3, vec![1, 2, 0, 0, 0, 3, 0, 0, 4])
+        );
+        assert_eq!(Matrix::block_diagonal(&[]), Matrix::new(0, 0, vec![]));
+    }
+
+    /// Test matrices that compare equal hash the same, whatever their layout
+    #[test]
+    fn test_hash_ignores_layout() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |matr: &Matrix| {
+            let mut hasher = DefaultHasher::new();
+            matr.hash(&mut hasher);
+            hasher.finish()
+        };
+        let matr = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+        let col_major = matr.to_layout(Layout::ColMajor);
+        assert_eq!(hash(&matr), hash(&col_major));
+        assert_ne!(
+            hash(&matr),
+            hash(&Matrix::new(2, 3, vec![1, 2, 3, 4, 5, 6]))
+        );
+    }
+
+    /// Test zero borders are trimmed down to the non-zero bounding box
+    #[test]
+    fn test_trim_zero_borders() {
+        #[rustfmt::skip]
+        let padded = Matrix::new(5, 4, vec![
+            0, 0, 0, 0, 0,
+            0, 1, 0, 2, 0,
+            0, 0, 0, 3, 0,
+            0, 0, 0, 0, 0,
+        ]);
+        assert_eq!(
+            padded.trim_zero_borders(),
+            Matrix::new(3, 2, vec![1, 0, 2, 0, 0, 3])
+        );
+        assert_eq!(
+            padded.to_layout(Layout::ColMajor).trim_zero_borders(),
+            Matrix::new(3, 2, vec![1, 0, 2, 0, 0, 3])
+        );
+
+        let no_border = Matrix::new(2, 2, vec![1, 0, 0, 1]);
+        assert_eq!(no_border.trim_zero_borders(), no_border);
+
+        let zeros = Matrix::new(3, 2, vec![0; 6]).trim_zero_borders();
+        assert_eq!((zeros.rows(), zeros.cols()), (0, 0));
+    }
+
+    /// Test the equality mask marks the two cells that differ
+    #[test]
+    fn test_eq_mask() {
+        let actual = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+        let expected = Matrix::new(3, 2, vec![1, 9, 3, 4, 5, -6]).to_layout(Layout::ColMajor);
+        assert_eq!(
+            actual.eq_mask(&expected),
+            Matrix::new(3, 2, vec![1, 0, 1, 1, 1, 0])
+        );
+        assert_eq!(actual.eq_mask(&actual), Matrix::new(3, 2, vec![1; 6]));
+    }
+
+    /// Test the equality mask needs matrices of the same shape
+    #[test]
+    #[should_panic]
+    fn test_eq_mask_shape_mismatch() {
+        Matrix::new(3, 2, vec![0; 6]).eq_mask(&Matrix::new(2, 3, vec![0; 6]));
+    }
+
+    /// Test a Toeplitz matrix is constant along each diagonal, and needs its
+    /// first column and row to agree
+    #[test]
+    fn test_toeplitz() {
+        let matrix = Matrix::toeplitz(&[1, 2, 3], &[1, 4, 5, 6]).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(matrix, Matrix::new(4, 3, vec![
+            1, 4, 5, 6,
+            2, 1, 4, 5,
+            3, 2, 1, 4,
+        ]));
+        for i in 1..matrix.rows() {
+            for j in 1..matrix.cols() {
+                assert_eq!(matrix[[i, j]], matrix[[i - 1, j - 1]]);
+            }
+        }
+
+        assert_eq!(
+            Matrix::toeplitz(&[1, 2], &[7, 3]),
+            Err(MatrixError::CornerMismatch {
+                first_col: 1,
+                first_row: 7,
+            })
+        );
+    }
+
+    /// Test a 4x4 matrix splits into four 2x2 tiles in row-major tile order
+    #[test]
+    fn test_tiles() {
+        let matrix = Matrix::from_range(4, 4, 1);
+        let tiles = matrix.tiles(2, 2);
+        assert_eq!(
+            tiles,
+            vec![
+                Matrix::new(2, 2, vec![1, 2, 5, 6]),
+                Matrix::new(2, 2, vec![3, 4, 7, 8]),
+                Matrix::new(2, 2, vec![9, 10, 13, 14]),
+                Matrix::new(2, 2, vec![11, 12, 15, 16]),
+            ]
+        );
+        assert_eq!(matrix.to_layout(Layout::ColMajor).tiles(2, 2), tiles);
+        assert_eq!(matrix.tiles(4, 4), vec![matrix.clone()]);
+        assert_eq!(matrix.tiles(1, 4).len(), 4);
+    }
+
+    /// Test tiles must divide the matrix evenly
+    #[test]
+    #[should_panic(expected = "Tile columns must divide the columns")]
+    fn test_tiles_uneven() {
+        Matrix::from_range(4, 4, 1).tiles(2, 3);
+    }
+
+    /// Test reducing rows to their products and columns to their maxima
+    #[test]
+    fn test_reduce_rows_and_cols() {
+        let matrix = Matrix::new(3, 2, vec![1, -2, 3, 4, 5, 6]);
+        assert_eq!(matrix.reduce_rows(1, |acc, num| acc * num), vec![-6, 120]);
+        assert_eq!(matrix.reduce_cols(i32::MIN, std::cmp::max), vec![4, 5, 6]);
+
+        let col_major = matrix.to_layout(Layout::ColMajor);
+        assert_eq!(
+            col_major.reduce_rows(1, |acc, num| acc * num),
+            vec![-6, 120]
+        );
+        assert_eq!(
+            col_major.reduce_cols(i32::MIN, std::cmp::max),
+            vec![4, 5, 6]
+        );
+
+        let no_cols = Matrix::new(0, 2, Vec::new());
+        assert_eq!(no_cols.reduce_rows(7, |acc, num| acc + num), vec![7, 7]);
+    }
+
+    /// Test multiplying on a set number of threads matches the sequential
+    /// product, including more threads than rows
+    #[test]
+    fn test_mul_mat_threads() {
+        let lhs = random_matrix(6, 7, 20, 11);
+        let rhs = random_matrix(5, 6, 20, 12);
+        let expected = lhs.mul_mat_sequential(&rhs);
+        for threads in 0..10 {
+            assert_eq!(
+                lhs.mul_mat_threads(&rhs, threads),
+                expected,
+                "{} threads",
+                threads
+            );
+        }
+        let col_major = lhs.to_layout(Layout::ColMajor);
+        assert_eq!(col_major.mul_mat_threads(&rhs, 3), expected);
+    }
+
+    /// Test each row of a circulant matrix is the row above rotated right
+    #[test]
+    fn test_circulant() {
+        let matrix = Matrix::circulant(&[1, 2, 3]);
+        #[rustfmt::skip]
+        assert_eq!(matrix, Matrix::new(3, 3, vec![
+            1, 2, 3,
+            3, 1, 2,
+            2, 3, 1,
+        ]));
+        for i in 1..3 {
+            let mut rotated = matrix.to_rows()[i - 1].clone();
+            rotated.rotate_right(1);
+            assert_eq!(matrix.to_rows()[i], rotated);
+        }
+
+        assert_eq!(Matrix::circulant(&[5]), Matrix::new(1, 1, vec![5]));
+        assert_eq!(Matrix::circulant(&[]).rows(), 0);
+    }
+
+    /// Test the single pass statistics against values worked out by hand
+    #[test]
+    fn test_stats() {
+        let matrix = Matrix::new(3, 2, vec![4, -7, 2, 9, 0, 1]);
+        assert_eq!(
+            matrix.stats(),
+            Some(MatrixStats {
+                min: -7,
+                max: 9,
+                sum: 9,
+                mean: 1.5,
+                count: 6,
+            })
+        );
+        assert_eq!(matrix.to_layout(Layout::ColMajor).stats(), matrix.stats());
+
+        let large = Matrix::new(2, 1, vec![i32::MAX, i32::MAX]).stats().unwrap();
+        assert_eq!(large.sum, 2 * i64::from(i32::MAX));
+        assert_eq!(large.mean, f64::from(i32::MAX));
+
+        assert_eq!(Matrix::new(0, 3, Vec::new()).stats(), None);
+    }
+
+    /// Test reading CSV, including spaces around the elements and a ragged row
+    #[test]
+    fn test_from_csv_reader() {
+        let matrix = Matrix::from_csv_reader("1,2,3\n\n4, 5 ,6\n".as_bytes()).unwrap();
+        assert_eq!(matrix, Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]));
+
+        let mut written = Vec::new();
+        matrix.write_csv(&mut written).unwrap();
+        assert_eq!(Matrix::from_csv_reader(&written[..]).unwrap(), matrix);
+
+        assert_eq!(
+            Matrix::from_csv_reader("1,2\n3\n".as_bytes()),
+            Err(MatrixError::RaggedRow {
+                line: 2,
+                expected: 2,
+                found: 1,
+            })
+        );
+        assert!(matches!(
+            Matrix::from_csv_reader("1,,2\n".as_bytes()),
+            Err(MatrixError::Parse { line: 1, .. })
+        ));
+    }
+
+    /// Test the same matrix is read from each format, whether hinted or sniffed
+    #[test]
+    fn test_from_reader_auto() {
+        let expected = Matrix::new(3, 2, vec![1, -2, 3, 4, 5, -6]);
+        let inputs = [
+            (
+                FormatHint::Json,
+                r#"{"rows": 2, "cols": 3, "data": [1, -2, 3, 4, 5, -6]}"#,
+            ),
+            (FormatHint::Json, "  [[1, -2, 3], [4, 5, -6]]\n"),
+            (FormatHint::Csv, "1,-2,3\n4,5,-6\n"),
+            (FormatHint::Text, "1 -2 3\n4\t5 -6\n"),
+        ];
+        for (hint, text) in &inputs {
+            assert_eq!(
+                Matrix::from_reader_auto(text.as_bytes(), *hint).as_ref(),
+                Ok(&expected),
+                "{:?}",
+                hint
+            );
+            assert_eq!(
+                Matrix::from_reader_auto(text.as_bytes(), FormatHint::Auto).as_ref(),
+                Ok(&expected),
+                "{:?} sniffed",
+                hint
+            );
+        }
+
+        // a hint wins over what the text looks like
+        assert!(Matrix::from_reader_auto("1,2\n".as_bytes(), FormatHint::Text).is_err());
+        assert!(matches!(
+            Matrix::from_reader_auto("{1, 2}".as_bytes(), FormatHint::Auto),
+            Err(MatrixError::Format(_))
+        ));
+    }
+
+    /// Test zipping in parallel matches the sequential zip_map, including
+    /// across layouts and with more threads than elements
+    #[test]
+    fn test_par_zip_map() {
+        let lhs = random_matrix(17, 13, 100, 5);
+        let rhs = random_matrix(17, 13, 100, 6).to_layout(Layout::ColMajor);
+        let expected = lhs.zip_map(&rhs, |a, b| a * 3 - b);
+        for &threads in &[1, 2, 3, 8, 1000] {
+            assert_eq!(
+                lhs.zip_map_in_chunks(&rhs, |a, b| a * 3 - b, threads),
+                expected,
+                "{} threads",
+                threads
+            );
+        }
+        assert_eq!(lhs.par_zip_map(&rhs, |a, b| a * 3 - b), expected);
+
+        let large = random_matrix(300, 300, 1000, 7);
+        assert_eq!(
+            large.par_zip_map(&large, |a, b| a.wrapping_mul(b)),
+            large.zip_map(&large, |a, b| a.wrapping_mul(b))
+        );
+    }
+
+    /// Test zipping in parallel needs matrices of the same shape
+    #[test]
+    #[should_panic]
+    fn test_par_zip_map_shape_mismatch() {
+        let lhs = Matrix::new(3, 2, vec![0; 6]);
+        lhs.zip_map_in_chunks(&Matrix::new(2, 3, vec![0; 6]), |a, b| a + b, 2);
+    }
+
+    /// Benchmark zipping a large matrix sequentially and in parallel
+    #[test]
+    #[ignore]
+    fn bench_par_zip_map() {
+        let lhs = random_matrix(2000, 2000, 1000, 42);
+        let rhs = random_matrix(2000, 2000, 1000, 43);
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            lhs.zip_map(&rhs, |a, b| a * b + a);
+        }
+        println!("2000x2000 zip_map: {:?} per run", start.elapsed() / 10);
+
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            lhs.par_zip_map(&rhs, |a, b| a * b + a);
+        }
+        println!("2000x2000 par_zip_map: {:?} per run", start.elapsed() / 10);
+    }
+
+    /// Test a checked product matches the wrapping one when nothing overflows,
+    /// and otherwise reports the first cell that does
+    #[test]
+    fn test_checked_mul_mat() {
+        let lhs = random_matrix(6, 7, 20, 21);
+        let rhs = random_matrix(5, 6, 20, 22);
+        for threads in 0..9 {
+            assert_eq!(
+                lhs.checked_mul_mat(&rhs, threads),
+                Ok(lhs.mul_mat_sequential(&rhs)),
+                "{} threads",
+                threads
+            );
+        }
+
+        // the third and fourth rows of the product overflow in the first column
+        let big = 1 << 30;
+        let lhs = Matrix::new(2, 4, vec![1, 0, 0, 1, 1, 1, 2, 2]);
+        let rhs = Matrix::new(2, 2, vec![big, 0, big, 1]);
+        for threads in 0..5 {
+            assert_eq!(
+                lhs.checked_mul_mat(&rhs, threads),
+                Err(MatrixError::Overflow { row: 2, col: 0 }),
+                "{} threads",
+                threads
+            );
+        }
+
+        // partial sums past i32::MAX are fine as long as the result fits
+        let lhs = Matrix::new(2, 1, vec![i32::MAX, -1]);
+        let rhs = Matrix::new(1, 2, vec![1, 1]);
+        assert_eq!(
+            lhs.checked_mul_mat(&Matrix::new(1, 2, vec![1, 1]), 1),
+            Ok(Matrix::new(1, 1, vec![i32::MAX - 1]))
+        );
+        assert_eq!(
+            Matrix::new(2, 1, vec![i32::MAX, 1]).checked_mul_mat(&rhs, 1),
+            Err(MatrixError::Overflow { row: 0, col: 0 })
+        );
+    }
+
+    /// Test matrices over the element limit fail to deserialize, and the
+    /// limit is lifted afterwards
+    #[test]
+    fn test_with_element_limit() {
+        let json = r#"{"rows": 2, "cols": 3, "data": [1, 2, 3, 4, 5, 6]}"#;
+
+        let matrix = with_element_limit(Some(6), || Matrix::from_json_str(json));
+        assert_eq!(matrix, Ok(Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6])));
+
+        // the declared shape is checked even if the data is short
+        let declared = r#"{"rows": 2, "cols": 3, "data": [1]}"#;
+        let err = with_element_limit(Some(5), || Matrix::from_json_str(declared)).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("a 2x3 matrix has more than the limit of 5 elements"),
+            "{}",
+            err
+        );
+
+        // and the data is cut off once it passes the limit, whatever the shape says
+        let long = r#"{"rows": 1, "cols": 1, "data": [1, 2, 3, 4, 5, 6]}"#;
+        for json in &[json, long] {
+            let err = with_element_limit(Some(5), || Matrix::from_json_str(json)).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains("data has more than the limit of 5 elements"),
+                "{}",
+                err
+            );
+            assert!(with_element_limit(Some(5), || AnyMatrix::from_json_str(json)).is_err());
+        }
+        let operand = format!(
+            r#"{{"left_operand": {}, "operator": "Add", "right_operand": {}}}"#,
+            long, long
+        );
+        let err = with_element_limit(Some(5), || Operation::from_json_str(&operand)).unwrap_err();
+        assert!(err.to_string().contains("limit of 5 elements"), "{}", err);
+
+        let huge = format!(r#"{{"rows": {}, "cols": 2, "data": []}}"#, usize::MAX);
+        assert!(with_element_limit(Some(5), || Matrix::from_json_str(&huge)).is_err());
+
+        // nested limits restore the outer one
+        with_element_limit(Some(5), || {
+            assert!(with_element_limit(None, || Matrix::from_json_str(json)).is_ok());
+            assert!(Matrix::from_json_str(json).is_err());
+        });
+        assert!(Matrix::from_json_str(json).is_ok());
+    }
+
+    /// Test a few cells of the 2D prefix sum against sums worked out by hand
+    #[test]
+    fn test_prefix_sum_2d() {
+        #[rustfmt::skip]
+        let matrix = Matrix::new(4, 3, vec![
+            1, 2, 3, 4,
+            5, 6, 7, 8,
+            9, 10, 11, 12,
+        ]);
+        let sums = matrix.prefix_sum_2d();
+        assert_eq!(sums[[0, 0]], 1);
+        assert_eq!(sums[[0, 3]], 10);
+        assert_eq!(sums[[1, 1]], 1 + 2 + 5 + 6);
+        assert_eq!(sums[[2, 0]], 1 + 5 + 9);
+        assert_eq!(sums[[2, 3]], 78);
+        assert_eq!(matrix.to_layout(Layout::ColMajor).prefix_sum_2d(), sums);
+
+        // partial sums past i32::MAX are fine as long as the final sums fit
+        let matrix = Matrix::new(2, 2, vec![i32::MAX, i32::MIN, i32::MIN, 5]);
+        assert_eq!(
+            matrix.prefix_sum_2d(),
+            Matrix::new(2, 2, vec![i32::MAX, -1, -1, i32::MIN + 4])
+        );
+    }
+
+    /// Test a 2D prefix sum that doesn't fit in an i32 panics with its cell
+    #[test]
+    #[should_panic(expected = "result at row 1, column 0 overflows an i32")]
+    fn test_prefix_sum_2d_overflow() {
+        Matrix::new(1, 2, vec![i32::MAX, 1]).prefix_sum_2d();
+    }
+
+    /// Test building a matrix from an iterator of exactly the right length,
+    /// one that ends early, and one that runs on
+    #[test]
+    fn test_from_iter_shaped() {
+        assert_eq!(
+            Matrix::from_iter_shaped(3, 2, vec![1, 2, 3, 4, 5, 6].into_iter()),
+            Ok(Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]))
+        );
+        assert_eq!(
+            Matrix::from_iter_shaped(3, 2, 1..5),
+            Err(MatrixError::DataLength {
+                expected: 6,
+                found: 4,
+            })
+        );
+
+        let mut long = 1..10;
+        assert_eq!(
+            Matrix::from_iter_shaped(2, 2, long.by_ref()),
+            Ok(Matrix::new(2, 2, vec![1, 2, 3, 4]))
+        );
+        assert_eq!(long.next(), Some(5));
+
+        assert_eq!(
+            Matrix::from_iter_shaped(usize::MAX, 2, std::iter::empty()),
+            Err(MatrixError::DimensionOverflow {
+                rows: 2,
+                cols: usize::MAX,
+            })
+        );
+        assert_eq!(
+            Matrix::from_iter_shaped(1 << 40, 1 << 20, std::iter::empty()),
+            Err(MatrixError::DataLength {
+                expected: 1 << 60,
+                found: 0,
+            })
+        );
+    }
+
+    /// Test diagonal dominance on a dominant, a non-dominant and a non-square
+    /// matrix, agreeing with FloatMatrix
+    #[test]
+    fn test_is_diagonally_dominant() {
+        let dominant = Matrix::new(3, 3, vec![4, -1, 1, 2, -5, 3, 0, 1, 1]);
+        assert!(dominant.is_diagonally_dominant());
+        assert!(dominant
+            .to_layout(Layout::ColMajor)
+            .is_diagonally_dominant());
+        assert!(FloatMatrix::from(&dominant).is_diagonally_dominant());
+
+        let not_dominant = Matrix::new(3, 3, vec![4, -1, 1, 2, -5, 4, 0, 1, 1]);
+        assert!(!not_dominant.is_diagonally_dominant());
+        assert!(!FloatMatrix::from(&not_dominant).is_diagonally_dominant());
+
+        assert!(!Matrix::new(2, 1, vec![1, 0]).is_diagonally_dominant());
+        assert!(Matrix::new(2, 2, vec![i32::MIN, i32::MAX, 0, 1]).is_diagonally_dominant());
+    }
+
+    /// Test multiplying a vector by the exchange matrix reverses it
+    #[test]
+    fn test_exchange() {
+        let exchange = Matrix::exchange(4);
+        assert_eq!(exchange.anti_diagonal(), vec![1; 4]);
+        assert!(exchange.is_permutation_matrix());
+
+        let column = Matrix::new(1, 4, vec![1, 2, 3, 4]);
+        assert_eq!(
+            exchange.mul_mat(&column),
+            Matrix::new(1, 4, vec![4, 3, 2, 1])
+        );
+        let row = Matrix::new(4, 1, vec![1, 2, 3, 4]);
+        assert_eq!(row.mul_mat(&exchange), Matrix::new(4, 1, vec![4, 3, 2, 1]));
+
+        assert_eq!(Matrix::exchange(0).rows(), 0);
+    }
+
+    /// Test flat indices map to elements row by row in either layout
+    #[test]
+    fn test_get_set_flat() {
+        let our_mat = Matrix::from_range(3, 2, 1);
+        for layout in &[Layout::RowMajor, Layout::ColMajor] {
+            let mut matr = our_mat.to_layout(*layout);
+            for index in 0..6 {
+                assert_eq!(matr.get_flat(index), Some(&our_mat[[index / 3, index % 3]]));
+            }
+            assert_eq!(matr.get_flat(6), None);
+
+            matr.set_flat(4, 50);
+            assert_eq!(matr[[1, 1]], 50);
+            assert_eq!(matr.get_flat(4), Some(&50));
+        }
+        assert_eq!(Matrix::<i32>::new(0, 0, vec![]).get_flat(0), None);
+    }
+
+    /// Test setting past the last element panics
+    #[test]
+    #[should_panic(expected = "Flat index out of bounds")]
+    fn test_set_flat_out_of_bounds() {
+        Matrix::new(2, 1, vec![1, 2]).set_flat(2, 0);
+    }
+
+    /// Test shifting rows and columns each way, wrapping around the edges
+    #[test]
+    fn test_shift() {
+        let our_mat = Matrix::from_range(2, 3, 1);
+        assert_eq!(
+            our_mat.shift_rows(1),
+            Matrix::new(2, 3, vec![5, 6, 1, 2, 3, 4])
+        );
+        assert_eq!(
+            our_mat.shift_rows(-1),
+            Matrix::new(2, 3, vec![3, 4, 5, 6, 1, 2])
+        );
+        assert_eq!(our_mat.shift_rows(3), our_mat);
+        assert_eq!(our_mat.shift_rows(-4), our_mat.shift_rows(-1));
+        assert_eq!(our_mat.shift_rows(1).shift_rows(-1), our_mat);
+
+        assert_eq!(
+            our_mat.shift_cols(1),
+            Matrix::new(2, 3, vec![2, 1, 4, 3, 6, 5])
+        );
+        assert_eq!(our_mat.shift_cols(-1), our_mat.shift_cols(1));
+        assert_eq!(our_mat.shift_cols(isize::MIN), our_mat);
+        assert_eq!(
+            our_mat.to_layout(Layout::ColMajor).shift_rows(1),
+            our_mat.shift_rows(1)
+        );
+
+        let empty = Matrix::new(0, 0, vec![]);
+        assert_eq!(empty.shift_rows(2), empty);
+        assert_eq!(empty.shift_cols(-2), empty);
+    }
+
+    /// Test every multiply strategy gives the same product, including shapes
+    /// Strassen's algorithm has to pad and split more than once
+    #[test]
+    fn test_mul_mat_with_strategies() {
+        let strategies = [
+            MulStrategy::Sequential,
+            MulStrategy::Parallel,
+            MulStrategy::Blocked,
+            MulStrategy::Strassen,
+        ];
+        let shapes = [
+            (1, 1, 1),
+            (3, 2, 4),
+            (65, 1, 2),
+            (130, 70, 90),
+            (0, 3, 2),
+            (2, 0, 3),
+        ];
+        for (seed, &(rows, inner, cols)) in shapes.iter().enumerate() {
+            let lhs = random_matrix(inner, rows, 100, seed as u64);
+            let rhs = random_matrix(cols, inner, 100, seed as u64 + 50);
+            let expected = lhs.mul_mat_sequential(&rhs);
+            for &strategy in &strategies {
+                assert_eq!(
+                    lhs.mul_mat_with(&rhs, strategy),
+                    expected,
+                    "{:?} on {}x{} * {}x{}",
+                    strategy,
+                    rows,
+                    inner,
+                    inner,
+                    cols
+                );
+                assert_eq!(
+                    lhs.to_layout(Layout::ColMajor)
+                        .mul_mat_with(&rhs.to_layout(Layout::ColMajor), strategy),
+                    expected
+                );
+            }
+            assert_eq!(lhs.mul_mat(&rhs), expected);
+        }
+
+        let small = random_matrix(4, 4, 10, 1);
+        assert_eq!(MulStrategy::choose(&small, &small), MulStrategy::Sequential);
+        let large = random_matrix(64, 64, 10, 1);
+        assert_eq!(MulStrategy::choose(&large, &large), MulStrategy::Parallel);
+    }
+
+    /// Benchmark each multiply strategy on the same large matrices
+    #[test]
+    #[ignore]
+    fn bench_mul_mat_with() {
+        let lhs = random_matrix(512, 512, 1000, 42);
+        let rhs = random_matrix(512, 512, 1000, 43);
+        for &strategy in &[
+            MulStrategy::Sequential,
+            MulStrategy::Parallel,
+            MulStrategy::Blocked,
+            MulStrategy::Strassen,
+        ] {
+            let start = std::time::Instant::now();
+            for _ in 0..3 {
+                lhs.mul_mat_with(&rhs, strategy);
+            }
+            println!("512x512 {:?}: {:?} per run", strategy, start.elapsed() / 3);
+        }
+    }
+
+    /// Test the Bareiss determinant on matrices that need row swaps, agree
+    /// with LU decomposition, and need more than an i32
+    #[test]
+    fn test_determinant_bareiss() {
+        assert_eq!(Matrix::<i32>::new(0, 0, vec![]).determinant(), 1);
+        assert_eq!(Matrix::new(1, 1, vec![-7]).determinant(), -7);
+        // a zero in the first pivot position needs a row swap
+        assert_eq!(Matrix::new(2, 2, vec![0, 1, 1, 0]).determinant(), -1);
+        assert_eq!(
+            Matrix::new(3, 3, vec![0, 2, 1, 0, 3, 4, 5, 1, 1]).determinant(),
+            25
+        );
+        assert_eq!(Matrix::<i32>::identity(40).determinant(), 1);
+
+        for seed in 0..20 {
+            let our_mat = random_matrix(6, 6, 9, seed);
+            let expected = our_mat.determinant_lu().round() as i64;
+            assert_eq!(our_mat.determinant(), expected, "seed {}", seed);
+        }
+
+        let big = Matrix::new(2, 2, vec![i32::MAX, 0, 0, i32::MAX]);
+        assert_eq!(big.determinant(), i64::from(i32::MAX) * i64::from(i32::MAX));
+    }
+
+    /// Test a determinant that doesn't fit in an i64, or whose elimination
+    /// overflows an i128, is an error rather than wrapping
+    #[test]
+    fn test_determinant_overflow() {
+        let diagonal = Matrix::new(3, 3, vec![i32::MAX, 0, 0, 0, i32::MAX, 0, 0, 0, i32::MAX]);
+        assert_eq!(
+            diagonal.try_determinant(),
+            Err(MatrixError::Overflow { row: 0, col: 0 })
+        );
+        let dense = random_matrix(6, 6, i32::MAX, 1);
+        assert_eq!(
+            dense.try_determinant(),
+            Err(MatrixError::Overflow { row: 0, col: 0 })
+        );
+        assert_eq!(
+            Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]).try_determinant(),
+            Err(MatrixError::DimensionMismatch {
+                expected: (2, 2),
+                found: (2, 3),
+            })
+        );
+    }
+
+    /// Test the exact integer inverse, and each reason there isn't one
+    #[test]
+    fn test_inverse() {
+        let unimodular = Matrix::new(3, 3, vec![2, 3, 1, 1, 2, 1, 1, 1, 1]);
+        assert_eq!(unimodular.determinant(), 1);
+        let inverse = unimodular.inverse().unwrap();
+        assert_eq!(unimodular.mul_mat(&inverse), Matrix::identity(3));
+        assert_eq!(inverse.mul_mat(&unimodular), Matrix::identity(3));
+
+        // a zero in the first pivot position needs a row swap
+        let swap = Matrix::new(2, 2, vec![0, 1, 1, 0]);
+        assert_eq!(swap.inverse(), Ok(swap.clone()));
+        assert_eq!(
+            Matrix::<i32>::new(0, 0, vec![]).inverse(),
+            Ok(Matrix::new(0, 0, vec![]))
+        );
+
+        assert_eq!(
+            Matrix::new(2, 2, vec![4, 7, 2, 6]).inverse(),
+            Err(MatrixError::FractionalInverse)
+        );
+        assert_eq!(
+            Matrix::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).inverse(),
+            Err(MatrixError::Singular)
+        );
+        assert_eq!(
+            Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]).inverse(),
+            Err(MatrixError::DimensionMismatch {
+                expected: (2, 2),
+                found: (2, 3),
+            })
+        );
+        // the inverse of this is [[1, -big], [0, 1]] with big at the limit
+        let edge = Matrix::new(2, 2, vec![1, i32::MAX, 0, 1]);
+        assert_eq!(
+            edge.inverse(),
+            Ok(Matrix::new(2, 2, vec![1, -i32::MAX, 0, 1]))
+        );
+        assert_eq!(
+            Matrix::new(2, 2, vec![1, i32::MIN, 0, 1]).inverse(),
+            Err(MatrixError::Overflow { row: 0, col: 1 })
+        );
+    }
+
+    /// Test the floating point inverse keeps fractions, and agrees with the
+    /// exact inverse when that exists
+    #[test]
+    fn test_inverse_float() {
+        let our_mat = Matrix::new(2, 2, vec![4, 7, 2, 6]);
+        let inverse = our_mat.inverse_float().unwrap();
+        let expected = [0.6, -0.7, -0.2, 0.4];
+        for (index, &value) in expected.iter().enumerate() {
+            assert!((inverse[[index / 2, index % 2]] - value).abs() < 1e-12);
+        }
+
+        let unimodular = Matrix::new(3, 3, vec![2, 3, 1, 1, 2, 1, 1, 1, 1]);
+        assert_eq!(
+            unimodular.inverse_float().unwrap().to_i32_checked(),
+            unimodular.inverse()
+        );
+        assert_eq!(
+            Matrix::new(2, 2, vec![1, 2, 2, 4]).inverse_float(),
+            Err(MatrixError::Singular)
+        );
+    }
+
+    /// Test the methods that work for any Element on i64 and f64 matrices
+    #[test]
+    fn test_generic_elements() {
+        let wide = Matrix::new(2, 2, vec![1_i64 << 40, 1, 0, 1]);
+        assert_eq!(wide.mul_mat_sequential(&Matrix::identity(2)), wide);
+        assert_eq!(
+            wide.add_mat(&wide).transpose().to_rows(),
+            vec![vec![1_i64 << 41, 0], vec![2, 2]]
+        );
+
+        let float = Matrix::new(2, 1, vec![0.5, 1.5]);
+        assert_eq!(float.sub_mat(&float), Matrix::new(2, 1, vec![0.0, 0.0]));
+        assert_eq!(float.hconcat(&float)[[0, 3]], 1.5);
+        assert_eq!(Matrix::new(2, 1, vec![1, 2]).map(f64::from)[[0, 1]], 2.0);
+
+        let json = serde_json::to_string(&float).unwrap();
+        assert_eq!(serde_json::from_str::<Matrix<f64>>(&json).unwrap(), float);
+    }
+
+    /// Test reading a matrix picks the narrowest element type its data fits
+    #[test]
+    fn test_any_matrix_from_json_str() {
+        assert_eq!(
+            AnyMatrix::from_json_str(r#"{"rows": 1, "cols": 2, "data": [1, -2]}"#),
+            Ok(AnyMatrix::I32(Matrix::new(2, 1, vec![1, -2])))
+        );
+        assert_eq!(
+            AnyMatrix::from_json_str(r#"{"rows": 1, "cols": 2, "data": [1, 3000000000]}"#),
+            Ok(AnyMatrix::I64(Matrix::new(2, 1, vec![1, 3_000_000_000])))
+        );
+        assert_eq!(
+            AnyMatrix::from_json_str(r#"{"rows": 1, "cols": 2, "values": [1, 2.5]}"#),
+            Ok(AnyMatrix::F64(Matrix::new(2, 1, vec![1.0, 2.5])))
+        );
+        assert_eq!(
+            AnyMatrix::from_json_str(r#"{"rows": 1, "cols": 3, "data": [1.0, 2.5]}"#),
+            Err(MatrixError::DataLength {
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    /// Test applying an Operator widens both operands to the wider element type
+    #[test]
+    fn test_any_matrix_try_apply() {
+        let small = AnyMatrix::I32(Matrix::new(2, 2, vec![1, 2, 3, 4]));
+        let wide = AnyMatrix::I64(Matrix::new(2, 2, vec![1 << 40, 0, 0, 1]));
+        let float = AnyMatrix::F64(Matrix::new(2, 2, vec![0.5, 0.0, 0.0, 0.5]));
+
+        assert_eq!(
+            small.try_apply(Operator::Multiply, &small, 1),
+            Ok(AnyMatrix::I32(Matrix::new(2, 2, vec![7, 10, 15, 22])))
+        );
+        assert_eq!(
+            small.try_apply(Operator::Multiply, &wide, 1),
+            Ok(AnyMatrix::I64(Matrix::new(
+                2,
+                2,
+                vec![1 << 40, 2, 3 << 40, 4]
+            )))
+        );
+        assert_eq!(
+            float.try_apply(Operator::Subtract, &small, 1),
+            Ok(AnyMatrix::F64(Matrix::new(
+                2,
+                2,
+                vec![-0.5, -2.0, -3.0, -3.5]
+            )))
+        );
+        assert_eq!(
+            float.try_apply(
+                Operator::Add,
+                &AnyMatrix::F64(Matrix::new(1, 2, vec![1.0, 2.0])),
+                1
+            ),
+            Err(MatrixError::DimensionMismatch {
+                expected: (2, 2),
+                found: (2, 1),
+            })
+        );
+    }
+
+    /// Test a precision changes how floating point elements are displayed, and
+    /// leaves whole numbers alone
+    #[test]
+    fn test_display_precision() {
+        let float = Matrix::new(2, 1, vec![0.5, 1.0 / 3.0]);
+        assert_eq!(format!("{:.3}", float), " 0.500  0.333 \n");
+        assert_eq!(
+            format!("{:.3}", Matrix::new(2, 1, vec![1, 20])),
+            "     1     20 \n"
+        );
+    }
+
+    /// Test the fallible arithmetic returns errors instead of panicking
+    #[test]
+    fn test_try_arithmetic() {
+        let wide = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+        let square = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+        assert_eq!(
+            square.try_add(&square),
+            Ok(Matrix::new(2, 2, vec![2, 4, 6, 8]))
+        );
+        assert_eq!(
+            square.try_sub(&wide),
+            Err(MatrixError::DimensionMismatch {
+                expected: (2, 2),
+                found: (2, 3),
+            })
+        );
+        assert_eq!(
+            square.try_mul(&wide),
+            Ok(Matrix::new(3, 2, vec![9, 12, 15, 19, 26, 33]))
+        );
+        assert_eq!(
+            wide.try_mul(&square),
+            Err(MatrixError::DimensionMismatch {
+                expected: (3, 2),
+                found: (2, 2),
+            })
+        );
+        assert_eq!(
+            Matrix::new(1, 1, vec![i32::MAX]).try_mul(&Matrix::new(1, 1, vec![2])),
+            Err(MatrixError::Overflow { row: 0, col: 0 })
+        );
+        let float = Matrix::new(2, 1, vec![0.5, 1.0]);
+        assert_eq!(float.try_add(&float), Ok(Matrix::new(2, 1, vec![1.0, 2.0])));
+    }
+
+    /// Test getting and setting elements outside the matrix gives an error
+    #[test]
+    fn test_try_get_set() {
+        let mut matr = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]).transpose();
+        assert_eq!(matr.try_get(2, 1), Ok(6));
+        assert_eq!(matr.try_set(0, 1, 9), Ok(()));
+        assert_eq!(matr[[0, 1]], 9);
+        assert_eq!(
+            matr.try_get(0, 2),
+            Err(MatrixError::IndexOutOfBounds {
+                row: 0,
+                col: 2,
+                rows: 3,
+                cols: 2,
+            })
+        );
+        assert!(matr.try_set(3, 0, 1).is_err());
+    }
+
+    /// Test indexing outside the matrix panics with the error's message
+    #[test]
+    #[should_panic(expected = "row 2, column 0 is outside a 2x2 matrix")]
+    fn test_index_out_of_bounds() {
+        let _ = Matrix::<i32>::identity(2)[[2, 0]];
+    }
+
+    /// Test adding mismatched matrices panics with the error's message
+    #[test]
+    #[should_panic(expected = "expected a 1x2 matrix, found 2x1")]
+    fn test_add_mat_mismatch() {
+        Matrix::new(2, 1, vec![1, 2]).add_mat(&Matrix::new(1, 2, vec![1, 2]));
     }
 }
@@ -1,13 +1,39 @@
-use crate::matrix::Matrix;
+use crate::error::{MatrixError, OperationError, UnaryOperationError};
+use crate::float_matrix::FloatMatrix;
+use crate::matrix::{self, AnyMatrix, Layout, Matrix, MatrixRepr, Number, FORMAT_VERSION};
+use serde::de::{self, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt::{Display, Formatter, Result};
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// The direction two matrices are joined in
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Axis {
+    /// Side by side; the matrices must have the same number of rows
+    Horizontal,
+    /// One above the other; the matrices must have the same number of columns
+    Vertical,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Operator {
     Multiply,
+    /// The transpose of the left operand multiplied by the right operand
+    MultiplyTransposed,
     Add,
     Subtract,
+    Concat {
+        axis: Axis,
+    },
 }
 
 impl Display for Operator {
@@ -24,22 +50,603 @@ impl Display for Operator {
     fn fmt(&self, f: &mut Formatter) -> Result {
         let output = match self {
             Operator::Multiply => "Multiplied by\n",
+            Operator::MultiplyTransposed => "Transposed and multiplied by\n",
             Operator::Add => "Added to\n",
             Operator::Subtract => "Minus\n",
+            Operator::Concat {
+                axis: Axis::Horizontal,
+            } => "Joined on the right by\n",
+            Operator::Concat {
+                axis: Axis::Vertical,
+            } => "Stacked on top of\n",
         };
         f.write_str(output)
     }
 }
 
+impl TryFrom<&str> for Operator {
+    type Error = MatrixError;
+
+    /// Parse an Operator from either its symbol or its name, ignoring case
+    /// and surrounding whitespace
+    ///
+    /// # Arguments
+    /// * name - one of "+"/"add", "-"/"subtract", "*"/"multiply",
+    ///   "multiply_transposed", "|"/"hconcat" or "/"/"vconcat"
+    ///
+    /// # Return
+    /// The Operator named, or UnknownOperator if there isn't one
+    fn try_from(name: &str) -> std::result::Result<Operator, MatrixError> {
+        match name.trim().to_lowercase().as_str() {
+            "+" | "add" => Ok(Operator::Add),
+            "-" | "subtract" => Ok(Operator::Subtract),
+            "*" | "multiply" => Ok(Operator::Multiply),
+            "multiply_transposed" => Ok(Operator::MultiplyTransposed),
+            "|" | "hconcat" => Ok(Operator::Concat {
+                axis: Axis::Horizontal,
+            }),
+            "/" | "vconcat" => Ok(Operator::Concat {
+                axis: Axis::Vertical,
+            }),
+            _ => Err(MatrixError::UnknownOperator(name.to_string())),
+        }
+    }
+}
+
+impl FromStr for Operator {
+    type Err = MatrixError;
+
+    /// Parse an Operator from either its symbol or its name, the same as
+    /// Operator::try_from
+    fn from_str(name: &str) -> std::result::Result<Operator, MatrixError> {
+        Operator::try_from(name)
+    }
+}
+
+impl Operator {
+    /// Apply this Operator to two matrices
+    ///
+    /// # Arguments
+    /// * self - reference to this Operator
+    /// * lhs - the left operand
+    /// * rhs - the right operand
+    ///
+    /// # Returns
+    /// Returns the Matrix resulting from the operation
+    pub fn apply(&self, lhs: &Matrix, rhs: &Matrix) -> Matrix {
+        self.apply_with(lhs, rhs, true)
+    }
+
+    /// Apply this Operator to two matrices, choosing whether multiplication
+    /// may run concurrently
+    ///
+    /// # Arguments
+    /// * self - reference to this Operator
+    /// * lhs - the left operand
+    /// * rhs - the right operand
+    /// * parallel - false to keep the work on the current thread
+    ///
+    /// # Returns
+    /// Returns the Matrix resulting from the operation
+    pub fn apply_with(&self, lhs: &Matrix, rhs: &Matrix, parallel: bool) -> Matrix {
+        self.apply_with_threads(lhs, rhs, thread_count(parallel))
+    }
+
+    /// Apply this Operator to two matrices, multiplying on a set number of
+    /// threads
+    ///
+    /// # Arguments
+    /// * self - reference to this Operator
+    /// * lhs - the left operand
+    /// * rhs - the right operand
+    /// * threads - how many threads multiplication may use; 1 keeps the work
+    ///   on the current thread
+    ///
+    /// # Returns
+    /// Returns the Matrix resulting from the operation
+    pub fn apply_with_threads(&self, lhs: &Matrix, rhs: &Matrix, threads: usize) -> Matrix {
+        match self {
+            Operator::Add => lhs.add_mat(rhs),
+            Operator::Subtract => lhs.sub_mat(rhs),
+            Operator::Multiply => lhs.mul_mat_threads(rhs, threads),
+            Operator::MultiplyTransposed => lhs.transpose_mul(rhs),
+            Operator::Concat {
+                axis: Axis::Horizontal,
+            } => lhs.hconcat(rhs),
+            Operator::Concat {
+                axis: Axis::Vertical,
+            } => lhs.vconcat(rhs),
+        }
+    }
+
+    /// Apply this Operator to two matrices like `apply_with_threads`, checking
+    /// the shapes first and that a product doesn't overflow
+    ///
+    /// # Arguments
+    /// * self - reference to this Operator
+    /// * lhs - the left operand
+    /// * rhs - the right operand
+    /// * threads - how many threads multiplication may use; 1 keeps the work
+    ///   on the current thread
+    ///
+    /// # Returns
+    /// The Matrix resulting from the operation, a DimensionMismatch if the
+    /// operands are incompatible, or an Overflow giving the first cell of a
+    /// product that doesn't fit in an i32
+    pub fn try_apply_with_threads(
+        &self,
+        lhs: &Matrix,
+        rhs: &Matrix,
+        threads: usize,
+    ) -> std::result::Result<Matrix, MatrixError> {
+        self.check_shapes((lhs.rows(), lhs.cols()), (rhs.rows(), rhs.cols()))?;
+        match self {
+            Operator::Multiply => lhs.checked_mul_mat(rhs, threads),
+            _ => Ok(self.apply_with_threads(lhs, rhs, threads)),
+        }
+    }
+
+    /// Apply this Operator to two matrices of any Number, checking the
+    /// shapes first. Unlike `try_apply_with_threads`, products are worked out
+    /// on the current thread and aren't checked for overflow
+    ///
+    /// # Arguments
+    /// * self - reference to this Operator
+    /// * lhs - the left operand
+    /// * rhs - the right operand
+    ///
+    /// # Returns
+    /// The Matrix resulting from the operation, or a DimensionMismatch if the
+    /// operands are incompatible
+    pub fn try_apply_elements<T: Number>(
+        &self,
+        lhs: &Matrix<T>,
+        rhs: &Matrix<T>,
+    ) -> std::result::Result<Matrix<T>, MatrixError> {
+        self.check_shapes((lhs.rows(), lhs.cols()), (rhs.rows(), rhs.cols()))?;
+        Ok(match self {
+            Operator::Add => lhs.add_mat(rhs),
+            Operator::Subtract => lhs.sub_mat(rhs),
+            Operator::Multiply => lhs.mul_mat_sequential(rhs),
+            Operator::MultiplyTransposed => lhs.transpose().mul_mat_sequential(rhs),
+            Operator::Concat {
+                axis: Axis::Horizontal,
+            } => lhs.hconcat(rhs),
+            Operator::Concat {
+                axis: Axis::Vertical,
+            } => lhs.vconcat(rhs),
+        })
+    }
+
+    /// Work out the (rows, cols) shape of applying this Operator to operands
+    /// of the given shapes
+    ///
+    /// # Arguments
+    /// * self - reference to this Operator
+    /// * lhs - the (rows, cols) of the left operand
+    /// * rhs - the (rows, cols) of the right operand
+    ///
+    /// # Returns
+    /// The shape of the result, or None if the operands are incompatible
+    pub fn result_shape(&self, lhs: (usize, usize), rhs: (usize, usize)) -> Option<(usize, usize)> {
+        self.check_shapes(lhs, rhs).ok()
+    }
+
+    /// Work out the (rows, cols) shape of applying this Operator to operands
+    /// of the given shapes, explaining why if they are incompatible
+    ///
+    /// # Arguments
+    /// * self - reference to this Operator
+    /// * lhs - the (rows, cols) of the left operand
+    /// * rhs - the (rows, cols) of the right operand
+    ///
+    /// # Returns
+    /// The shape of the result, or a DimensionMismatch giving the closest
+    /// shape the right operand could have had
+    pub fn check_shapes(
+        &self,
+        lhs: (usize, usize),
+        rhs: (usize, usize),
+    ) -> std::result::Result<(usize, usize), MatrixError> {
+        let (result, expected) = match self {
+            Operator::Add | Operator::Subtract => (lhs, lhs),
+            Operator::Multiply => ((lhs.0, rhs.1), (lhs.1, rhs.1)),
+            Operator::MultiplyTransposed => ((lhs.1, rhs.1), (lhs.0, rhs.1)),
+            Operator::Concat {
+                axis: Axis::Horizontal,
+            } => ((lhs.0, lhs.1 + rhs.1), (lhs.0, rhs.1)),
+            Operator::Concat {
+                axis: Axis::Vertical,
+            } => ((lhs.0 + rhs.0, lhs.1), (rhs.0, lhs.1)),
+        };
+        if rhs == expected {
+            Ok(result)
+        } else {
+            Err(MatrixError::DimensionMismatch {
+                expected,
+                found: rhs,
+            })
+        }
+    }
+}
+
+/// One side of an Operation: either a Matrix given directly, or another
+/// Operation whose result is used
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged, try_from = "OperandRepr")]
+pub enum Operand {
+    Matrix(Matrix),
+    Operation(Box<Operation>),
+}
+
+/// An Operand as it is read from a serialized format, with the keys of both a
+/// Matrix and an Operation. Reading it this way, rather than trying each in
+/// turn, keeps the reason a nested Matrix or Operation couldn't be read
+#[derive(Deserialize)]
+struct OperandRepr {
+    #[serde(default)]
+    version: u32,
+    rows: Option<usize>,
+    cols: Option<usize>,
+    #[serde(default, alias = "values", deserialize_with = "deserialize_some_data")]
+    data: Option<Vec<i32>>,
+    #[serde(default)]
+    layout: Layout,
+    left_operand: Option<Box<Operand>>,
+    operator: Option<Operator>,
+    right_operand: Option<Box<Operand>>,
+    #[serde(default)]
+    result: Option<Matrix>,
+}
+
+/// Reads the data of an OperandRepr the way a Matrix's is read, within the
+/// element limit
+fn deserialize_some_data<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Vec<i32>>, D::Error> {
+    matrix::deserialize_data(deserializer).map(Some)
+}
+
+impl TryFrom<OperandRepr> for Operand {
+    type Error = String;
+
+    /// Build an Operand from its serialized form: an Operation if it has an
+    /// operator, otherwise a Matrix
+    fn try_from(repr: OperandRepr) -> std::result::Result<Operand, String> {
+        let expected = "expected a matrix with rows, cols and data, \
+                        or an operation with left_operand, operator and right_operand";
+        if repr.operator.is_some() || repr.left_operand.is_some() || repr.right_operand.is_some() {
+            let (left_operand, operator, right_operand) =
+                match (repr.left_operand, repr.operator, repr.right_operand) {
+                    (Some(left), Some(operator), Some(right)) => (*left, operator, *right),
+                    _ => return Err(expected.to_string()),
+                };
+            let op = Operation::try_from(OperationRepr {
+                version: repr.version,
+                left_operand,
+                operator,
+                right_operand,
+                result: repr.result,
+            })?;
+            return Ok(Operand::Operation(Box::new(op)));
+        }
+
+        match (repr.rows, repr.cols, repr.data) {
+            (Some(rows), Some(cols), Some(data)) => Matrix::try_from(MatrixRepr {
+                version: repr.version,
+                rows,
+                cols,
+                data,
+                layout: repr.layout,
+            })
+            .map(Operand::Matrix),
+            _ => Err(expected.to_string()),
+        }
+    }
+}
+
+impl Operand {
+    /// Get the Matrix this Operand stands for, evaluating it if it is an Operation
+    ///
+    /// # Arguments
+    /// * self - reference to this Operand
+    /// * parallel - false to keep any multiplication on the current thread
+    ///
+    /// # Returns
+    /// The Matrix, borrowed if it was given directly
+    pub fn value(&self, parallel: bool) -> Cow<'_, Matrix> {
+        self.value_with_threads(thread_count(parallel))
+    }
+
+    /// Get the Matrix this Operand stands for, evaluating it if it is an
+    /// Operation with multiplication on a set number of threads
+    ///
+    /// # Arguments
+    /// * self - reference to this Operand
+    /// * threads - how many threads multiplication may use
+    ///
+    /// # Returns
+    /// The Matrix, borrowed if it was given directly
+    pub fn value_with_threads(&self, threads: usize) -> Cow<'_, Matrix> {
+        match self {
+            Operand::Matrix(matr) => Cow::Borrowed(matr),
+            Operand::Operation(op) => Cow::Owned(op.do_operation_with_threads(threads)),
+        }
+    }
+
+    /// Get the Matrix this Operand stands for like `value_with_threads`,
+    /// returning an error if it is an Operation that can't be done
+    fn try_value_with_threads(
+        &self,
+        threads: usize,
+    ) -> std::result::Result<Cow<'_, Matrix>, OperationError> {
+        match self {
+            Operand::Matrix(matr) => Ok(Cow::Borrowed(matr)),
+            Operand::Operation(op) => Ok(Cow::Owned(op.try_do_operation_with_threads(threads)?)),
+        }
+    }
+
+    /// Format the Matrix this Operand stands for, or, if it is an Operation
+    /// that can't be done, the reason why, so displaying never panics
+    ///
+    /// # Arguments
+    /// * self - reference to this Operand
+    /// * fmt_matrix - formats the Matrix
+    ///
+    /// # Returns
+    /// The formatted Matrix or error, ending in a newline
+    fn format_value<F: Fn(&Matrix) -> String>(&self, fmt_matrix: F) -> String {
+        match self.try_value_with_threads(thread_count(true)) {
+            Ok(matr) => fmt_matrix(&matr),
+            Err(e) => format!("{}\n", e),
+        }
+    }
+
+    /// Work out the (rows, cols) shape of this Operand without evaluating it
+    ///
+    /// # Arguments
+    /// * self - reference to this Operand
+    ///
+    /// # Returns
+    /// The shape, or None if it is an Operation on incompatible operands
+    pub fn shape(&self) -> Option<(usize, usize)> {
+        match self {
+            Operand::Matrix(matr) => Some((matr.rows(), matr.cols())),
+            Operand::Operation(op) => op.shape(),
+        }
+    }
+
+    /// Work out the (rows, cols) shape of this Operand without evaluating it,
+    /// checking any Operation it holds
+    ///
+    /// # Arguments
+    /// * self - reference to this Operand
+    ///
+    /// # Returns
+    /// The shape, or why an Operation it holds can't be evaluated
+    fn checked_shape(&self) -> std::result::Result<(usize, usize), MatrixError> {
+        match self {
+            Operand::Matrix(matr) => Ok((matr.rows(), matr.cols())),
+            Operand::Operation(op) => op.checked_shape(),
+        }
+    }
+}
+
+/// Two operands and the Operator joining them, along with the result once it
+/// has been computed. It is read through OperationRepr and written by hand
+/// rather than through the RefCell, so it can be nested in, or flattened into,
+/// a larger serde struct
+#[derive(Deserialize, Debug)]
+#[serde(try_from = "OperationRepr")]
 pub struct Operation {
-    left_operand: Matrix,
+    left_operand: Operand,
+    operator: Operator,
+    right_operand: Operand,
+    result: RefCell<Option<Matrix>>,
+}
+
+/// An Operation as it is read from a serialized format, before its version is
+/// checked
+#[derive(Deserialize)]
+struct OperationRepr {
+    #[serde(default)]
+    version: u32,
+    left_operand: Operand,
     operator: Operator,
-    right_operand: Matrix,
-    result: (RefCell<Option<Matrix>>),
+    right_operand: Operand,
+    #[serde(default)]
+    result: Option<Matrix>,
+}
+
+impl TryFrom<OperationRepr> for Operation {
+    type Error = String;
+
+    /// Build an Operation from its serialized form, if the version can be read
+    fn try_from(repr: OperationRepr) -> std::result::Result<Operation, String> {
+        matrix::check_format_version(repr.version)?;
+        Ok(Operation {
+            left_operand: repr.left_operand,
+            operator: repr.operator,
+            right_operand: repr.right_operand,
+            result: RefCell::new(repr.result),
+        })
+    }
+}
+
+impl Serialize for Operation {
+    /// Serialize Operation with the current format version
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Operation", 5)?;
+        state.serialize_field("version", &FORMAT_VERSION)?;
+        state.serialize_field("left_operand", &self.left_operand)?;
+        state.serialize_field("operator", &self.operator)?;
+        state.serialize_field("right_operand", &self.right_operand)?;
+        state.serialize_field("result", &*self.result.borrow())?;
+        state.end()
+    }
 }
 
 impl Operation {
+    /// Build an Operation that hasn't been evaluated yet
+    ///
+    /// # Arguments
+    /// * left_operand - the left side of the Operation
+    /// * operator - what to do to the operands
+    /// * right_operand - the right side of the Operation
+    ///
+    /// # Returns
+    /// The Operation, with no result stored
+    pub fn new(left_operand: Operand, operator: Operator, right_operand: Operand) -> Operation {
+        Operation {
+            left_operand,
+            operator,
+            right_operand,
+            result: RefCell::new(None),
+        }
+    }
+
+    /// Read an Operation from JSON
+    ///
+    /// # Arguments
+    /// * s - the JSON text
+    ///
+    /// # Returns
+    /// The Operation, or the reason it couldn't be read
+    pub fn from_json_str(s: &str) -> std::result::Result<Operation, MatrixError> {
+        serde_json::from_str(s).map_err(|e| MatrixError::Format(e.to_string()))
+    }
+
+    /// Get the Operator of this Operation
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// The Operator
+    pub fn operator(&self) -> Operator {
+        self.operator
+    }
+
+    /// Get the left operand of this Operation
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// The left Operand
+    pub fn left_operand(&self) -> &Operand {
+        &self.left_operand
+    }
+
+    /// Get the right operand of this Operation
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// The right Operand
+    pub fn right_operand(&self) -> &Operand {
+        &self.right_operand
+    }
+
+    /// Work out the (rows, cols) shape of the result without evaluating the
+    /// Operation
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// The shape, or None if the operands are incompatible
+    pub fn shape(&self) -> Option<(usize, usize)> {
+        self.operator
+            .result_shape(self.left_operand.shape()?, self.right_operand.shape()?)
+    }
+
+    /// Check this Operation can be evaluated without evaluating it: every
+    /// nested Operation must have compatible operands, and a stored result
+    /// must have the shape the Operation produces
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// Ok, or the first problem found
+    pub fn validate(&self) -> std::result::Result<(), MatrixError> {
+        let shape = self.checked_shape()?;
+        match &*self.result.borrow() {
+            Some(result) if (result.rows(), result.cols()) != shape => {
+                Err(MatrixError::DimensionMismatch {
+                    expected: shape,
+                    found: (result.rows(), result.cols()),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Work out the shape of this Operation, explaining why if its operands
+    /// are incompatible
+    fn checked_shape(&self) -> std::result::Result<(usize, usize), MatrixError> {
+        self.operator.check_shapes(
+            self.left_operand.checked_shape()?,
+            self.right_operand.checked_shape()?,
+        )
+    }
+
+    /// Build an Operation adding a Matrix to the result of this one
+    ///
+    /// # Arguments
+    /// * self - this Operation, which becomes the left operand
+    /// * rhs - the Matrix to add
+    ///
+    /// # Returns
+    /// The new Operation
+    pub fn then_add(self, rhs: &Matrix) -> Operation {
+        self.then(Operator::Add, rhs)
+    }
+
+    /// Build an Operation subtracting a Matrix from the result of this one
+    ///
+    /// # Arguments
+    /// * self - this Operation, which becomes the left operand
+    /// * rhs - the Matrix to subtract
+    ///
+    /// # Returns
+    /// The new Operation
+    pub fn then_subtract(self, rhs: &Matrix) -> Operation {
+        self.then(Operator::Subtract, rhs)
+    }
+
+    /// Build an Operation multiplying the result of this one by a Matrix
+    ///
+    /// # Arguments
+    /// * self - this Operation, which becomes the left operand
+    /// * rhs - the Matrix to multiply by
+    ///
+    /// # Returns
+    /// The new Operation
+    pub fn then_multiply(self, rhs: &Matrix) -> Operation {
+        self.then(Operator::Multiply, rhs)
+    }
+
+    /// Build an Operation applying an Operator to the result of this one and
+    /// a Matrix
+    ///
+    /// # Arguments
+    /// * self - this Operation, which becomes the left operand
+    /// * operator - the Operator to apply
+    /// * rhs - the right operand
+    ///
+    /// # Returns
+    /// The new Operation
+    pub fn then(self, operator: Operator, rhs: &Matrix) -> Operation {
+        Operation::new(
+            Operand::Operation(Box::new(self)),
+            operator,
+            Operand::Matrix(rhs.clone()),
+        )
+    }
+
     /// Do Operation, based on operator set
     ///
     /// # Arguments
@@ -50,15 +657,79 @@ impl Operation {
     ///
     /// Author: Matthew Krohn
     pub fn do_operation(&self) -> Matrix {
-        match self.operator {
-            Operator::Add => self.left_operand.add_mat(&self.right_operand),
-            Operator::Subtract => self.left_operand.sub_mat(&self.right_operand),
-            Operator::Multiply => self.left_operand.mul_mat(&self.right_operand),
-            //			_ => {
-            //				println!("Other operation");
-            //				Matrix::new(0, 0, vec![])
-            //			}
-        }
+        self.do_operation_with(true)
+    }
+
+    /// Do Operation, based on operator set, choosing whether multiplication
+    /// may run concurrently
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    /// * parallel - false to keep the work on the current thread
+    ///
+    /// # Returns
+    /// Returns the Matrix resulting from this operation
+    pub fn do_operation_with(&self, parallel: bool) -> Matrix {
+        self.do_operation_with_threads(thread_count(parallel))
+    }
+
+    /// Do Operation, based on operator set, multiplying on a set number of
+    /// threads
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    /// * threads - how many threads multiplication may use; 1 keeps the work
+    ///   on the current thread
+    ///
+    /// # Returns
+    /// Returns the Matrix resulting from this operation
+    pub fn do_operation_with_threads(&self, threads: usize) -> Matrix {
+        self.operator.apply_with_threads(
+            &self.left_operand.value_with_threads(threads),
+            &self.right_operand.value_with_threads(threads),
+            threads,
+        )
+    }
+
+    /// Do Operation, based on operator set, returning an error instead of
+    /// panicking if any step has incompatible operands
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// The Matrix resulting from this operation, or the operator and operand
+    /// shapes of the step that couldn't be done
+    pub fn try_do_operation(&self) -> std::result::Result<Matrix, OperationError> {
+        self.try_do_operation_with_threads(thread_count(true))
+    }
+
+    /// Do Operation, based on operator set, multiplying on a set number of
+    /// threads and returning an error instead of panicking if any step has
+    /// incompatible operands
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    /// * threads - how many threads multiplication may use; 1 keeps the work
+    ///   on the current thread
+    ///
+    /// # Returns
+    /// The Matrix resulting from this operation, or the operator and operand
+    /// shapes of the step that couldn't be done
+    pub fn try_do_operation_with_threads(
+        &self,
+        threads: usize,
+    ) -> std::result::Result<Matrix, OperationError> {
+        let left = self.left_operand.try_value_with_threads(threads)?;
+        let right = self.right_operand.try_value_with_threads(threads)?;
+        self.operator
+            .try_apply_with_threads(&left, &right, threads)
+            .map_err(|cause| OperationError {
+                operator: self.operator,
+                left_shape: (left.rows(), left.cols()),
+                right_shape: (right.rows(), right.cols()),
+                cause,
+            })
     }
 
     /// Do operation, and then replace the stored result
@@ -68,32 +739,2007 @@ impl Operation {
     ///
     /// Author: Matthew Krohn
     pub fn do_operation_and_store(&self) {
-        let matr = self.do_operation();
-        self.result.replace(Some(matr));
+        self.do_operation_and_store_with(true)
     }
-}
 
-impl Display for Operation {
-    /// Format Operation for display
+    /// Do operation, choosing whether multiplication may run concurrently,
+    /// and then replace the stored result
     ///
     /// # Arguments
     /// * self - reference to this Operation
-    /// * f - formatter to write to
+    /// * parallel - false to keep the work on the current thread
+    pub fn do_operation_and_store_with(&self, parallel: bool) {
+        let matr = self.do_operation_with(parallel);
+        self.result.replace(Some(matr.into_row_major()));
+    }
+
+    /// Do operation, multiplying on a set number of threads, and then replace
+    /// the stored result
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    /// * threads - how many threads multiplication may use; 1 keeps the work
+    ///   on the current thread
+    pub fn do_operation_and_store_with_threads(&self, threads: usize) {
+        let matr = self.do_operation_with_threads(threads);
+        self.result.replace(Some(matr.into_row_major()));
+    }
+
+    /// Do operation, multiplying on a set number of threads, and then replace
+    /// the stored result; leaves the stored result alone if any step has
+    /// incompatible operands
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    /// * threads - how many threads multiplication may use; 1 keeps the work
+    ///   on the current thread
     ///
     /// # Returns
-    /// The result of the write operation
+    /// Ok, or the operator and operand shapes of the step that couldn't be done
+    pub fn try_do_operation_and_store_with_threads(
+        &self,
+        threads: usize,
+    ) -> std::result::Result<(), OperationError> {
+        let matr = self.try_do_operation_with_threads(threads)?;
+        self.result.replace(Some(matr.into_row_major()));
+        Ok(())
+    }
+
+    /// Do operation, reusing a cached result when the same operator has
+    /// already been applied to the same operands, and then replace the stored
+    /// result
     ///
-    /// Author: Jennifer Kulich
-    fn fmt(&self, f: &mut Formatter) -> Result {
-        let mut output = String::new();
-        output = format!(
-            "{}{}\n{}\n{}",
-            output, self.left_operand, self.operator, self.right_operand
+    /// # Arguments
+    /// * self - reference to this Operation
+    /// * cache - the results of earlier operations, which this one is added to
+    /// * parallel - false to keep the work on the current thread
+    pub fn do_operation_and_store_cached(&self, cache: &mut OperationCache, parallel: bool) {
+        let matr = cache.evaluate(self, parallel);
+        self.result.replace(Some(matr.into_row_major()));
+    }
+
+    /// Gets a copy of the stored result, if the operation has been run
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// The stored result Matrix, or None if it has not been computed
+    pub fn result(&self) -> Option<Matrix> {
+        self.result.borrow().clone()
+    }
+
+    /// Check the stored result is still what the Operation computes, since it
+    /// isn't updated when the operands change or when it is read from a file
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// Whether a result is stored and matches a fresh computation; false if
+    /// the operands can no longer be combined
+    pub fn verify_result(&self) -> bool {
+        match &*self.result.borrow() {
+            Some(result) => self.try_do_operation().is_ok_and(|fresh| *result == fresh),
+            None => false,
+        }
+    }
+
+    /// Check this Operation, including its result, serializes to the same JSON
+    /// as a stored golden file. Formatting differences in the file don't matter
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    /// * path - the golden JSON file
+    ///
+    /// # Panics
+    /// If the file can't be read or parsed, or holds different JSON, showing a
+    /// line by line diff of the expected and actual JSON
+    pub fn assert_matches_golden(&self, path: &Path) {
+        let golden = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("unable to read golden file {}. {}", path.display(), e));
+        let expected: serde_json::Value = serde_json::from_str(&golden)
+            .unwrap_or_else(|e| panic!("invalid json in golden file {}. {}", path.display(), e));
+        let actual = serde_json::to_value(self).unwrap();
+        if expected == actual {
+            return;
+        }
+
+        let expected = serde_json::to_string_pretty(&expected).unwrap();
+        let actual = serde_json::to_string_pretty(&actual).unwrap();
+        let mut expected_lines = expected.lines();
+        let mut actual_lines = actual.lines();
+        let mut diff = String::new();
+        loop {
+            match (expected_lines.next(), actual_lines.next()) {
+                (None, None) => break,
+                (Some(e), Some(a)) if e == a => diff.push_str(&format!("  {}\n", e)),
+                (e, a) => {
+                    if let Some(e) = e {
+                        diff.push_str(&format!("- {}\n", e));
+                    }
+                    if let Some(a) = a {
+                        diff.push_str(&format!("+ {}\n", a));
+                    }
+                }
+            }
+        }
+        panic!(
+            "operation does not match golden file {} (- expected, + actual)\n{}",
+            path.display(),
+            diff
+        );
+    }
+
+    /// Read a JSON array of Operations one element at a time, evaluating and
+    /// handing each to `emit` before the next is read, so the whole array is
+    /// never held in memory
+    ///
+    /// # Arguments
+    /// * reader - the JSON array to read
+    /// * parallel - false to keep any multiplication on the current thread
+    /// * emit - called with each Operation once its result is stored
+    ///
+    /// # Returns
+    /// How many Operations were evaluated, or the reason the input couldn't be
+    /// read or an element couldn't be evaluated; Operations before that
+    /// element have already been emitted
+    pub fn stream_array<R: Read, F: FnMut(Operation)>(
+        reader: R,
+        parallel: bool,
+        emit: F,
+    ) -> std::result::Result<usize, MatrixError> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let count = OperationStream { parallel, emit }
+            .deserialize(&mut deserializer)
+            .and_then(|count| deserializer.end().map(|_| count))
+            .map_err(|e| MatrixError::Format(e.to_string()))?;
+        Ok(count)
+    }
+
+    /// Serialize Operation as TOML; a missing result is left out
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// The TOML text, or the reason it couldn't be written
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> std::result::Result<String, MatrixError> {
+        // going through a toml::Value puts plain keys ahead of tables, which
+        // the TOML format needs
+        toml::Value::try_from(self)
+            .map(|value| value.to_string())
+            .map_err(|e| MatrixError::Format(e.to_string()))
+    }
+
+    /// Read an Operation from TOML
+    ///
+    /// # Arguments
+    /// * s - the TOML text
+    ///
+    /// # Returns
+    /// The Operation, or the reason it couldn't be read
+    #[cfg(feature = "toml")]
+    pub fn from_toml(s: &str) -> std::result::Result<Operation, MatrixError> {
+        toml::from_str(s).map_err(|e| MatrixError::Format(e.to_string()))
+    }
+
+    /// Format Operation for display, annotating square operands with their
+    /// trace and determinant
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    ///
+    /// # Returns
+    /// The formatted Operation
+    pub fn display_debug(&self) -> String {
+        self.format_with(Matrix::display_with_summary)
+    }
+
+    /// Format Operation, using the given function to format the operands
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    /// * fmt_operand - formats each operand
+    ///
+    /// # Returns
+    /// The formatted Operation
+    fn format_with<F: Fn(&Matrix) -> String>(&self, fmt_operand: F) -> String {
+        let mut output = format!(
+            "{}\n{}\n{}",
+            self.left_operand.format_value(&fmt_operand),
+            self.operator,
+            self.right_operand.format_value(&fmt_operand)
         );
         let borrowed_option = self.result.borrow();
         if let Some(matr) = &*borrowed_option {
             output = format!("\n{}\n{}\n\n{}", output, "Equals", matr);
         }
-        write!(f, "{}", output)
+        output
+    }
+}
+
+/// Operators that act on a single matrix
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum UnaryOperator {
+    /// Swap the rows and columns
+    Transpose,
+    /// The determinant of a square matrix, a single number
+    Determinant,
+    /// The inverse of a square matrix, in floating point since its elements
+    /// are usually fractions
+    Inverse,
+}
+
+/// What a UnaryOperator produces: a Matrix, or a single number
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum UnaryResult {
+    Scalar(i64),
+    Matrix(Matrix),
+    FloatMatrix(FloatMatrix),
+}
+
+impl Display for UnaryResult {
+    /// Format UnaryResult for display; a scalar is shown on its own line
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryResult
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            UnaryResult::Scalar(num) => writeln!(f, "{}", num),
+            UnaryResult::Matrix(matr) => write!(f, "{}", matr),
+            UnaryResult::FloatMatrix(matr) => write!(f, "{}", matr),
+        }
+    }
+}
+
+impl Display for UnaryOperator {
+    /// Format UnaryOperator for display, as the step applied to the matrix
+    /// above it
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperator
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let output = match self {
+            UnaryOperator::Transpose => "Transposed\n",
+            UnaryOperator::Determinant => "Its determinant\n",
+            UnaryOperator::Inverse => "Inverted\n",
+        };
+        f.write_str(output)
+    }
+}
+
+impl UnaryOperator {
+    /// Apply this UnaryOperator to a matrix
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperator
+    /// * operand - the matrix to apply it to
+    ///
+    /// # Returns
+    /// The Matrix or scalar resulting from the operation
+    ///
+    /// # Panics
+    /// If the operand has the wrong shape, or has no inverse
+    pub fn apply(&self, operand: &Matrix) -> UnaryResult {
+        self.try_apply(operand).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Apply this UnaryOperator to a matrix like `apply`, checking its shape
+    /// first
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperator
+    /// * operand - the matrix to apply it to
+    ///
+    /// # Returns
+    /// The Matrix or scalar resulting from the operation, a DimensionMismatch
+    /// if the operand has the wrong shape, Overflow if its determinant is too
+    /// large for an i64, or Singular if it has no inverse
+    pub fn try_apply(&self, operand: &Matrix) -> std::result::Result<UnaryResult, MatrixError> {
+        self.check_shape((operand.rows(), operand.cols()))?;
+        Ok(match self {
+            UnaryOperator::Transpose => UnaryResult::Matrix(operand.transpose()),
+            UnaryOperator::Determinant => UnaryResult::Scalar(operand.try_determinant()?),
+            UnaryOperator::Inverse => UnaryResult::FloatMatrix(operand.inverse_float()?),
+        })
+    }
+
+    /// Work out the (rows, cols) shape of applying this UnaryOperator to an
+    /// operand of the given shape; a scalar result counts as 1x1
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperator
+    /// * operand - the (rows, cols) of the operand
+    ///
+    /// # Returns
+    /// The shape of the result, or a DimensionMismatch giving the closest
+    /// shape the operand could have had
+    pub fn check_shape(
+        &self,
+        operand: (usize, usize),
+    ) -> std::result::Result<(usize, usize), MatrixError> {
+        match self {
+            UnaryOperator::Transpose => Ok((operand.1, operand.0)),
+            UnaryOperator::Determinant if operand.0 == operand.1 => Ok((1, 1)),
+            UnaryOperator::Inverse if operand.0 == operand.1 => Ok(operand),
+            UnaryOperator::Determinant | UnaryOperator::Inverse => {
+                Err(MatrixError::DimensionMismatch {
+                    expected: (operand.0, operand.0),
+                    found: operand,
+                })
+            }
+        }
+    }
+}
+
+/// A UnaryOperator applied to one Operand, along with the result once it has
+/// been computed. In JSON it is written as
+/// `{"operand": <matrix or operation>, "operator": "Transpose"}`; a scalar
+/// result is written as a plain number
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnaryOperation {
+    operand: Operand,
+    operator: UnaryOperator,
+    #[serde(default)]
+    result: Option<UnaryResult>,
+}
+
+impl UnaryOperation {
+    /// Build a UnaryOperation that hasn't been evaluated yet
+    ///
+    /// # Arguments
+    /// * operand - the matrix, or Operation whose result, it applies to
+    /// * operator - what to do to the operand
+    ///
+    /// # Returns
+    /// The UnaryOperation, with no result stored
+    pub fn new(operand: Operand, operator: UnaryOperator) -> UnaryOperation {
+        UnaryOperation {
+            operand,
+            operator,
+            result: None,
+        }
+    }
+
+    /// Get the UnaryOperator of this UnaryOperation
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperation
+    ///
+    /// # Returns
+    /// The UnaryOperator
+    pub fn operator(&self) -> UnaryOperator {
+        self.operator
+    }
+
+    /// Get the operand of this UnaryOperation
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperation
+    ///
+    /// # Returns
+    /// The Operand
+    pub fn operand(&self) -> &Operand {
+        &self.operand
+    }
+
+    /// Evaluate the operand, multiplying on a set number of threads, and apply
+    /// the operator to it
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperation
+    /// * threads - how many threads multiplication may use
+    ///
+    /// # Returns
+    /// The Matrix or scalar resulting from this operation, or the step that
+    /// can't be done
+    pub fn try_do_operation_with_threads(
+        &self,
+        threads: usize,
+    ) -> std::result::Result<UnaryResult, UnaryOperationError> {
+        let operand = self.operand.try_value_with_threads(threads)?;
+        self.operator
+            .try_apply(&operand)
+            .map_err(|cause| UnaryOperationError::Operator {
+                operator: self.operator,
+                shape: (operand.rows(), operand.cols()),
+                cause,
+            })
+    }
+
+    /// Evaluate like `try_do_operation_with_threads`, storing the result so it
+    /// is displayed and serialized with the UnaryOperation
+    ///
+    /// # Arguments
+    /// * self - mutable reference to this UnaryOperation
+    /// * threads - how many threads multiplication may use
+    ///
+    /// # Returns
+    /// Nothing, or the step that can't be done
+    pub fn try_do_operation_and_store_with_threads(
+        &mut self,
+        threads: usize,
+    ) -> std::result::Result<(), UnaryOperationError> {
+        self.result = Some(match self.try_do_operation_with_threads(threads)? {
+            UnaryResult::Matrix(matr) => UnaryResult::Matrix(matr.into_row_major()),
+            other => other,
+        });
+        Ok(())
+    }
+
+    /// Get the stored result
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperation
+    ///
+    /// # Returns
+    /// The result, if it has been computed
+    pub fn result(&self) -> Option<&UnaryResult> {
+        self.result.as_ref()
+    }
+}
+
+impl Display for UnaryOperation {
+    /// Format UnaryOperation for display, the same way as an Operation
+    ///
+    /// # Arguments
+    /// * self - reference to this UnaryOperation
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let output = format!(
+            "{}\n{}",
+            self.operand.format_value(Matrix::to_string),
+            self.operator
+        );
+        match &self.result {
+            Some(result) => write!(
+                f,
+                "\n{}\nEquals\n\n{}",
+                output,
+                with_precision(result, f.precision())
+            ),
+            None => f.write_str(&output),
+        }
+    }
+}
+
+/// The norms a Normalization can divide by
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Norm {
+    /// The square root of the sum of the squares of the elements
+    Frobenius,
+    /// The largest absolute column sum
+    One,
+}
+
+impl Norm {
+    /// Compute this norm of a matrix
+    ///
+    /// # Arguments
+    /// * self - the norm to compute
+    /// * matr - the matrix to measure
+    ///
+    /// # Returns
+    /// The norm of `matr`
+    pub fn of(self, matr: &FloatMatrix) -> f64 {
+        match self {
+            Norm::Frobenius => matr.frobenius_norm(),
+            Norm::One => matr.norm_1(),
+        }
+    }
+}
+
+impl Display for Norm {
+    /// Format Norm for display, as the step applied to the matrix above it
+    ///
+    /// # Arguments
+    /// * self - reference to this Norm
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let output = match self {
+            Norm::Frobenius => "Divided by its Frobenius norm\n",
+            Norm::One => "Divided by its 1-norm\n",
+        };
+        f.write_str(output)
+    }
+}
+
+/// An Operand divided by one of its own norms, giving a floating point matrix
+/// whose norm is 1. In JSON it is written as
+/// `{"normalize": <matrix or operation>, "by": "Frobenius"}`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Normalization {
+    normalize: Operand,
+    by: Norm,
+    #[serde(default)]
+    result: Option<FloatMatrix>,
+}
+
+impl Normalization {
+    /// Create a Normalization that hasn't been evaluated yet
+    ///
+    /// # Arguments
+    /// * operand - the matrix, or Operation whose result, is normalized
+    /// * by - the norm to divide by
+    ///
+    /// # Returns
+    /// The Normalization
+    pub fn new(operand: Operand, by: Norm) -> Normalization {
+        Normalization {
+            normalize: operand,
+            by,
+            result: None,
+        }
+    }
+
+    /// Evaluate the operand and divide it by its norm. A matrix whose norm is
+    /// zero is all zeros, and is returned unchanged rather than divided by zero
+    ///
+    /// # Arguments
+    /// * self - reference to this Normalization
+    /// * threads - how many threads multiplication may use
+    ///
+    /// # Returns
+    /// The normalized matrix, or the step of the operand that can't be done
+    pub fn try_normalize_with_threads(
+        &self,
+        threads: usize,
+    ) -> std::result::Result<FloatMatrix, OperationError> {
+        let matr = FloatMatrix::from(&*self.normalize.try_value_with_threads(threads)?);
+        let norm = self.by.of(&matr);
+        if norm == 0.0 {
+            Ok(matr)
+        } else {
+            Ok(matr.div_scalar(norm))
+        }
+    }
+
+    /// Normalize like `try_normalize_with_threads`, storing the result so it
+    /// is displayed and serialized with the Normalization
+    ///
+    /// # Arguments
+    /// * self - mutable reference to this Normalization
+    /// * threads - how many threads multiplication may use
+    ///
+    /// # Returns
+    /// Nothing, or the step of the operand that can't be done
+    pub fn try_normalize_and_store_with_threads(
+        &mut self,
+        threads: usize,
+    ) -> std::result::Result<(), OperationError> {
+        self.result = Some(self.try_normalize_with_threads(threads)?);
+        Ok(())
+    }
+
+    /// Get the stored result
+    ///
+    /// # Arguments
+    /// * self - reference to this Normalization
+    ///
+    /// # Returns
+    /// The normalized matrix, if it has been computed
+    pub fn result(&self) -> Option<&FloatMatrix> {
+        self.result.as_ref()
+    }
+}
+
+impl Display for Normalization {
+    /// Format Normalization for display, the same way as an Operation
+    ///
+    /// # Arguments
+    /// * self - reference to this Normalization
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let output = format!(
+            "{}\n{}",
+            self.normalize.format_value(Matrix::to_string),
+            self.by
+        );
+        match &self.result {
+            Some(matr) => write!(
+                f,
+                "\n{}\nEquals\n\n{}",
+                output,
+                with_precision(matr, f.precision())
+            ),
+            None => f.write_str(&output),
+        }
+    }
+}
+
+/// Formats a result to the given precision, or as it normally is if there is
+/// none
+///
+/// # Arguments
+/// * result - the result to format
+/// * precision - how many digits to show after the decimal point
+///
+/// # Returns
+/// The formatted result
+fn with_precision<D: Display>(result: &D, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => format!("{:.*}", precision, result),
+        None => result.to_string(),
+    }
+}
+
+/// One side of a FloatOperation: either a matrix given directly, or another
+/// FloatOperation whose result is used
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum FloatOperand {
+    Matrix(Matrix<f64>),
+    Operation(Box<FloatOperation>),
+}
+
+impl FloatOperand {
+    /// Get the matrix this FloatOperand stands for, evaluating it if it is a
+    /// FloatOperation
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatOperand
+    ///
+    /// # Returns
+    /// The matrix, borrowed if it was given directly, or the error of the
+    /// step that failed
+    pub fn try_value(&self) -> std::result::Result<Cow<'_, Matrix<f64>>, OperationError> {
+        match self {
+            FloatOperand::Matrix(matr) => Ok(Cow::Borrowed(matr)),
+            FloatOperand::Operation(op) => Ok(Cow::Owned(op.try_do_operation()?)),
+        }
+    }
+}
+
+impl Display for FloatOperand {
+    /// Format FloatOperand for display: a matrix as it is, and a
+    /// FloatOperation as its steps
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatOperand
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            FloatOperand::Matrix(matr) => matr.fmt(f),
+            FloatOperand::Operation(op) => op.fmt(f),
+        }
+    }
+}
+
+/// An Operation on matrices with f64 elements, for inputs whose data isn't all
+/// whole numbers. Products aren't spread across threads
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct FloatOperation {
+    left_operand: FloatOperand,
+    operator: Operator,
+    right_operand: FloatOperand,
+    #[serde(default)]
+    result: Option<Matrix<f64>>,
+}
+
+impl FloatOperation {
+    /// Build a FloatOperation that hasn't been evaluated yet
+    ///
+    /// # Arguments
+    /// * left_operand - the left side of the FloatOperation
+    /// * operator - what to do to the operands
+    /// * right_operand - the right side of the FloatOperation
+    ///
+    /// # Returns
+    /// The FloatOperation, with no result stored
+    pub fn new(
+        left_operand: FloatOperand,
+        operator: Operator,
+        right_operand: FloatOperand,
+    ) -> FloatOperation {
+        FloatOperation {
+            left_operand,
+            operator,
+            right_operand,
+            result: None,
+        }
+    }
+
+    /// Get the Operator of this FloatOperation
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatOperation
+    ///
+    /// # Returns
+    /// The Operator
+    pub fn operator(&self) -> Operator {
+        self.operator
+    }
+
+    /// Evaluate this FloatOperation in floating point
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatOperation
+    ///
+    /// # Returns
+    /// The resulting matrix, or the error of the step that failed
+    pub fn try_do_operation(&self) -> std::result::Result<Matrix<f64>, OperationError> {
+        let lhs = self.left_operand.try_value()?;
+        let rhs = self.right_operand.try_value()?;
+        self.operator
+            .try_apply_elements(&lhs, &rhs)
+            .map_err(|cause| OperationError {
+                operator: self.operator,
+                left_shape: (lhs.rows(), lhs.cols()),
+                right_shape: (rhs.rows(), rhs.cols()),
+                cause,
+            })
+    }
+
+    /// Evaluate this FloatOperation and store the result
+    ///
+    /// # Arguments
+    /// * self - mutable reference to this FloatOperation
+    ///
+    /// # Returns
+    /// Nothing, or the error of the step that failed
+    pub fn try_do_operation_and_store(&mut self) -> std::result::Result<(), OperationError> {
+        self.result = Some(self.try_do_operation()?.into_row_major());
+        Ok(())
+    }
+
+    /// Get the stored result of this FloatOperation
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatOperation
+    ///
+    /// # Returns
+    /// The result, or None if it hasn't been evaluated and stored
+    pub fn result(&self) -> Option<&Matrix<f64>> {
+        self.result.as_ref()
+    }
+}
+
+impl Display for FloatOperation {
+    /// Format FloatOperation for display, the same way as an Operation, with
+    /// every matrix shown to the formatter's precision if it has one
+    ///
+    /// # Arguments
+    /// * self - reference to this FloatOperation
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let output = format!(
+            "{}\n{}\n{}",
+            with_precision(&self.left_operand, f.precision()),
+            self.operator,
+            with_precision(&self.right_operand, f.precision())
+        );
+        match &self.result {
+            Some(matr) => write!(
+                f,
+                "\n{}\nEquals\n\n{}",
+                output,
+                with_precision(matr, f.precision())
+            ),
+            None => f.write_str(&output),
+        }
+    }
+}
+
+/// An Operation on two matrices that aren't both i32, done in the wider of
+/// their element types. Its operands are matrices given directly; an
+/// Operation can't be nested in one
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct WideOperation {
+    left_operand: AnyMatrix,
+    operator: Operator,
+    right_operand: AnyMatrix,
+    #[serde(default)]
+    result: Option<AnyMatrix>,
+}
+
+impl WideOperation {
+    /// Build a WideOperation that hasn't been evaluated yet
+    ///
+    /// # Arguments
+    /// * left_operand - the left side of the WideOperation
+    /// * operator - what to do to the operands
+    /// * right_operand - the right side of the WideOperation
+    ///
+    /// # Returns
+    /// The WideOperation, with no result stored
+    pub fn new(
+        left_operand: AnyMatrix,
+        operator: Operator,
+        right_operand: AnyMatrix,
+    ) -> WideOperation {
+        WideOperation {
+            left_operand,
+            operator,
+            right_operand,
+            result: None,
+        }
+    }
+
+    /// Get the Operator of this WideOperation
+    ///
+    /// # Arguments
+    /// * self - reference to this WideOperation
+    ///
+    /// # Returns
+    /// The Operator
+    pub fn operator(&self) -> Operator {
+        self.operator
+    }
+
+    /// Get the left operand of this WideOperation
+    ///
+    /// # Arguments
+    /// * self - reference to this WideOperation
+    ///
+    /// # Returns
+    /// The left matrix
+    pub fn left_operand(&self) -> &AnyMatrix {
+        &self.left_operand
+    }
+
+    /// Get the right operand of this WideOperation
+    ///
+    /// # Arguments
+    /// * self - reference to this WideOperation
+    ///
+    /// # Returns
+    /// The right matrix
+    pub fn right_operand(&self) -> &AnyMatrix {
+        &self.right_operand
+    }
+
+    /// Evaluate this WideOperation in the wider of its operands' element types
+    ///
+    /// # Arguments
+    /// * self - reference to this WideOperation
+    /// * threads - how many threads an i32 multiplication may use
+    ///
+    /// # Returns
+    /// The resulting matrix, or why the operator can't be applied
+    ///
+    /// ```
+    /// # use sample_code::matrix::{AnyMatrix, Matrix};
+    /// # use sample_code::operation::{Operator, WideOperation};
+    /// let lhs = AnyMatrix::I64(Matrix::new(2, 1, vec![3_000_000_000, 1]));
+    /// let rhs = AnyMatrix::I32(Matrix::new(2, 1, vec![1, 2]));
+    /// let op = WideOperation::new(lhs, Operator::Add, rhs);
+    /// let sum = op.try_do_operation_with_threads(1).unwrap();
+    /// assert_eq!(sum, AnyMatrix::I64(Matrix::new(2, 1, vec![3_000_000_001, 3])));
+    /// ```
+    pub fn try_do_operation_with_threads(
+        &self,
+        threads: usize,
+    ) -> std::result::Result<AnyMatrix, OperationError> {
+        self.left_operand
+            .try_apply(self.operator, &self.right_operand, threads)
+            .map_err(|cause| OperationError {
+                operator: self.operator,
+                left_shape: self.left_operand.shape(),
+                right_shape: self.right_operand.shape(),
+                cause,
+            })
+    }
+
+    /// Evaluate this WideOperation and store the result
+    ///
+    /// # Arguments
+    /// * self - mutable reference to this WideOperation
+    /// * threads - how many threads an i32 multiplication may use
+    ///
+    /// # Returns
+    /// Nothing, or why the operator can't be applied
+    pub fn try_do_operation_and_store_with_threads(
+        &mut self,
+        threads: usize,
+    ) -> std::result::Result<(), OperationError> {
+        self.result = Some(
+            self.try_do_operation_with_threads(threads)?
+                .into_row_major(),
+        );
+        Ok(())
+    }
+
+    /// Get the stored result of this WideOperation
+    ///
+    /// # Arguments
+    /// * self - reference to this WideOperation
+    ///
+    /// # Returns
+    /// The result, or None if it hasn't been evaluated and stored
+    pub fn result(&self) -> Option<&AnyMatrix> {
+        self.result.as_ref()
+    }
+}
+
+impl Display for WideOperation {
+    /// Format WideOperation for display, the same way as an Operation, with
+    /// every matrix shown to the formatter's precision if it has one
+    ///
+    /// # Arguments
+    /// * self - reference to this WideOperation
+    /// * f - formatter to write to
+    ///
+    /// # Return
+    /// The result of the write
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let output = format!(
+            "{}\n{}\n{}",
+            with_precision(&self.left_operand, f.precision()),
+            self.operator,
+            with_precision(&self.right_operand, f.precision())
+        );
+        match &self.result {
+            Some(matr) => write!(
+                f,
+                "\n{}\nEquals\n\n{}",
+                output,
+                with_precision(matr, f.precision())
+            ),
+            None => f.write_str(&output),
+        }
+    }
+}
+
+/// Evaluates the elements of a JSON array of Operations as they are read
+struct OperationStream<F> {
+    parallel: bool,
+    emit: F,
+}
+
+impl<'de, F: FnMut(Operation)> DeserializeSeed<'de> for OperationStream<F> {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<usize, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F: FnMut(Operation)> Visitor<'de> for OperationStream<F> {
+    type Value = usize;
+
+    fn expecting(&self, f: &mut Formatter) -> Result {
+        f.write_str("an array of operations")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> std::result::Result<usize, A::Error> {
+        let mut count = 0;
+        while let Some(op) = seq.next_element::<Operation>()? {
+            op.try_do_operation_and_store_with_threads(thread_count(self.parallel))
+                .map_err(de::Error::custom)?;
+            (self.emit)(op);
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Get how many threads multiplication uses when it may run concurrently or not
+fn thread_count(parallel: bool) -> usize {
+    if parallel {
+        std::thread::available_parallelism().map_or(1, |count| count.get())
+    } else {
+        1
+    }
+}
+
+/// The result of an Operation kept by an OperationCache, along with the
+/// operands it was computed from
+#[derive(Debug)]
+struct CachedResult {
+    left: Matrix,
+    right: Matrix,
+    result: Matrix,
+}
+
+/// Remembers the results of Operations, keyed by the hashes of their operands
+/// and their Operator, so repeating an Operation doesn't recompute it
+#[derive(Debug, Default)]
+pub struct OperationCache {
+    entries: HashMap<(u64, Operator, u64), CachedResult>,
+    hits: usize,
+    misses: usize,
+}
+
+impl OperationCache {
+    /// Create an empty OperationCache
+    ///
+    /// # Returns
+    /// The OperationCache, with nothing cached
+    pub fn new() -> OperationCache {
+        OperationCache::default()
+    }
+
+    /// Evaluate an Operation, returning the cached result if the same Operator
+    /// has already been applied to equal operands. Operands that are
+    /// Operations are evaluated through the cache as well
+    ///
+    /// # Arguments
+    /// * self - mutable reference to this OperationCache
+    /// * op - the Operation to evaluate
+    /// * parallel - false to keep any multiplication on the current thread
+    ///
+    /// # Returns
+    /// The Matrix resulting from the Operation
+    pub fn evaluate(&mut self, op: &Operation, parallel: bool) -> Matrix {
+        let left = self.operand_value(&op.left_operand, parallel);
+        let right = self.operand_value(&op.right_operand, parallel);
+        let key = (hash_matrix(&left), op.operator, hash_matrix(&right));
+
+        // the hashes can collide, so only trust an entry with equal operands
+        if let Some(entry) = self.entries.get(&key) {
+            if entry.left == *left && entry.right == *right {
+                self.hits += 1;
+                return entry.result.clone();
+            }
+        }
+
+        self.misses += 1;
+        let result = op.operator.apply_with(&left, &right, parallel);
+        self.entries.insert(
+            key,
+            CachedResult {
+                left: left.into_owned(),
+                right: right.into_owned(),
+                result: result.clone(),
+            },
+        );
+        result
+    }
+
+    /// Get the Matrix an Operand stands for, evaluating it through this cache
+    /// if it is an Operation
+    fn operand_value<'a>(&mut self, operand: &'a Operand, parallel: bool) -> Cow<'a, Matrix> {
+        match operand {
+            Operand::Matrix(matr) => Cow::Borrowed(matr),
+            Operand::Operation(op) => Cow::Owned(self.evaluate(op, parallel)),
+        }
+    }
+
+    /// Get how many evaluations were answered from the cache
+    ///
+    /// # Arguments
+    /// * self - reference to this OperationCache
+    ///
+    /// # Returns
+    /// The number of cache hits
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Get how many evaluations had to be computed
+    ///
+    /// # Arguments
+    /// * self - reference to this OperationCache
+    ///
+    /// # Returns
+    /// The number of cache misses
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Get how many results are cached
+    ///
+    /// # Arguments
+    /// * self - reference to this OperationCache
+    ///
+    /// # Returns
+    /// The number of cached results
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether nothing is cached
+    ///
+    /// # Arguments
+    /// * self - reference to this OperationCache
+    ///
+    /// # Returns
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Hash a Matrix on its own, for use in an OperationCache key
+fn hash_matrix(matr: &Matrix) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    matr.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Display for Operation {
+    /// Format Operation for display
+    ///
+    /// # Arguments
+    /// * self - reference to this Operation
+    /// * f - formatter to write to
+    ///
+    /// # Returns
+    /// The result of the write operation
+    ///
+    /// Author: Jennifer Kulich
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "{}", self.format_with(Matrix::to_string))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test the debug display annotates square operands
+    #[test]
+    fn test_display_debug() {
+        let op = Operation {
+            left_operand: Operand::Matrix(Matrix::new(2, 2, vec![1, 2, 3, 4])),
+            operator: Operator::Add,
+            right_operand: Operand::Matrix(Matrix::new(2, 2, vec![1, 0, 0, 1])),
+            result: RefCell::new(None),
+        };
+        assert_eq!(
+            op.display_debug(),
+            "     1      2 \n     3      4 \nTrace: 5\nDeterminant: -2\n\nAdded to\n\n     1      0 \n     0      1 \nTrace: 2\nDeterminant: 1\n"
+        );
+        assert_eq!(
+            op.to_string(),
+            "     1      2 \n     3      4 \n\nAdded to\n\n     1      0 \n     0      1 \n"
+        );
+    }
+
+    /// Test displaying an Operation whose nested operand can't be evaluated
+    /// shows why instead of panicking
+    #[test]
+    fn test_display_invalid_nested() {
+        let invalid = Operation::new(
+            Operand::Matrix(Matrix::new(2, 1, vec![1, 2])),
+            Operator::Add,
+            Operand::Matrix(Matrix::new(1, 2, vec![1, 2])),
+        );
+        let nested = Operation::new(
+            Operand::Operation(Box::new(invalid)),
+            Operator::Multiply,
+            Operand::Matrix(Matrix::identity(2)),
+        );
+        let op = Operation::new(
+            Operand::Operation(Box::new(nested)),
+            Operator::Subtract,
+            Operand::Matrix(Matrix::identity(2)),
+        );
+        let shown = op.to_string();
+        assert!(
+            shown.starts_with("unable to apply Add to a 1x2 and a 2x1 matrix"),
+            "{}",
+            shown
+        );
+        assert!(op.display_debug().contains("\nMinus\n"));
+
+        let unary = UnaryOperation::new(Operand::Operation(Box::new(op)), UnaryOperator::Transpose);
+        assert!(unary.to_string().starts_with("unable to apply Add"));
+    }
+
+    /// Test the result of one operation can be concatenated with a matrix
+    #[test]
+    fn test_concat_nested_operation() {
+        let op: Operation = serde_json::from_str(
+            r#"{
+                "left_operand": {
+                    "left_operand": {"rows": 2, "cols": 1, "data": [1, 2]},
+                    "operator": "Add",
+                    "right_operand": {"rows": 2, "cols": 1, "data": [10, 20]},
+                    "result": null
+                },
+                "operator": {"Concat": {"axis": "Horizontal"}},
+                "right_operand": {"rows": 2, "cols": 2, "data": [3, 4, 5, 6]},
+                "result": null
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            op.do_operation(),
+            Matrix::new(3, 2, vec![11, 3, 4, 22, 5, 6])
+        );
+    }
+
+    /// Test the shape of a concatenation
+    #[test]
+    fn test_concat_result_shape() {
+        let horizontal = Operator::Concat {
+            axis: Axis::Horizontal,
+        };
+        let vertical = Operator::Concat {
+            axis: Axis::Vertical,
+        };
+        assert_eq!(horizontal.result_shape((2, 3), (2, 1)), Some((2, 4)));
+        assert_eq!(horizontal.result_shape((2, 3), (3, 3)), None);
+        assert_eq!(vertical.result_shape((2, 3), (1, 3)), Some((3, 3)));
+        assert_eq!(vertical.result_shape((2, 3), (2, 1)), None);
+    }
+
+    /// Test an Operation survives a round trip through TOML
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_round_trip() {
+        let op: Operation = serde_json::from_str(
+            r#"{
+                "left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+                "operator": "Subtract",
+                "right_operand": {"rows": 1, "cols": 2, "data": [3, 5]},
+                "result": null
+            }"#,
+        )
+        .unwrap();
+
+        let read_back = Operation::from_toml(&op.to_toml().unwrap()).unwrap();
+        assert_eq!(read_back.result(), None);
+        assert_eq!(read_back.do_operation(), Matrix::new(2, 1, vec![-2, -3]));
+
+        op.do_operation_and_store();
+        let read_back = Operation::from_toml(&op.to_toml().unwrap()).unwrap();
+        assert_eq!(read_back.result(), op.result());
+    }
+
+    /// Test parsing an Operator from each accepted spelling
+    #[test]
+    fn test_operator_try_from() {
+        let spellings = [
+            ("+", Operator::Add),
+            ("add", Operator::Add),
+            ("-", Operator::Subtract),
+            ("subtract", Operator::Subtract),
+            ("*", Operator::Multiply),
+            ("multiply", Operator::Multiply),
+            ("multiply_transposed", Operator::MultiplyTransposed),
+            (" Multiply ", Operator::Multiply),
+            (
+                "|",
+                Operator::Concat {
+                    axis: Axis::Horizontal,
+                },
+            ),
+            (
+                "hconcat",
+                Operator::Concat {
+                    axis: Axis::Horizontal,
+                },
+            ),
+            (
+                "/",
+                Operator::Concat {
+                    axis: Axis::Vertical,
+                },
+            ),
+            (
+                "VCONCAT",
+                Operator::Concat {
+                    axis: Axis::Vertical,
+                },
+            ),
+        ];
+        for (name, expected) in spellings.iter() {
+            assert_eq!(Operator::try_from(*name), Ok(*expected));
+        }
+
+        for name in ["", "divide", "++", "add!"].iter() {
+            assert_eq!(
+                Operator::try_from(*name),
+                Err(MatrixError::UnknownOperator(name.to_string()))
+            );
+        }
+    }
+
+    /// Test each Operation in a streamed array is evaluated in order
+    #[test]
+    fn test_stream_array() {
+        let input = r#"[
+            {"left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+             "operator": "Add",
+             "right_operand": {"rows": 1, "cols": 2, "data": [3, 4]}},
+            {"left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+             "operator": "Multiply",
+             "right_operand": {"rows": 2, "cols": 1, "data": [3, 4]}},
+            {"left_operand": {"rows": 1, "cols": 1, "data": [5]},
+             "operator": {"Concat": {"axis": "Vertical"}},
+             "right_operand": {"rows": 1, "cols": 1, "data": [6]}}
+        ]"#;
+
+        let mut results = Vec::new();
+        let count = Operation::stream_array(input.as_bytes(), false, |op| {
+            results.push(op.result().unwrap())
+        });
+
+        assert_eq!(count, Ok(3));
+        assert_eq!(
+            results,
+            vec![
+                Matrix::new(2, 1, vec![4, 6]),
+                Matrix::new(1, 1, vec![11]),
+                Matrix::new(1, 2, vec![5, 6]),
+            ]
+        );
+
+        // the elements before a malformed one are still emitted
+        let mut emitted = 0;
+        let count = Operation::stream_array(&input.as_bytes()[..input.len() - 30], false, |_| {
+            emitted += 1
+        });
+        assert!(matches!(count, Err(MatrixError::Format(_))));
+        assert_eq!(emitted, 2);
+
+        assert!(Operation::stream_array("{}".as_bytes(), false, |_| ()).is_err());
+
+        // an element whose operands can't be combined is an error, not a panic
+        let input = r#"[
+            {"left_operand": {"rows": 1, "cols": 1, "data": [1]},
+             "operator": "Add",
+             "right_operand": {"rows": 1, "cols": 1, "data": [2]}},
+            {"left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+             "operator": "Add",
+             "right_operand": {"rows": 2, "cols": 1, "data": [3, 4]}}
+        ]"#;
+        let mut emitted = 0;
+        let count = Operation::stream_array(input.as_bytes(), false, |_| emitted += 1);
+        match count {
+            Err(MatrixError::Format(e)) => assert!(e.contains("expected a 1x2 matrix"), "{}", e),
+            other => panic!("expected a format error, found {:?}", other),
+        }
+        assert_eq!(emitted, 1);
+    }
+
+    /// Gets the path of a golden file in tests/data
+    fn golden_file(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("data")
+            .join(name)
+    }
+
+    /// Builds the Operation stored in the golden files, with its result
+    fn golden_op() -> Operation {
+        let op = Operation {
+            left_operand: Operand::Matrix(Matrix::new(2, 2, vec![1, 2, 3, 4])),
+            operator: Operator::Add,
+            right_operand: Operand::Matrix(Matrix::new(2, 2, vec![10, 20, 30, 40])),
+            result: RefCell::new(None),
+        };
+        op.do_operation_and_store();
+        op
+    }
+
+    /// Test an Operation matches its golden file
+    #[test]
+    fn test_matches_golden() {
+        golden_op().assert_matches_golden(&golden_file("golden_add.json"));
+    }
+
+    /// Test a different golden file fails with a diff of the changed line
+    #[test]
+    #[should_panic(expected = "-       45\n+       44\n")]
+    fn test_mismatches_golden() {
+        golden_op().assert_matches_golden(&golden_file("golden_add_wrong.json"));
+    }
+
+    /// Test building and evaluating an Operation fluently
+    #[test]
+    fn test_fluent_operation() {
+        let our_mat1 = Matrix::new(2, 2, vec![1, 2, 3, 4]);
+        let our_mat2 = Matrix::new(2, 2, vec![1, 0, 0, 1]);
+
+        let op = our_mat1.clone().then_add(&our_mat2);
+        assert_eq!(op.do_operation(), Matrix::new(2, 2, vec![2, 2, 3, 5]));
+
+        let op = our_mat1
+            .clone()
+            .then_multiply(&our_mat1)
+            .then_subtract(&our_mat2)
+            .then(
+                Operator::Concat {
+                    axis: Axis::Horizontal,
+                },
+                &our_mat2,
+            );
+        op.do_operation_and_store();
+        assert_eq!(
+            op.result(),
+            Some(Matrix::new(4, 2, vec![6, 10, 1, 0, 15, 21, 0, 1]))
+        );
+    }
+
+    /// Test the shapes of nested Operations are worked out without evaluating
+    #[test]
+    fn test_operation_shape() {
+        let op = Matrix::from_range(3, 2, 1)
+            .then_multiply(&Matrix::from_range(4, 3, 1))
+            .then(
+                Operator::Concat {
+                    axis: Axis::Vertical,
+                },
+                &Matrix::from_range(4, 1, 1),
+            );
+        assert_eq!(op.shape(), Some((3, 4)));
+        assert_eq!(op.left_operand().shape(), Some((2, 4)));
+        assert_eq!(op.right_operand().shape(), Some((1, 4)));
+        assert_eq!(
+            op.operator(),
+            Operator::Concat {
+                axis: Axis::Vertical
+            }
+        );
+
+        let op = Matrix::from_range(3, 2, 1).then_add(&Matrix::from_range(2, 3, 1));
+        assert_eq!(op.shape(), None);
+    }
+
+    /// Test reading an Operation from valid and invalid JSON
+    #[test]
+    fn test_operation_from_json_str() {
+        let op = Operation::from_json_str(
+            r#"{
+                "left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+                "operator": "Subtract",
+                "right_operand": {"rows": 1, "cols": 2, "data": [3, 4]}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(op.operator(), Operator::Subtract);
+        assert_eq!(op.result(), None);
+        assert_eq!(op.do_operation(), Matrix::new(2, 1, vec![-2, -2]));
+
+        let err = Operation::from_json_str(
+            r#"{"left_operand": {"rows": 1, "cols": 1, "data": [1]}, "operator": "Divide"}"#,
+        );
+        assert!(matches!(err, Err(MatrixError::Format(_))));
+        assert!(Operation::from_json_str("").is_err());
+    }
+
+    /// Test an Operation without a version is read as version 0, and written
+    /// back with the current version
+    #[test]
+    fn test_operation_format_version() {
+        let version_0 = Operation::from_json_str(
+            r#"{
+                "left_operand": {"rows": 1, "cols": 1, "data": [1]},
+                "operator": "Add",
+                "right_operand": {"rows": 1, "cols": 1, "data": [2]}
+            }"#,
+        )
+        .unwrap();
+        let json = serde_json::to_value(&version_0).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["left_operand"]["version"], 1);
+
+        let version_1 = Operation::from_json_str(&json.to_string()).unwrap();
+        assert_eq!(version_1.do_operation(), Matrix::new(1, 1, vec![3]));
+
+        let mut json = json;
+        json["version"] = serde_json::json!(2);
+        assert!(Operation::from_json_str(&json.to_string()).is_err());
+    }
+
+    /// Test a repeated Operation is answered from the cache instead of being
+    /// recomputed
+    #[test]
+    fn test_operation_cache_hit() {
+        let mut cache = OperationCache::new();
+        let first = Matrix::new(2, 2, vec![1, 2, 3, 4]).then_multiply(&Matrix::identity(2));
+        first.do_operation_and_store_cached(&mut cache, false);
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+
+        let second = Matrix::new(2, 2, vec![1, 2, 3, 4]).then_multiply(&Matrix::identity(2));
+        second.do_operation_and_store_cached(&mut cache, false);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert_eq!(second.result(), first.result());
+        assert_eq!(cache.len(), 1);
+
+        let other = Matrix::new(2, 2, vec![1, 2, 3, 4]).then_add(&Matrix::identity(2));
+        other.do_operation_and_store_cached(&mut cache, false);
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+        assert_eq!(other.result(), Some(Matrix::new(2, 2, vec![2, 2, 3, 5])));
+    }
+
+    /// Test nested Operations are cached too
+    #[test]
+    fn test_operation_cache_nested() {
+        let mut cache = OperationCache::new();
+        let nested = Matrix::new(1, 2, vec![1, 2])
+            .then_add(&Matrix::new(1, 2, vec![3, 4]))
+            .then_add(&Matrix::new(1, 2, vec![1, 1]));
+        assert_eq!(
+            cache.evaluate(&nested, false),
+            Matrix::new(1, 2, vec![5, 7])
+        );
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+        assert_eq!(
+            cache.evaluate(&nested, false),
+            Matrix::new(1, 2, vec![5, 7])
+        );
+        assert_eq!((cache.hits(), cache.misses()), (2, 2));
+    }
+
+    /// Test validating an Operation finds incompatible operands, including in
+    /// nested Operations, and a stored result of the wrong shape
+    #[test]
+    fn test_validate() {
+        let valid = Matrix::new(2, 3, vec![1; 6]).then_multiply(&Matrix::new(1, 2, vec![1, 2]));
+        assert_eq!(valid.validate(), Ok(()));
+        valid.do_operation_and_store();
+        assert_eq!(valid.validate(), Ok(()));
+
+        let mismatched = Matrix::new(2, 3, vec![1; 6]).then_add(&Matrix::new(3, 2, vec![1; 6]));
+        assert_eq!(
+            mismatched.validate(),
+            Err(MatrixError::DimensionMismatch {
+                expected: (3, 2),
+                found: (2, 3),
+            })
+        );
+
+        let nested = Operation::new(
+            Operand::Operation(Box::new(mismatched)),
+            Operator::Subtract,
+            Operand::Matrix(Matrix::new(2, 3, vec![1; 6])),
+        );
+        assert!(matches!(
+            nested.validate(),
+            Err(MatrixError::DimensionMismatch { .. })
+        ));
+
+        let wrong_result = Operation::from_json_str(
+            r#"{
+                "left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+                "operator": "Add",
+                "right_operand": {"rows": 1, "cols": 2, "data": [3, 4]},
+                "result": {"rows": 2, "cols": 1, "data": [4, 6]}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            wrong_result.validate(),
+            Err(MatrixError::DimensionMismatch {
+                expected: (1, 2),
+                found: (2, 1),
+            })
+        );
+    }
+
+    /// Test an Operation can be embedded in a larger config, both under its
+    /// own key and flattened into the config's fields
+    #[test]
+    fn test_operation_in_wrapper() {
+        #[derive(Serialize, Deserialize)]
+        struct Nested {
+            name: String,
+            operation: Operation,
+        }
+
+        #[derive(Serialize, Deserialize)]
+        struct Flattened {
+            name: String,
+            #[serde(flatten)]
+            operation: Operation,
+        }
+
+        let operation = r#"{
+            "left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+            "operator": "Add",
+            "right_operand": {"rows": 1, "cols": 2, "data": [3, 4]},
+            "result": {"rows": 1, "cols": 2, "data": [4, 6]}
+        }"#;
+
+        let nested: Nested =
+            serde_json::from_str(&format!(r#"{{"name": "sum", "operation": {}}}"#, operation))
+                .unwrap();
+        assert_eq!(nested.name, "sum");
+        assert_eq!(nested.operation.operator(), Operator::Add);
+        assert_eq!(
+            nested.operation.result(),
+            Some(Matrix::new(2, 1, vec![4, 6]))
+        );
+
+        let json = serde_json::to_value(&nested).unwrap();
+        assert_eq!(json["operation"]["version"], FORMAT_VERSION);
+        let nested: Nested = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            nested.operation.do_operation(),
+            Matrix::new(2, 1, vec![4, 6])
+        );
+
+        let flattened: Flattened = serde_json::from_str(&format!(
+            r#"{{"name": "sum", {}"#,
+            operation.trim_start().trim_start_matches('{')
+        ))
+        .unwrap();
+        assert_eq!(flattened.name, "sum");
+        assert_eq!(
+            flattened.operation.result(),
+            Some(Matrix::new(2, 1, vec![4, 6]))
+        );
+
+        let json = serde_json::to_value(&flattened).unwrap();
+        assert_eq!(json["name"], "sum");
+        assert_eq!(json["operator"], "Add");
+        assert_eq!(json["version"], FORMAT_VERSION);
+        let flattened: Flattened = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            flattened.operation.do_operation(),
+            Matrix::new(2, 1, vec![4, 6])
+        );
+    }
+
+    /// Test a freshly stored result verifies, and one left stale by changing
+    /// an operand, or missing, doesn't
+    #[test]
+    fn test_verify_result() {
+        let mut op = Matrix::new(2, 1, vec![1, 2]).then_add(&Matrix::new(2, 1, vec![3, 4]));
+        assert!(!op.verify_result());
+
+        op.do_operation_and_store();
+        assert!(op.verify_result());
+
+        op.right_operand = Operand::Matrix(Matrix::new(2, 1, vec![5, 6]));
+        assert!(!op.verify_result());
+        op.do_operation_and_store();
+        assert!(op.verify_result());
+
+        op.right_operand = Operand::Matrix(Matrix::new(1, 2, vec![5, 6]));
+        assert!(!op.verify_result());
+
+        let stale = Operation::from_json_str(
+            r#"{
+                "left_operand": {"rows": 1, "cols": 1, "data": [1]},
+                "operator": "Add",
+                "right_operand": {"rows": 1, "cols": 1, "data": [2]},
+                "result": {"rows": 1, "cols": 1, "data": [4]}
+            }"#,
+        )
+        .unwrap();
+        assert!(!stale.verify_result());
+    }
+
+    /// Test a failing Operation reports the operator and shapes of the step
+    /// that failed, including inside a nested Operation
+    #[test]
+    fn test_try_do_operation() {
+        let op =
+            Matrix::new(2, 2, vec![1, 2, 3, 4]).then_multiply(&Matrix::new(1, 3, vec![1, 2, 3]));
+        let err = op.try_do_operation().unwrap_err();
+        assert_eq!(err.operator, Operator::Multiply);
+        assert_eq!((err.left_shape, err.right_shape), ((2, 2), (3, 1)));
+        let message = err.to_string();
+        assert!(message.contains("Multiply"), "{}", message);
+        assert!(message.contains("2x2"), "{}", message);
+        assert!(message.contains("3x1"), "{}", message);
+
+        let nested = op.then_add(&Matrix::new(1, 2, vec![1, 2]));
+        let err = nested
+            .try_do_operation_and_store_with_threads(1)
+            .unwrap_err();
+        assert_eq!(err.operator, Operator::Multiply);
+        assert_eq!(nested.result(), None);
+
+        let valid = Matrix::new(2, 1, vec![1, 2]).then_subtract(&Matrix::new(2, 1, vec![1, 1]));
+        assert_eq!(valid.try_do_operation(), Ok(Matrix::new(2, 1, vec![0, 1])));
+        valid.try_do_operation_and_store_with_threads(1).unwrap();
+        assert_eq!(valid.result(), Some(Matrix::new(2, 1, vec![0, 1])));
+    }
+
+    /// Test a product that overflows reports the first cell that does,
+    /// rather than wrapping
+    #[test]
+    fn test_try_do_operation_overflow() {
+        let op = Matrix::new(2, 2, vec![1, 2, 3, i32::MAX]).then_multiply(&Matrix::new(
+            2,
+            2,
+            vec![1, 0, 0, 2],
+        ));
+        let err = op.try_do_operation().unwrap_err();
+        assert_eq!(err.operator, Operator::Multiply);
+        assert_eq!(err.cause, MatrixError::Overflow { row: 1, col: 1 });
+        assert!(err.to_string().contains("row 1, column 1"), "{}", err);
+
+        let err = op.try_do_operation_with_threads(1).unwrap_err();
+        assert_eq!(err.cause, MatrixError::Overflow { row: 1, col: 1 });
+    }
+
+    /// Test a nested Matrix or Operation that can't be read gives its own
+    /// reason, rather than just not matching either
+    #[test]
+    fn test_operand_errors() {
+        let nested_version = Operation::from_json_str(
+            r#"{
+                "left_operand": {"version": 9, "rows": 1, "cols": 1, "data": [1]},
+                "operator": "Add",
+                "right_operand": {"rows": 1, "cols": 1, "data": [2]}
+            }"#,
+        )
+        .unwrap_err();
+        assert!(
+            nested_version
+                .to_string()
+                .contains("unsupported format version 9"),
+            "{}",
+            nested_version
+        );
+
+        let incomplete = Operation::from_json_str(
+            r#"{
+                "left_operand": {"left_operand": {"rows": 1, "cols": 1, "data": [1]}},
+                "operator": "Add",
+                "right_operand": {"rows": 1, "cols": 1, "data": [2]}
+            }"#,
+        )
+        .unwrap_err();
+        assert!(
+            incomplete
+                .to_string()
+                .contains("expected a matrix with rows, cols and data"),
+            "{}",
+            incomplete
+        );
+
+        let values = Operation::from_json_str(
+            r#"{
+                "left_operand": {"rows": 1, "cols": 2, "values": [1, 2], "layout": "ColMajor"},
+                "operator": "Add",
+                "right_operand": {"rows": 1, "cols": 2, "data": [3, 4]}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(values.do_operation(), Matrix::new(2, 1, vec![4, 6]));
+    }
+
+    /// Test normalizing the result of an Operation by each norm
+    #[test]
+    fn test_normalization() {
+        let mut norm: Normalization = serde_json::from_str(
+            r#"{
+                "normalize": {
+                    "left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+                    "operator": "Add",
+                    "right_operand": {"rows": 1, "cols": 2, "data": [2, 2]}
+                },
+                "by": "Frobenius"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(norm.result(), None);
+        norm.try_normalize_and_store_with_threads(1).unwrap();
+        assert_eq!(norm.result(), Some(&FloatMatrix::new(2, 1, vec![0.6, 0.8])));
+        assert!(norm
+            .to_string()
+            .ends_with("Divided by its Frobenius norm\n\nEquals\n\n   0.6    0.8 \n"));
+
+        let one = Normalization::new(
+            Operand::Matrix(Matrix::new(2, 2, vec![1, -3, 1, 1])),
+            Norm::One,
+        );
+        assert_eq!(
+            one.try_normalize_with_threads(1),
+            Ok(FloatMatrix::new(2, 2, vec![0.25, -0.75, 0.25, 0.25]))
+        );
+
+        let zero = Normalization::new(
+            Operand::Matrix(Matrix::new(2, 1, vec![0, 0])),
+            Norm::Frobenius,
+        );
+        assert_eq!(
+            zero.try_normalize_with_threads(1),
+            Ok(FloatMatrix::new(2, 1, vec![0.0, 0.0]))
+        );
+    }
+
+    /// Test normalizing an Operation that can't be done gives its error
+    #[test]
+    fn test_normalization_error() {
+        let norm = Normalization::new(
+            Operand::Operation(Box::new(Operation::new(
+                Operand::Matrix(Matrix::new(2, 2, vec![1, 2, 3, 4])),
+                Operator::Add,
+                Operand::Matrix(Matrix::new(1, 2, vec![1, 2])),
+            ))),
+            Norm::Frobenius,
+        );
+        assert_eq!(
+            norm.try_normalize_with_threads(1).unwrap_err().operator,
+            Operator::Add
+        );
+    }
+
+    /// Test transposing a matrix and the result of an Operation
+    #[test]
+    fn test_unary_transpose() {
+        let mut op: UnaryOperation = serde_json::from_str(
+            r#"{
+                "operand": {"rows": 2, "cols": 3, "data": [1, 2, 3, 4, 5, 6]},
+                "operator": "Transpose"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(op.operator(), UnaryOperator::Transpose);
+        assert_eq!(op.result(), None);
+        op.try_do_operation_and_store_with_threads(1).unwrap();
+        assert_eq!(
+            op.result(),
+            Some(&UnaryResult::Matrix(Matrix::new(
+                2,
+                3,
+                vec![1, 4, 2, 5, 3, 6]
+            )))
+        );
+        assert_eq!(UnaryOperator::Transpose.check_shape((2, 3)), Ok((3, 2)));
+        assert!(op.to_string().contains("Transposed\n\nEquals\n\n"));
+
+        let written = serde_json::to_value(&op).unwrap();
+        assert_eq!(written["operator"], "Transpose");
+        assert_eq!(
+            written["result"]["data"],
+            serde_json::json!([1, 4, 2, 5, 3, 6])
+        );
+        let result: Matrix = serde_json::from_value(written["result"].clone()).unwrap();
+        assert_eq!(result, Matrix::new(2, 3, vec![1, 4, 2, 5, 3, 6]));
+
+        let nested = UnaryOperation::new(
+            Operand::Operation(Box::new(Operation::new(
+                Operand::Matrix(Matrix::new(2, 1, vec![1, 2])),
+                Operator::Concat {
+                    axis: Axis::Vertical,
+                },
+                Operand::Matrix(Matrix::new(2, 1, vec![3, 4])),
+            ))),
+            UnaryOperator::Transpose,
+        );
+        assert_eq!(
+            nested.try_do_operation_with_threads(1),
+            Ok(UnaryResult::Matrix(Matrix::new(2, 2, vec![1, 3, 2, 4])))
+        );
+    }
+
+    /// Test the determinant operator gives a scalar, written as a plain number,
+    /// and rejects a non-square operand
+    #[test]
+    fn test_unary_determinant() {
+        let mut op: UnaryOperation = serde_json::from_str(
+            r#"{
+                "operand": {"rows": 2, "cols": 2, "data": [1, 2, 3, 4]},
+                "operator": "Determinant"
+            }"#,
+        )
+        .unwrap();
+        op.try_do_operation_and_store_with_threads(1).unwrap();
+        assert_eq!(op.result(), Some(&UnaryResult::Scalar(-2)));
+        assert!(op
+            .to_string()
+            .ends_with("Its determinant\n\nEquals\n\n-2\n"));
+
+        let written = serde_json::to_string(&op).unwrap();
+        assert!(
+            written.ends_with(r#""operator":"Determinant","result":-2}"#),
+            "{}",
+            written
+        );
+        let read: UnaryOperation = serde_json::from_str(&written).unwrap();
+        assert_eq!(read.result(), Some(&UnaryResult::Scalar(-2)));
+
+        let not_square = UnaryOperation::new(
+            Operand::Matrix(Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6])),
+            UnaryOperator::Determinant,
+        );
+        assert_eq!(
+            not_square.try_do_operation_with_threads(1),
+            Err(UnaryOperationError::Operator {
+                operator: UnaryOperator::Determinant,
+                shape: (2, 3),
+                cause: MatrixError::DimensionMismatch {
+                    expected: (2, 2),
+                    found: (2, 3),
+                },
+            })
+        );
+    }
+
+    /// Test the inverse operator gives a float matrix, written with fractional
+    /// elements, and rejects a singular operand
+    #[test]
+    fn test_unary_inverse() {
+        let mut op = UnaryOperation::new(
+            Operand::Matrix(Matrix::new(2, 2, vec![2, 0, 0, 4])),
+            UnaryOperator::Inverse,
+        );
+        op.try_do_operation_and_store_with_threads(1).unwrap();
+        let expected = FloatMatrix::new(2, 2, vec![0.5, 0.0, 0.0, 0.25]);
+        assert_eq!(
+            op.result(),
+            Some(&UnaryResult::FloatMatrix(expected.clone()))
+        );
+        assert!(op.to_string().contains("Inverted\n\nEquals\n\n"));
+
+        let written = serde_json::to_string(&op).unwrap();
+        let read: UnaryOperation = serde_json::from_str(&written).unwrap();
+        assert_eq!(read.result(), Some(&UnaryResult::FloatMatrix(expected)));
+        // a stored result whose data doesn't fill its shape isn't read
+        let short = written.replace("[0.5,0.0,0.0,0.25]", "[0.5]");
+        assert_ne!(short, written);
+        assert!(serde_json::from_str::<UnaryOperation>(&short).is_err());
+
+        let singular = UnaryOperation::new(
+            Operand::Matrix(Matrix::new(2, 2, vec![1, 2, 2, 4])),
+            UnaryOperator::Inverse,
+        );
+        assert_eq!(
+            singular.try_do_operation_with_threads(1),
+            Err(UnaryOperationError::Operator {
+                operator: UnaryOperator::Inverse,
+                shape: (2, 2),
+                cause: MatrixError::Singular,
+            })
+        );
+        assert_eq!(UnaryOperator::Inverse.check_shape((3, 3)), Ok((3, 3)));
+        assert!(UnaryOperator::Inverse.check_shape((3, 2)).is_err());
+    }
+
+    /// Test a FloatOperation is read and evaluated in floating point, with
+    /// nested operations and a precision for display
+    #[test]
+    fn test_float_operation() {
+        let mut op: FloatOperation = serde_json::from_str(
+            r#"{
+                "left_operand": {
+                    "left_operand": {"rows": 1, "cols": 2, "data": [0.5, 1]},
+                    "operator": "Add",
+                    "right_operand": {"rows": 1, "cols": 2, "data": [0.25, 0.25]}
+                },
+                "operator": "Multiply",
+                "right_operand": {"rows": 2, "cols": 1, "data": [2, 4]}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(op.operator(), Operator::Multiply);
+        assert_eq!(op.result(), None);
+        op.try_do_operation_and_store().unwrap();
+        assert_eq!(op.result(), Some(&Matrix::new(1, 1, vec![6.5])));
+        assert!(format!("{:.2}", op).ends_with("Equals\n\n  6.50 \n"));
+        assert!(op.to_string().ends_with("Equals\n\n   6.5 \n"));
+
+        let mismatched = FloatOperation::new(
+            FloatOperand::Matrix(Matrix::new(2, 1, vec![0.5, 1.5])),
+            Operator::Subtract,
+            FloatOperand::Matrix(Matrix::new(1, 2, vec![0.5, 1.5])),
+        );
+        assert_eq!(
+            mismatched.try_do_operation().unwrap_err().cause,
+            MatrixError::DimensionMismatch {
+                expected: (1, 2),
+                found: (2, 1),
+            }
+        );
     }
 }
@@ -0,0 +1,433 @@
+//! Running a Computation the way the sample_code binary does: how many threads
+//! to use, the report of a run, and writing the result in the format an
+//! output file's extension asks for
+use crate::computation::Computation;
+use crate::error::MatrixError;
+use crate::matrix::{AnyMatrix, Matrix};
+use crate::operation::{Operation, Operator, UnaryResult};
+use serde::Serialize;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// The formats a Computation can be written out as
+#[derive(Debug, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+    Text,
+    Bincode,
+}
+
+impl OutputFormat {
+    /// Choose the output format from a file's extension, falling back to JSON
+    ///
+    /// # Arguments
+    /// * path - the path of the output file
+    ///
+    /// # Returns
+    /// The format to write the file in
+    pub fn from_path(path: &Path) -> OutputFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => OutputFormat::Csv,
+            Some("txt") => OutputFormat::Text,
+            Some("bin") => OutputFormat::Bincode,
+            _ => OutputFormat::Json,
+        }
+    }
+}
+
+/// Something that can be written out: all of it as JSON or text, or only its
+/// result as CSV or bincode
+pub trait Output: Serialize + Display {
+    /// Get the result, if it has been computed and is an i32 matrix, the only
+    /// kind of result CSV and bincode are written for
+    ///
+    /// # Arguments
+    /// * self - reference to this Output
+    ///
+    /// # Returns
+    /// A copy of the result
+    fn matrix_result(&self) -> Option<Matrix>;
+}
+
+impl Output for Operation {
+    fn matrix_result(&self) -> Option<Matrix> {
+        self.result()
+    }
+}
+
+impl Output for Computation {
+    fn matrix_result(&self) -> Option<Matrix> {
+        match self {
+            Computation::Operation(op) => op.result(),
+            Computation::Unary(op) => match op.result() {
+                Some(UnaryResult::Matrix(matr)) => Some(matr.clone()),
+                _ => None,
+            },
+            Computation::Wide(op) => match op.result() {
+                Some(AnyMatrix::I32(matr)) => Some(matr.clone()),
+                _ => None,
+            },
+            Computation::Normalization(_) | Computation::Float(_) => None,
+        }
+    }
+}
+
+/// Write an Output to a file, in the format picked by the file's extension.
+/// JSON and text hold the whole of it; CSV and bincode only the result
+///
+/// # Arguments
+/// * op - the Output to write
+/// * path - the file to write to
+///
+/// # Returns
+/// Nothing, or why it couldn't be written
+pub fn write_output<O: Output>(op: &O, path: &Path) -> io::Result<()> {
+    let format = OutputFormat::from_path(path);
+    // checked before the file is created, so an existing file isn't emptied
+    // when there's nothing that can be written to it
+    let result = match format {
+        OutputFormat::Csv | OutputFormat::Bincode => Some(op.matrix_result().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "only an integer matrix result can be written as csv or bin",
+            )
+        })?),
+        OutputFormat::Json | OutputFormat::Text => None,
+    };
+    let mut out = BufWriter::new(File::create(path)?);
+
+    match (format, result) {
+        (OutputFormat::Csv, Some(result)) => result.write_csv(&mut out)?,
+        (OutputFormat::Bincode, Some(result)) => result.write_bincode(&mut out)?,
+        (OutputFormat::Json, _) => serde_json::to_writer_pretty(&mut out, op)?,
+        (_, _) => write!(out, "{}", op)?,
+    }
+    out.flush()
+}
+
+/// The environment variable giving the default number of threads to multiply
+/// on
+pub const THREADS_VAR: &str = "MATRIX_THREADS";
+
+/// Parse a number of threads, which must be at least one
+///
+/// # Arguments
+/// * s - the number, as the user gave it
+///
+/// # Returns
+/// The number of threads, or why it isn't valid
+pub fn parse_threads(s: &str) -> Result<usize, String> {
+    match s.trim().parse() {
+        Ok(0) => Err("the number of threads must be at least 1".to_string()),
+        Ok(threads) => Ok(threads),
+        Err(e) => Err(format!("invalid number of threads {:?}: {}", s, e)),
+    }
+}
+
+/// Work out how many threads to multiply on. Running on one thread wins, then
+/// a number given outright, then the MATRIX_THREADS environment variable, then
+/// one per core
+///
+/// # Arguments
+/// * parallel - false to run on a single thread
+/// * flag - the number of threads given outright, if any
+/// * env - the value of MATRIX_THREADS, if it is set
+///
+/// # Returns
+/// The number of threads, or why MATRIX_THREADS isn't valid
+pub fn thread_count(
+    parallel: bool,
+    flag: Option<usize>,
+    env: Option<&str>,
+) -> Result<usize, String> {
+    match (parallel, flag, env) {
+        (false, _, _) => Ok(1),
+        (true, Some(threads), _) => Ok(threads),
+        (true, None, Some(value)) => {
+            parse_threads(value).map_err(|e| format!("{} in {}", e, THREADS_VAR))
+        }
+        (true, None, None) => {
+            Ok(std::thread::available_parallelism().map_or(1, |count| count.get()))
+        }
+    }
+}
+
+/// The metadata written about a run of an operation on two matrices; shapes
+/// are [rows, cols]
+#[derive(Serialize, Debug)]
+pub struct Report {
+    operator: Operator,
+    left_shape: Option<(usize, usize)>,
+    right_shape: Option<(usize, usize)>,
+    result_shape: Option<(usize, usize)>,
+    threads: usize,
+    elapsed_ms: f64,
+}
+
+impl Report {
+    /// Gather the metadata of an evaluated Operation
+    ///
+    /// # Arguments
+    /// * op - the Operation that was run
+    /// * threads - how many threads multiplication could use
+    /// * elapsed - how long evaluating it took
+    ///
+    /// # Returns
+    /// The Report
+    pub fn new(op: &Operation, threads: usize, elapsed: Duration) -> Report {
+        Report {
+            operator: op.operator(),
+            left_shape: op.left_operand().shape(),
+            right_shape: op.right_operand().shape(),
+            result_shape: op.result().map(|matr| (matr.rows(), matr.cols())),
+            threads,
+            elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+        }
+    }
+
+    /// Gather the metadata of an evaluated Computation, if it is an operation
+    /// on two matrices
+    ///
+    /// # Arguments
+    /// * computation - the Computation that was run
+    /// * threads - how many threads multiplication could use
+    /// * elapsed - how long evaluating it took
+    ///
+    /// # Returns
+    /// The Report, or None for a Computation with no right operand
+    pub fn of(computation: &Computation, threads: usize, elapsed: Duration) -> Option<Report> {
+        match computation {
+            Computation::Operation(op) => Some(Report::new(op, threads, elapsed)),
+            Computation::Wide(op) => Some(Report {
+                operator: op.operator(),
+                left_shape: Some(op.left_operand().shape()),
+                right_shape: Some(op.right_operand().shape()),
+                result_shape: op.result().map(AnyMatrix::shape),
+                threads,
+                elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Get the path of the report written beside a file, replacing its extension.
+/// The binary puts it beside the output file, or beside the input when there
+/// is none; for an operation assembled from parts, that's the left matrix's
+/// file
+///
+/// # Arguments
+/// * path - the file the report goes beside
+///
+/// # Returns
+/// The report's path, e.g. out.report.json for out.json
+pub fn report_path(path: &Path) -> PathBuf {
+    path.with_extension("report.json")
+}
+
+/// Evaluate a Computation and store its result, writing a Report of the run
+/// if asked to
+///
+/// # Arguments
+/// * computation - the Computation to evaluate
+/// * threads - how many threads multiplication may use
+/// * report - the file to write a Report to, if one was asked for
+///
+/// # Returns
+/// Nothing, or why it couldn't be evaluated or reported on
+pub fn run_computation(
+    computation: &mut Computation,
+    threads: usize,
+    report: Option<&Path>,
+) -> Result<(), String> {
+    // only an operation on two matrices has operand shapes to report
+    if report.is_some()
+        && !matches!(
+            computation,
+            Computation::Operation(_) | Computation::Wide(_)
+        )
+    {
+        return Err("--report is only written for an operation on two matrices".to_string());
+    }
+    let start = Instant::now();
+    computation
+        .run_with_threads(threads)
+        .map_err(|e| e.to_string())?;
+    let elapsed = start.elapsed();
+
+    if let (Some(path), Some(report)) = (report, Report::of(computation, threads, elapsed)) {
+        let written = File::create(path)
+            .map_err(serde_json::Error::io)
+            .and_then(|out| serde_json::to_writer_pretty(out, &report));
+        written.map_err(|e| format!("unable to write report. {}", e))?;
+    }
+    Ok(())
+}
+
+/// Validate every .json Operation file in a directory, without running them
+///
+/// # Arguments
+/// * dir - the directory to check
+///
+/// # Returns
+/// The number of files that passed, and each file that failed with why, in
+/// file name order
+pub fn validate_dir(dir: &Path) -> io::Result<(usize, Vec<(PathBuf, MatrixError)>)> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension() == Some("json".as_ref()) {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    let mut passed = 0;
+    let mut failed = Vec::new();
+    for path in files {
+        let checked = std::fs::read_to_string(&path)
+            .map_err(MatrixError::from)
+            .and_then(|json| Operation::from_json_str(&json))
+            .and_then(|op| op.validate());
+        match checked {
+            Ok(()) => passed += 1,
+            Err(e) => failed.push((path, e)),
+        }
+    }
+    Ok((passed, failed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Build a small, already evaluated Operation to write out
+    fn evaluated_op() -> Operation {
+        let op: Operation = serde_json::from_str(
+            r#"{
+                "left_operand": {"rows": 1, "cols": 2, "data": [1, 2]},
+                "operator": "Add",
+                "right_operand": {"rows": 1, "cols": 2, "data": [3, 4]},
+                "result": null
+            }"#,
+        )
+        .unwrap();
+        op.do_operation_and_store();
+        op
+    }
+
+    /// Get a path in the temp directory for a test output file
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sample_code_{}_{}", std::process::id(), name))
+    }
+
+    /// Test each extension picks the matching format
+    #[test]
+    fn test_output_format_from_path() {
+        let format = |p: &str| OutputFormat::from_path(Path::new(p));
+        assert_eq!(format("out.json"), OutputFormat::Json);
+        assert_eq!(format("out.csv"), OutputFormat::Csv);
+        assert_eq!(format("out.txt"), OutputFormat::Text);
+        assert_eq!(format("out.bin"), OutputFormat::Bincode);
+        assert_eq!(format("out"), OutputFormat::Json);
+    }
+
+    /// Test each extension is written with the right serializer
+    #[test]
+    fn test_write_output() {
+        let op = evaluated_op();
+
+        let path = temp_path("out.json");
+        write_output(&op, &path).unwrap();
+        let written: Operation = serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        assert_eq!(written.result(), op.result());
+        fs::remove_file(&path).unwrap();
+
+        let path = temp_path("out.csv");
+        write_output(&op, &path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "4,6\n");
+        fs::remove_file(&path).unwrap();
+
+        let path = temp_path("out.txt");
+        write_output(&op, &path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), op.to_string());
+        fs::remove_file(&path).unwrap();
+
+        let path = temp_path("out.bin");
+        write_output(&op, &path).unwrap();
+        let written = Matrix::from_bincode_reader(File::open(&path).unwrap()).unwrap();
+        assert_eq!(Some(written), op.result());
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// Test a result that can't be written as csv leaves the file as it was
+    #[test]
+    fn test_write_output_keeps_file() {
+        let op: Computation = crate::run_json(
+            r#"{
+                "left_operand": {"rows": 1, "cols": 1, "data": [0.5]},
+                "operator": "Add",
+                "right_operand": {"rows": 1, "cols": 1, "data": [1]}
+            }"#,
+            1,
+        )
+        .unwrap();
+        let path = temp_path("keep.csv");
+        fs::write(&path, "1,2\n").unwrap();
+        assert!(write_output(&op, &path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1,2\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// Test the report path replaces the extension
+    #[test]
+    fn test_report_path() {
+        assert_eq!(
+            report_path(Path::new("dir/out.json")),
+            PathBuf::from("dir/out.report.json")
+        );
+        assert_eq!(
+            report_path(Path::new("out")),
+            PathBuf::from("out.report.json")
+        );
+    }
+
+    /// Test the report gathers the shapes of an evaluated Operation
+    #[test]
+    fn test_report_shapes() {
+        let report = Report::new(&evaluated_op(), 2, Duration::from_millis(3));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "operator": "Add",
+                "left_shape": [1, 2],
+                "right_shape": [1, 2],
+                "result_shape": [1, 2],
+                "threads": 2,
+                "elapsed_ms": 3.0
+            })
+        );
+    }
+
+    /// Test running on one thread and a number given outright take priority
+    /// over MATRIX_THREADS
+    #[test]
+    fn test_thread_count() {
+        assert_eq!(thread_count(false, Some(4), Some("3")), Ok(1));
+        assert_eq!(thread_count(true, Some(4), Some("3")), Ok(4));
+        assert_eq!(thread_count(true, None, Some("3")), Ok(3));
+        assert_eq!(thread_count(true, None, Some("1")), Ok(1));
+        assert!(thread_count(true, None, None).unwrap() >= 1);
+
+        let err = thread_count(true, None, Some("0")).unwrap_err();
+        assert!(err.contains(THREADS_VAR));
+        assert!(thread_count(true, None, Some("lots")).is_err());
+        assert_eq!(thread_count(true, Some(2), Some("lots")), Ok(2));
+    }
+}
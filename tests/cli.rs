@@ -0,0 +1,609 @@
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Description: gets the path of a file in tests/data
+fn data_file(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("data")
+        .join(name)
+}
+
+/// Description: gets a path in the temp directory for a test output file
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("sample_code_cli_{}_{}", std::process::id(), name))
+}
+
+/// Description: runs the binary with the given arguments
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_sample_code"))
+        .args(args)
+        .output()
+        .expect("unable to run the binary")
+}
+
+/// Description: runs the binary on an input file, writing json, and reads the written json back
+fn run_to_json(input: &Path, name: &str) -> Value {
+    let out = temp_path(name);
+    let output = run(&["-i", input.to_str().unwrap(), "-o", out.to_str().unwrap()]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let written = std::fs::read_to_string(&out).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    serde_json::from_str(&written).unwrap()
+}
+
+/// Description: test concatenating a computed sum with a given matrix
+#[test]
+fn concat_sum_with_matrix() {
+    let written = run_to_json(&data_file("concat_sum.json"), "concat_sum.json");
+
+    assert_eq!(written["result"]["rows"], 3);
+    assert_eq!(written["result"]["cols"], 2);
+    assert_eq!(
+        written["result"]["data"],
+        serde_json::json!([11, 22, 33, 44, 5, 6])
+    );
+}
+
+/// Description: test multiplying by the transpose of the left operand
+#[test]
+fn transpose_multiply() {
+    let written = run_to_json(
+        &data_file("transpose_multiply.json"),
+        "transpose_multiply.json",
+    );
+
+    assert_eq!(written["operator"], "MultiplyTransposed");
+    assert_eq!(written["result"]["rows"], 2);
+    assert_eq!(written["result"]["cols"], 1);
+    assert_eq!(written["result"]["data"], serde_json::json!([11, 14]));
+}
+
+/// Description: test printing a matrix given inline, and rejecting a bad one
+#[test]
+fn inline_matrix() {
+    let output = run(&["--inline-matrix", "2x2:1,2,3,4"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Matrix 2x2:\n     1      2 \n     3      4 \n"
+    );
+
+    let output = run(&["--inline-matrix", "2x2:1,2,3"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("expected 4 elements, found 3"));
+}
+
+/// Description: test --report writes timing and shapes beside the output file
+#[test]
+fn report_beside_output() {
+    let out = temp_path("report.json");
+    let report = temp_path("report.report.json");
+    let input = data_file("concat_sum.json");
+    let output = run(&[
+        "-i",
+        input.to_str().unwrap(),
+        "-o",
+        out.to_str().unwrap(),
+        "--report",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    std::fs::remove_file(&report).unwrap();
+
+    assert_eq!(
+        written["operator"],
+        serde_json::json!({"Concat": {"axis": "Vertical"}})
+    );
+    assert_eq!(written["left_shape"], serde_json::json!([2, 2]));
+    assert_eq!(written["right_shape"], serde_json::json!([1, 2]));
+    assert_eq!(written["result_shape"], serde_json::json!([3, 2]));
+    assert!(written["elapsed_ms"].as_f64().unwrap() >= 0.0);
+}
+
+/// Description: test assembling an Operation from a file for each matrix and the operator
+#[test]
+fn operation_from_parts() {
+    let left = data_file("parts_left.json");
+    let right = data_file("parts_right.json");
+    let op_file = data_file("parts_op.json");
+
+    let out = temp_path("parts_op.json");
+    let output = run(&[
+        "--left",
+        left.to_str().unwrap(),
+        "--op-file",
+        op_file.to_str().unwrap(),
+        "--right",
+        right.to_str().unwrap(),
+        "-o",
+        out.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert_eq!(written["operator"], "Multiply");
+    assert_eq!(
+        written["result"]["data"],
+        serde_json::json!([19, 22, 43, 50])
+    );
+
+    let out = temp_path("parts_symbol.json");
+    let output = run(&[
+        "--left",
+        left.to_str().unwrap(),
+        "--op",
+        "+",
+        "--right",
+        right.to_str().unwrap(),
+        "-o",
+        out.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert_eq!(written["operator"], "Add");
+    assert_eq!(written["result"]["data"], serde_json::json!([6, 8, 10, 12]));
+}
+
+/// Description: test an operand can be given inline instead of in a file, and that a report
+///              then needs a file to go beside
+#[test]
+fn operation_from_inline_parts() {
+    let right = data_file("parts_right.json");
+    let output = run(&[
+        "--left",
+        "2x2:1,0,0,1",
+        "--op",
+        "*",
+        "--right",
+        right.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .ends_with("Equals\n\n     5      6 \n     7      8 \n\n"));
+
+    let output = run(&[
+        "--left", "1x2:1,2", "--op", "+", "--right", "1x2:3,4", "--report",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--report needs an output file"));
+}
+
+/// Description: test validating a directory reports each failing file and a summary
+#[test]
+fn validate_directory() {
+    let dir = data_file("validate");
+    let output = run(&["--validate-dir", dir.to_str().unwrap()]);
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", stdout);
+    assert!(lines[0].contains("mismatched.json: expected a 2x2 matrix, found 2x1"));
+    assert_eq!(lines[1], "1 passed, 1 failed");
+}
+
+/// Description: runs the binary on concat_sum.json with MATRIX_THREADS set, writing a report
+///              beside the given output file
+fn run_with_threads_var(env: &str, out: &Path, args: &[&str]) -> Output {
+    let input = data_file("concat_sum.json");
+    Command::new(env!("CARGO_BIN_EXE_sample_code"))
+        .args(["-i", input.to_str().unwrap(), "-o", out.to_str().unwrap()])
+        .arg("--report")
+        .args(args)
+        .env("MATRIX_THREADS", env)
+        .output()
+        .expect("unable to run the binary")
+}
+
+/// Description: test MATRIX_THREADS sets the thread count unless --threads overrides it
+#[test]
+fn threads_from_environment() {
+    let out = temp_path("threads.json");
+    let report = temp_path("threads.report.json");
+    for (env, args, expected) in &[("1", &[][..], 1), ("1", &["--threads", "3"][..], 3)] {
+        let output = run_with_threads_var(env, &out, args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+        std::fs::remove_file(&out).unwrap();
+        std::fs::remove_file(&report).unwrap();
+        assert_eq!(written["threads"], *expected);
+    }
+
+    let output = run_with_threads_var("0", &out, &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("MATRIX_THREADS"));
+}
+
+/// Description: test an operation on incompatible matrices fails with the operator and shapes
+#[test]
+fn mismatched_operation_error() {
+    let input = data_file("validate").join("mismatched.json");
+    let output = run(&["-i", input.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "unable to apply Add to a 2x2 and a 2x1 matrix: expected a 2x2 matrix, found 2x1\n"
+    );
+}
+
+/// Description: test --max-elements rejects a matrix larger than the limit
+#[test]
+fn max_elements_rejects_large_matrix() {
+    let input = data_file("concat_sum.json");
+    let output = run(&["-i", input.to_str().unwrap(), "--max-elements", "3"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("data has more than the limit of 3 elements"),
+        "{}",
+        stderr
+    );
+
+    let output = run(&["-i", input.to_str().unwrap(), "--max-elements", "4"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Description: test one run writing the result in two more formats beside the main output
+#[test]
+fn also_write_other_formats() {
+    let out = temp_path("also.json");
+    let csv = temp_path("also.csv");
+    let txt = temp_path("also.txt");
+    let input = data_file("concat_sum.json");
+    let output = run(&[
+        "-i",
+        input.to_str().unwrap(),
+        "-o",
+        out.to_str().unwrap(),
+        "--also-write",
+        csv.to_str().unwrap(),
+        "--also-write",
+        txt.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    let csv_text = std::fs::read_to_string(&csv).unwrap();
+    let txt_text = std::fs::read_to_string(&txt).unwrap();
+    for path in &[&out, &csv, &txt] {
+        std::fs::remove_file(path).unwrap();
+    }
+
+    assert_eq!(
+        written["result"]["data"],
+        serde_json::json!([11, 22, 33, 44, 5, 6])
+    );
+    assert_eq!(csv_text, "11,22\n33,44\n5,6\n");
+    assert!(txt_text.contains("Stacked on top of"), "{}", txt_text);
+}
+
+/// Description: test a normalize operation divides the result by its Frobenius norm
+#[test]
+fn normalize_by_frobenius_norm() {
+    let out = temp_path("normalize.json");
+    let input = data_file("normalize.json");
+    let output = run(&["-i", input.to_str().unwrap(), "-o", out.to_str().unwrap()]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert_eq!(written["by"], "Frobenius");
+    assert_eq!(
+        written["result"]["data"],
+        serde_json::json!([0.6, 0.0, 0.0, 0.8])
+    );
+
+    let output = run(&["-i", input.to_str().unwrap()]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Divided by its Frobenius norm"),
+        "{}",
+        stdout
+    );
+}
+
+/// Description: test printing each operand of an operation file without evaluating it
+#[test]
+fn extract_each_operand() {
+    let input = data_file("concat_sum.json");
+    let extract = |side: &str| {
+        let output = run(&["-i", input.to_str().unwrap(), "--extract", side]);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let left = extract("left");
+    assert!(left.contains("Added to"), "{}", left);
+    assert!(!left.contains("Equals"), "{}", left);
+    assert!(!left.contains("Stacked on top of"), "{}", left);
+
+    let right = extract("right");
+    assert!(right.starts_with("Matrix 1x2:\n"), "{}", right);
+    assert!(right.contains("5"), "{}", right);
+}
+
+/// Description: test extracting a nested operation whose operands can't be combined shows
+///              why instead of crashing
+#[test]
+fn extract_invalid_nested_operand() {
+    let input = temp_path("invalid_nested.json");
+    std::fs::write(
+        &input,
+        r#"{
+            "left_operand": {
+                "left_operand": {
+                    "left_operand": {"rows": 2, "cols": 1, "data": [1, 2]},
+                    "operator": "Add",
+                    "right_operand": {"rows": 1, "cols": 2, "data": [1, 2]}
+                },
+                "operator": "Multiply",
+                "right_operand": {"rows": 1, "cols": 1, "data": [1]}
+            },
+            "operator": "Add",
+            "right_operand": {"rows": 1, "cols": 1, "data": [1]}
+        }"#,
+    )
+    .unwrap();
+    let output = run(&["-i", input.to_str().unwrap(), "--extract", "left"]);
+    std::fs::remove_file(&input).unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("unable to apply Add"), "{}", stdout);
+}
+
+/// Description: test a unary transpose operation is evaluated, written and displayed
+#[test]
+fn unary_transpose() {
+    let input = data_file("transpose.json");
+    let written = run_to_json(&input, "transpose.json");
+
+    assert_eq!(written["operator"], "Transpose");
+    assert_eq!(written["result"]["rows"], 3);
+    assert_eq!(written["result"]["cols"], 2);
+    // the result is written row by row, so it reads right without its layout
+    assert_eq!(written["result"]["layout"], Value::Null);
+    assert_eq!(
+        written["result"]["data"],
+        serde_json::json!([1, 4, 2, 5, 3, 6])
+    );
+
+    let output = run(&["-i", input.to_str().unwrap()]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Transposed\n\nEquals"), "{}", stdout);
+}
+
+/// Description: test a unary operation is written in the format of each output file's extension,
+///              and that --report and results csv can't hold are refused
+#[test]
+fn unary_output_formats() {
+    let input = data_file("transpose.json");
+    let csv = temp_path("unary.csv");
+    let txt = temp_path("unary.txt");
+    let output = run(&[
+        "-i",
+        input.to_str().unwrap(),
+        "-o",
+        csv.to_str().unwrap(),
+        "--also-write",
+        txt.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(std::fs::read_to_string(&csv).unwrap(), "1,4\n2,5\n3,6\n");
+    assert!(std::fs::read_to_string(&txt)
+        .unwrap()
+        .contains("Transposed\n\nEquals"));
+    std::fs::remove_file(&csv).unwrap();
+    std::fs::remove_file(&txt).unwrap();
+
+    let determinant = data_file("determinant.json");
+    let output = run(&[
+        "-i",
+        determinant.to_str().unwrap(),
+        "-o",
+        csv.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("integer matrix result"));
+    let _ = std::fs::remove_file(&csv);
+
+    let output = run(&["-i", input.to_str().unwrap(), "--report"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--report"));
+}
+
+/// Description: test a determinant operation writes its result as a single number
+#[test]
+fn unary_determinant() {
+    let input = data_file("determinant.json");
+    let written = run_to_json(&input, "determinant.json");
+
+    assert_eq!(written["operator"], "Determinant");
+    assert_eq!(written["result"], 6);
+
+    let output = run(&["-i", input.to_str().unwrap()]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Its determinant\n\nEquals\n\n6\n"),
+        "{}",
+        stdout
+    );
+}
+
+/// Description: test an inverse operation writes fractional elements, and a singular
+///              matrix is reported instead of inverted
+#[test]
+fn unary_inverse() {
+    let written = run_to_json(&data_file("inverse.json"), "inverse.json");
+
+    assert_eq!(written["operator"], "Inverse");
+    let found: Vec<f64> = serde_json::from_value(written["result"]["data"].clone()).unwrap();
+    for (found, expected) in found.iter().zip(&[0.6, -0.7, -0.2, 0.4]) {
+        assert!((found - expected).abs() < 1e-12, "{:?}", found);
+    }
+
+    let input = data_file("inverse_singular.json");
+    let output = run(&["-i", input.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("singular"), "{}", stderr);
+}
+
+/// Description: test matrices with fractional elements are operated on in floating point
+#[test]
+fn float_operation_from_parts() {
+    let left = data_file("parts_left.json");
+    let right = data_file("parts_float.json");
+
+    let out = temp_path("parts_float.json");
+    let output = run(&[
+        "--left",
+        left.to_str().unwrap(),
+        "--op",
+        "*",
+        "--right",
+        right.to_str().unwrap(),
+        "-o",
+        out.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert_eq!(written["operator"], "Multiply");
+    assert_eq!(
+        written["result"]["data"],
+        serde_json::json!([0.5, 1.0, 1.5, 2.0])
+    );
+}
+
+/// Description: test an input file with whole numbers too large for an i32 is done on i64s,
+///              with a report of its shapes
+#[test]
+fn wide_operation_from_file() {
+    let input = data_file("wide_add.json");
+    let out = temp_path("wide_add.json");
+    let report = temp_path("wide_add.report.json");
+    let output = run(&[
+        "-i",
+        input.to_str().unwrap(),
+        "-o",
+        out.to_str().unwrap(),
+        "--report",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    let reported: Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    std::fs::remove_file(&report).unwrap();
+    assert_eq!(
+        written["result"]["data"],
+        serde_json::json!([3000000001_i64, 3])
+    );
+    assert_eq!(reported["result_shape"], serde_json::json!([1, 2]));
+}
+
+/// Description: test an Operation with fractional elements is done in floating point,
+/// and printed to the given precision
+#[test]
+fn float_operation() {
+    let input = data_file("float_operation.json");
+    let output = run(&["-i", input.to_str().unwrap(), "--precision", "2"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.ends_with("Equals\n\n  1.75   1.50 \n  3.50   3.00 \n\n"),
+        "{}",
+        stdout
+    );
+
+    let out = temp_path("float_operation.json");
+    let output = run(&["-i", input.to_str().unwrap(), "-o", out.to_str().unwrap()]);
+    assert!(output.status.success());
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert_eq!(
+        written["result"]["data"],
+        serde_json::json!([1.75, 1.5, 3.5, 3.0])
+    );
+}
+
+/// Description: test a matrix with fewer elements than its shape holds is rejected
+#[test]
+fn short_data_is_rejected() {
+    let input = data_file("short_data.json");
+    let output = run(&["-i", input.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("expected 4 elements, found 1"),
+        "{}",
+        stderr
+    );
+}